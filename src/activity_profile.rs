@@ -0,0 +1,296 @@
+use crate::errors::*;
+use crate::helpers::write_rows_to_file;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeDelta, Timelike, Weekday};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+static MIN_QUIET_STRETCH: usize = 3; // Days in a row for an hour, or weeks in a row for a weekday, a busy bucket has to be empty
+static MIN_BUSY_FRACTION: f64 = 0.5; // Fraction of days or weeks a bucket needs records on to be normally busy
+
+static WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActivityBucket {
+    HourOfDay(u32),
+    DayOfWeek(Weekday),
+}
+
+impl ActivityBucket {
+    fn get_bucket_type(&self) -> &'static str {
+        match self {
+            ActivityBucket::HourOfDay(_) => "Hour of Day",
+            ActivityBucket::DayOfWeek(_) => "Day of Week",
+        }
+    }
+}
+
+impl fmt::Display for ActivityBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActivityBucket::HourOfDay(hour) => write!(f, "{:02}:00", hour),
+            ActivityBucket::DayOfWeek(weekday) => write!(f, "{}", weekday),
+        }
+    }
+}
+
+/// A stretch of days (or weeks for a weekday) where a normally busy bucket had no records at all
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuietActivityPeriod {
+    pub bucket: ActivityBucket,
+    pub first_quiet_day: NaiveDate,
+    pub last_quiet_day: NaiveDate,
+    pub times_in_a_row: usize,
+}
+
+/// Record counts for every hour of every day the log covers, which the hour of day and day of week histograms are built from
+#[derive(Debug, Default)]
+pub struct ActivityProfile {
+    record_counts_by_day_and_hour: BTreeMap<NaiveDate, [usize; 24]>,
+}
+
+impl ActivityProfile {
+    pub fn add_timestamp(&mut self, timestamp: NaiveDateTime) {
+        self.record_counts_by_day_and_hour
+            .entry(timestamp.date())
+            .or_insert([0; 24])[timestamp.hour() as usize] += 1;
+    }
+
+    pub fn get_hour_of_day_counts(&self) -> [usize; 24] {
+        let mut hour_of_day_counts = [0; 24];
+        for counts in self.record_counts_by_day_and_hour.values() {
+            for (hour, count) in counts.iter().enumerate() {
+                hour_of_day_counts[hour] += count;
+            }
+        }
+        hour_of_day_counts
+    }
+
+    /// Monday first
+    pub fn get_day_of_week_counts(&self) -> [usize; 7] {
+        let mut day_of_week_counts = [0; 7];
+        for (day, counts) in self.record_counts_by_day_and_hour.iter() {
+            day_of_week_counts[day.weekday().num_days_from_monday() as usize] +=
+                counts.iter().sum::<usize>();
+        }
+        day_of_week_counts
+    }
+
+    /// Every day between the first and last, including days with no records. The first and last days are
+    /// left out since the log only covers part of them
+    fn get_full_days(&self) -> Vec<(NaiveDate, [usize; 24])> {
+        let (Some(first_day), Some(last_day)) = (
+            self.record_counts_by_day_and_hour.keys().next(),
+            self.record_counts_by_day_and_hour.keys().next_back(),
+        ) else {
+            return Vec::new();
+        };
+        let mut full_days = Vec::new();
+        let mut day = *first_day + TimeDelta::days(1);
+        while day < *last_day {
+            full_days.push((
+                day,
+                self.record_counts_by_day_and_hour
+                    .get(&day)
+                    .copied()
+                    .unwrap_or([0; 24]),
+            ));
+            day += TimeDelta::days(1);
+        }
+        full_days
+    }
+
+    pub fn get_quiet_periods(&self) -> Vec<QuietActivityPeriod> {
+        let full_days = self.get_full_days();
+        let mut quiet_periods = Vec::new();
+        for hour in 0..24 {
+            let days_with_records: Vec<(NaiveDate, bool)> = full_days
+                .iter()
+                .map(|(day, counts)| (*day, counts[hour] > 0))
+                .collect();
+            quiet_periods.extend(find_quiet_periods(
+                ActivityBucket::HourOfDay(hour as u32),
+                &days_with_records,
+            ));
+        }
+        for weekday in WEEKDAYS {
+            let days_with_records: Vec<(NaiveDate, bool)> = full_days
+                .iter()
+                .filter(|(day, _)| day.weekday() == weekday)
+                .map(|(day, counts)| (*day, counts.iter().any(|count| *count > 0)))
+                .collect();
+            quiet_periods.extend(find_quiet_periods(
+                ActivityBucket::DayOfWeek(weekday),
+                &days_with_records,
+            ));
+        }
+        quiet_periods
+    }
+
+    pub fn write_to_files(
+        &self,
+        output_dir: &Path,
+        file_name: &str,
+        quiet_periods: &[QuietActivityPeriod],
+    ) -> Result<()> {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for (hour, count) in self.get_hour_of_day_counts().iter().enumerate() {
+            let bucket = ActivityBucket::HourOfDay(hour as u32);
+            rows.push(vec![
+                bucket.get_bucket_type().to_string(),
+                bucket.to_string(),
+                count.to_string(),
+            ]);
+        }
+        for (weekday, count) in WEEKDAYS.iter().zip(self.get_day_of_week_counts()) {
+            let bucket = ActivityBucket::DayOfWeek(*weekday);
+            rows.push(vec![
+                bucket.get_bucket_type().to_string(),
+                bucket.to_string(),
+                count.to_string(),
+            ]);
+        }
+        write_rows_to_file(
+            &output_dir.join(format!(
+                "ActivityProfile/{}_ACTIVITY_PROFILE.csv",
+                file_name
+            )),
+            &["Bucket Type", "Bucket", "Record Count"],
+            rows,
+        )?;
+
+        if quiet_periods.is_empty() {
+            return Ok(());
+        }
+        write_rows_to_file(
+            &output_dir.join(format!("ActivityProfile/{}_QUIET_PERIODS.csv", file_name)),
+            &[
+                "Bucket Type",
+                "Bucket",
+                "First Quiet Day",
+                "Last Quiet Day",
+                "Times in a Row With No Records",
+            ],
+            quiet_periods.iter().map(|quiet_period| {
+                vec![
+                    quiet_period.bucket.get_bucket_type().to_string(),
+                    quiet_period.bucket.to_string(),
+                    quiet_period.first_quiet_day.to_string(),
+                    quiet_period.last_quiet_day.to_string(),
+                    quiet_period.times_in_a_row.to_string(),
+                ]
+            }),
+        )
+    }
+}
+
+/// A bucket with records on at least half of its days is normally busy, so a long enough run of empty days stands out
+fn find_quiet_periods(
+    bucket: ActivityBucket,
+    days_with_records: &[(NaiveDate, bool)],
+) -> Vec<QuietActivityPeriod> {
+    let num_busy_days = days_with_records
+        .iter()
+        .filter(|(_, has_records)| *has_records)
+        .count();
+    if days_with_records.is_empty()
+        || (num_busy_days as f64) < days_with_records.len() as f64 * MIN_BUSY_FRACTION
+    {
+        return Vec::new();
+    }
+    days_with_records
+        .split(|(_, has_records)| *has_records)
+        .filter(|quiet_days| quiet_days.len() >= MIN_QUIET_STRETCH)
+        .map(|quiet_days| QuietActivityPeriod {
+            bucket,
+            first_quiet_day: quiet_days[0].0,
+            last_quiet_day: quiet_days[quiet_days.len() - 1].0,
+            times_in_a_row: quiet_days.len(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod activity_profile_tests {
+    use super::*;
+    use crate::test_helpers::dt;
+
+    /// One record every hour from the first day to the last, skipping the hours given
+    fn build_profile(
+        first_day: &str,
+        num_days: i64,
+        skip: impl Fn(NaiveDateTime) -> bool,
+    ) -> ActivityProfile {
+        let mut profile = ActivityProfile::default();
+        let start = dt(&format!("{} 00:30:00", first_day));
+        for hour in 0..num_days * 24 {
+            let timestamp = start + TimeDelta::hours(hour);
+            if !skip(timestamp) {
+                profile.add_timestamp(timestamp);
+            }
+        }
+        profile
+    }
+
+    #[test]
+    fn counts_by_hour_and_weekday() {
+        // 2024-05-06 is a Monday
+        let profile = build_profile("2024-05-06", 7, |timestamp| timestamp.hour() == 2);
+        let hour_of_day_counts = profile.get_hour_of_day_counts();
+        assert_eq!(hour_of_day_counts[2], 0);
+        assert_eq!(hour_of_day_counts[3], 7);
+        assert_eq!(profile.get_day_of_week_counts(), [23; 7]);
+    }
+
+    #[test]
+    fn hour_that_is_never_busy_is_not_quiet() {
+        let profile = build_profile("2024-05-06", 14, |timestamp| timestamp.hour() == 2);
+        assert!(profile.get_quiet_periods().is_empty());
+    }
+
+    #[test]
+    fn finds_busy_hour_that_went_quiet() {
+        let profile = build_profile("2024-05-06", 14, |timestamp| {
+            timestamp.hour() == 2
+                && timestamp.date() >= NaiveDate::from_ymd_opt(2024, 5, 9).unwrap()
+                && timestamp.date() <= NaiveDate::from_ymd_opt(2024, 5, 12).unwrap()
+        });
+        assert_eq!(
+            profile.get_quiet_periods(),
+            vec![QuietActivityPeriod {
+                bucket: ActivityBucket::HourOfDay(2),
+                first_quiet_day: NaiveDate::from_ymd_opt(2024, 5, 9).unwrap(),
+                last_quiet_day: NaiveDate::from_ymd_opt(2024, 5, 12).unwrap(),
+                times_in_a_row: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_busy_weekday_that_went_quiet() {
+        // Nothing on Wednesdays for three weeks in the middle of eight weeks
+        let profile = build_profile("2024-05-06", 56, |timestamp| {
+            timestamp.weekday() == Weekday::Wed
+                && timestamp.date() >= NaiveDate::from_ymd_opt(2024, 5, 15).unwrap()
+                && timestamp.date() <= NaiveDate::from_ymd_opt(2024, 5, 29).unwrap()
+        });
+        let quiet_periods = profile.get_quiet_periods();
+        assert_eq!(
+            quiet_periods,
+            vec![QuietActivityPeriod {
+                bucket: ActivityBucket::DayOfWeek(Weekday::Wed),
+                first_quiet_day: NaiveDate::from_ymd_opt(2024, 5, 15).unwrap(),
+                last_quiet_day: NaiveDate::from_ymd_opt(2024, 5, 29).unwrap(),
+                times_in_a_row: 3,
+            }]
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use crate::alerts::{AlertKind, AlertLevel, AlertLevelThresholds, AlertRules};
+use crate::basic_objects::{LogFile, LogType};
+use crate::errors::*;
+use crate::helpers::record_output_file_written;
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+static SCOPEABLE_LOG_TYPES: [LogType; 6] = [
+    LogType::Csv,
+    LogType::Json,
+    LogType::Unstructured,
+    LogType::Evtx,
+    LogType::Syslog,
+    LogType::W3c,
+];
+
+/// Overrides that only apply to files matching the glob and log type. Either can be left out to match every file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScopedAlertOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_glob: Option<String>, // Matched against the full path of the file, or the path inside the archive for archive members
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_type: Option<String>, // Same names as the Log Type column, ex CSV or JSONL
+    #[serde(default)]
+    pub thresholds: BTreeMap<AlertKind, AlertLevelThresholds>,
+    #[serde(default)]
+    pub disabled: Vec<AlertKind>,
+}
+
+impl ScopedAlertOverride {
+    fn applies_to_file(&self, log_file: &LogFile) -> bool {
+        if let Some(log_type) = &self.log_type
+            && !log_type.eq_ignore_ascii_case(&log_file.log_type.to_string())
+        {
+            return false;
+        }
+        match self.file_glob.as_deref().map(Pattern::new) {
+            None => true,
+            Some(Ok(pattern)) => {
+                pattern.matches_path(&log_file.file_path)
+                    || pattern.matches_path(&log_file.get_inner_path())
+            }
+            Some(Err(_)) => false, // Globs are checked when the config is loaded
+        }
+    }
+}
+
+/// Alert thresholds and disabled alert kinds loaded from --alert-config. Overrides are applied in order after the
+/// top level ones, so a later override wins when more than one matches a file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertConfig {
+    #[serde(default)]
+    pub thresholds: BTreeMap<AlertKind, AlertLevelThresholds>,
+    #[serde(default)]
+    pub disabled: Vec<AlertKind>,
+    #[serde(default)]
+    pub overrides: Vec<ScopedAlertOverride>,
+}
+
+impl AlertConfig {
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            LavaError::new(
+                format!("Unable to read alert config file because of {e}"),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        let alert_config: AlertConfig = serde_yaml::from_str(&content).map_err(|e| {
+            LavaError::new(
+                format!("Failed to parse alert config file because of {e}"),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        alert_config.validate()?;
+        Ok(alert_config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for scoped_override in self.overrides.iter() {
+            if let Some(file_glob) = &scoped_override.file_glob {
+                Pattern::new(file_glob).map_err(|e| {
+                    LavaError::new(
+                        format!("Invalid file glob {file_glob} in alert config because of {e}"),
+                        LavaErrorLevel::Critical,
+                    )
+                })?;
+            }
+            if let Some(log_type) = &scoped_override.log_type
+                && !SCOPEABLE_LOG_TYPES.iter().any(|known_log_type| {
+                    log_type.eq_ignore_ascii_case(&known_log_type.to_string())
+                })
+            {
+                return Err(LavaError::new(
+                    format!(
+                        "Unknown log type {log_type} in alert config, expected one of {}",
+                        SCOPEABLE_LOG_TYPES
+                            .iter()
+                            .map(|known_log_type| known_log_type.to_string())
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ),
+                    LavaErrorLevel::Critical,
+                ));
+            }
+        }
+        // The event count alert checks divisibility, so a threshold of zero would divide by zero
+        let event_count_thresholds = std::iter::once(&self.thresholds).chain(
+            self.overrides
+                .iter()
+                .map(|scoped_override| &scoped_override.thresholds),
+        );
+        for thresholds in event_count_thresholds {
+            if let Some(level_thresholds) = thresholds.get(&AlertKind::SusEventCount)
+                && [AlertLevel::High, AlertLevel::Medium, AlertLevel::Low]
+                    .iter()
+                    .any(|alert_level| level_thresholds.get(*alert_level) == Some(0))
+            {
+                return Err(LavaError::new(
+                    "SusEventCount thresholds in the alert config must be greater than 0",
+                    LavaErrorLevel::Critical,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rules for files that no override is scoped to, which is also what multipart mode uses for the combined file
+    pub fn get_default_rules(&self) -> AlertRules {
+        let mut alert_rules = AlertRules::default();
+        alert_rules.apply(&self.thresholds, &self.disabled);
+        for scoped_override in self.overrides.iter() {
+            if scoped_override.file_glob.is_none() && scoped_override.log_type.is_none() {
+                alert_rules.apply(&scoped_override.thresholds, &scoped_override.disabled);
+            }
+        }
+        alert_rules
+    }
+
+    pub fn get_rules_for_file(&self, log_file: &LogFile) -> AlertRules {
+        let mut alert_rules = AlertRules::default();
+        alert_rules.apply(&self.thresholds, &self.disabled);
+        for scoped_override in self.overrides.iter() {
+            if scoped_override.applies_to_file(log_file) {
+                alert_rules.apply(&scoped_override.thresholds, &scoped_override.disabled);
+            }
+        }
+        alert_rules
+    }
+
+    /// The config with every top level threshold filled in, defaults included, so the output shows exactly what was alerted on
+    pub fn get_effective_config(&self) -> AlertConfig {
+        let mut alert_rules = AlertRules::default();
+        alert_rules.apply(&self.thresholds, &self.disabled);
+        let thresholds = AlertKind::ALL
+            .iter()
+            .map(|alert_kind| {
+                (
+                    alert_kind.clone(),
+                    AlertLevelThresholds {
+                        high: Some(alert_rules.get_threshold(AlertLevel::High, alert_kind)),
+                        medium: Some(alert_rules.get_threshold(AlertLevel::Medium, alert_kind)),
+                        low: Some(alert_rules.get_threshold(AlertLevel::Low, alert_kind)),
+                    },
+                )
+            })
+            .collect();
+        AlertConfig {
+            thresholds,
+            disabled: alert_rules.disabled.into_iter().collect(),
+            overrides: self.overrides.clone(),
+        }
+    }
+
+    pub fn write_effective_config_to_file(&self, output_dir: &Path) -> Result<()> {
+        let content = serde_yaml::to_string(&self.get_effective_config()).map_err(|e| {
+            LavaError::new(
+                format!("Unable to serialize alert config because of {e}"),
+                LavaErrorLevel::Medium,
+            )
+        })?;
+        let output_file = output_dir.join("LAVA_Alert_Config.yml");
+        record_output_file_written(&output_file);
+        fs::write(output_file, content).map_err(|e| {
+            LavaError::new(
+                format!("Unable to write alert config to output directory because of {e}"),
+                LavaErrorLevel::Medium,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod alert_config_tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn log_file(log_type: LogType, path: &str) -> LogFile {
+        LogFile {
+            log_type,
+            file_path: PathBuf::from(path),
+            type_detection: None,
+            archive_member: None,
+        }
+    }
+
+    fn parse(yaml: &str) -> AlertConfig {
+        let alert_config: AlertConfig = serde_yaml::from_str(yaml).unwrap();
+        alert_config.validate().unwrap();
+        alert_config
+    }
+
+    #[test]
+    fn top_level_thresholds_override_only_the_levels_given() {
+        let alert_config = parse(
+            "
+thresholds:
+  DupeEvents:
+    medium: 50
+disabled:
+  - SusEventCount
+",
+        );
+        let alert_rules = alert_config.get_rules_for_file(&log_file(LogType::Csv, "/logs/a.csv"));
+        assert_eq!(
+            alert_rules.get_threshold(AlertLevel::Medium, &AlertKind::DupeEvents),
+            50
+        );
+        assert_eq!(
+            alert_rules.get_threshold(AlertLevel::High, &AlertKind::DupeEvents),
+            100
+        );
+        assert!(alert_rules.is_disabled(&AlertKind::SusEventCount));
+        assert!(!alert_rules.is_disabled(&AlertKind::DupeEvents));
+    }
+
+    #[test]
+    fn overrides_are_scoped_by_glob_and_log_type() {
+        let alert_config = parse(
+            "
+overrides:
+  - file_glob: '*firewall*'
+    thresholds:
+      VolumeDrop:
+        low: 3
+  - log_type: jsonl
+    disabled:
+      - QuietActivityPeriods
+",
+        );
+        let firewall_rules =
+            alert_config.get_rules_for_file(&log_file(LogType::Csv, "/logs/firewall/fw1.csv"));
+        let json_rules =
+            alert_config.get_rules_for_file(&log_file(LogType::Json, "/logs/app.jsonl"));
+
+        assert_eq!(
+            firewall_rules.get_threshold(AlertLevel::Low, &AlertKind::VolumeDrop),
+            3
+        );
+        assert!(!firewall_rules.is_disabled(&AlertKind::QuietActivityPeriods));
+        assert_eq!(
+            json_rules.get_threshold(AlertLevel::Low, &AlertKind::VolumeDrop),
+            0
+        );
+        assert!(json_rules.is_disabled(&AlertKind::QuietActivityPeriods));
+        assert_eq!(alert_config.get_default_rules(), AlertRules::default());
+    }
+
+    #[test]
+    fn rejects_unknown_alert_kinds_and_bad_values() {
+        assert!(serde_yaml::from_str::<AlertConfig>("disabled: [NotAnAlert]").is_err());
+        assert!(
+            serde_yaml::from_str::<AlertConfig>("thresholds: {DupeEvents: {critical: 1}}").is_err()
+        );
+        let unknown_log_type: AlertConfig =
+            serde_yaml::from_str("overrides: [{log_type: parquet}]").unwrap();
+        assert!(unknown_log_type.validate().is_err());
+        let zero_event_count: AlertConfig =
+            serde_yaml::from_str("thresholds: {SusEventCount: {low: 0}}").unwrap();
+        assert!(zero_event_count.validate().is_err());
+    }
+
+    #[test]
+    fn effective_config_fills_in_every_default() {
+        let alert_config = parse("thresholds: {DupeEvents: {low: 5}}");
+        let effective_config = alert_config.get_effective_config();
+        assert_eq!(effective_config.thresholds.len(), AlertKind::ALL.len());
+        assert_eq!(
+            effective_config.thresholds[&AlertKind::DupeEvents],
+            AlertLevelThresholds {
+                high: Some(100),
+                medium: Some(10),
+                low: Some(5),
+            }
+        );
+        let round_tripped: AlertConfig =
+            serde_yaml::from_str(&serde_yaml::to_string(&effective_config).unwrap()).unwrap();
+        assert_eq!(round_tripped, effective_config);
+    }
+}
@@ -1,6 +1,10 @@
-use crate::basic_objects::PossibleAlertValues;
+use crate::basic_objects::{PossibleAlertValues, TimeGapAlertMethod, WELFORD_TIME_SIGNIFIGANCE};
+use crate::helpers::get_seconds_string_from_time_delta;
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize)]
 pub enum AlertLevel {
     High,
     Medium,
@@ -10,34 +14,144 @@ pub enum AlertLevel {
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum AlertType {
     SusTimeGap,
+    SusTimeGapPercentile,
     SusEventCount,
     DupeEvents,
     RedactionEvents,
     JsonError,
     MultipartOverlap(String, String),
+    EvtxRecordIdGap,
+    EvtxLogCleared,
+    OutOfOrderRecords(usize, TimeDelta), // Count and the worst displacement
+    MissedCadenceIntervals(usize, TimeDelta), // Count and the expected cadence
+    QuietActivityPeriods,
+    VolumeDrop(usize), // Number of windows
+    VolumeSpike(usize),
+    CrossFileDupes(String, String, usize), // Both files and how many records they share
 }
 
 impl AlertType {
     pub fn kind(&self) -> AlertKind {
         match self {
             AlertType::SusTimeGap => AlertKind::SusTimeGap,
+            AlertType::SusTimeGapPercentile => AlertKind::SusTimeGapPercentile,
             AlertType::SusEventCount => AlertKind::SusEventCount,
             AlertType::DupeEvents => AlertKind::DupeEvents,
             AlertType::RedactionEvents => AlertKind::RedactionEvents,
             AlertType::JsonError => AlertKind::JsonError,
             AlertType::MultipartOverlap(_, _) => AlertKind::MultipartOverlap,
+            AlertType::EvtxRecordIdGap => AlertKind::EvtxRecordIdGap,
+            AlertType::EvtxLogCleared => AlertKind::EvtxLogCleared,
+            AlertType::OutOfOrderRecords(_, _) => AlertKind::OutOfOrderRecords,
+            AlertType::MissedCadenceIntervals(_, _) => AlertKind::MissedCadenceIntervals,
+            AlertType::QuietActivityPeriods => AlertKind::QuietActivityPeriods,
+            AlertType::VolumeDrop(_) => AlertKind::VolumeDrop,
+            AlertType::VolumeSpike(_) => AlertKind::VolumeSpike,
+            AlertType::CrossFileDupes(_, _, _) => AlertKind::CrossFileDupes,
         }
     }
 }
 
-#[derive(Hash, Eq, PartialEq, Clone, Debug)]
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Clone, Debug, Serialize, Deserialize)]
 pub enum AlertKind {
     SusTimeGap,
+    SusTimeGapPercentile,
     SusEventCount,
     DupeEvents,
     RedactionEvents,
     JsonError,
     MultipartOverlap,
+    EvtxRecordIdGap,
+    EvtxLogCleared,
+    OutOfOrderRecords,
+    MissedCadenceIntervals,
+    QuietActivityPeriods,
+    VolumeDrop,
+    VolumeSpike,
+    CrossFileDupes,
+}
+
+impl AlertKind {
+    pub const ALL: [AlertKind; 15] = [
+        AlertKind::SusTimeGap,
+        AlertKind::SusTimeGapPercentile,
+        AlertKind::SusEventCount,
+        AlertKind::DupeEvents,
+        AlertKind::RedactionEvents,
+        AlertKind::JsonError,
+        AlertKind::MultipartOverlap,
+        AlertKind::EvtxRecordIdGap,
+        AlertKind::EvtxLogCleared,
+        AlertKind::OutOfOrderRecords,
+        AlertKind::MissedCadenceIntervals,
+        AlertKind::QuietActivityPeriods,
+        AlertKind::VolumeDrop,
+        AlertKind::VolumeSpike,
+        AlertKind::CrossFileDupes,
+    ];
+}
+
+/// Thresholds to use in place of the defaults for one alert kind. Levels that are left out keep their default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertLevelThresholds {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub medium: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low: Option<usize>,
+}
+
+impl AlertLevelThresholds {
+    pub fn get(&self, alert_level: AlertLevel) -> Option<usize> {
+        match alert_level {
+            AlertLevel::High => self.high,
+            AlertLevel::Medium => self.medium,
+            AlertLevel::Low => self.low,
+        }
+    }
+
+    /// Levels set in the other thresholds replace the ones set here
+    pub fn apply(&mut self, other: &AlertLevelThresholds) {
+        self.high = other.high.or(self.high);
+        self.medium = other.medium.or(self.medium);
+        self.low = other.low.or(self.low);
+    }
+}
+
+/// Thresholds and disabled alert kinds in effect for one file. Anything not overridden uses the built in defaults
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AlertRules {
+    pub thresholds: BTreeMap<AlertKind, AlertLevelThresholds>,
+    pub disabled: BTreeSet<AlertKind>,
+}
+
+impl AlertRules {
+    pub fn get_threshold(&self, alert_level: AlertLevel, alert_kind: &AlertKind) -> usize {
+        self.thresholds
+            .get(alert_kind)
+            .and_then(|thresholds| thresholds.get(alert_level))
+            .unwrap_or_else(|| get_alert_threshold_value(alert_level, alert_kind.clone()))
+    }
+
+    pub fn is_disabled(&self, alert_kind: &AlertKind) -> bool {
+        self.disabled.contains(alert_kind)
+    }
+
+    pub fn apply(
+        &mut self,
+        thresholds: &BTreeMap<AlertKind, AlertLevelThresholds>,
+        disabled: &[AlertKind],
+    ) {
+        for (alert_kind, level_thresholds) in thresholds {
+            self.thresholds
+                .entry(alert_kind.clone())
+                .or_default()
+                .apply(level_thresholds);
+        }
+        self.disabled.extend(disabled.iter().cloned());
+    }
 }
 
 fn get_alert_threshold_value(alert_level: AlertLevel, alert_type: AlertKind) -> usize {
@@ -47,6 +161,11 @@ fn get_alert_threshold_value(alert_level: AlertLevel, alert_type: AlertKind) ->
             AlertLevel::Medium => 30,
             AlertLevel::Low => 10,
         },
+        AlertKind::SusTimeGapPercentile => match alert_level {
+            AlertLevel::High => 1000,
+            AlertLevel::Medium => 100,
+            AlertLevel::Low => 10,
+        },
         AlertKind::SusEventCount => match alert_level {
             AlertLevel::High => 10000,
             AlertLevel::Medium => 1000,
@@ -72,64 +191,165 @@ fn get_alert_threshold_value(alert_level: AlertLevel, alert_type: AlertKind) ->
             AlertLevel::Medium => 0,
             AlertLevel::Low => 0,
         },
+        AlertKind::EvtxRecordIdGap => match alert_level {
+            AlertLevel::High => 0,
+            AlertLevel::Medium => 0,
+            AlertLevel::Low => 0,
+        },
+        AlertKind::EvtxLogCleared => match alert_level {
+            AlertLevel::High => 0,
+            AlertLevel::Medium => 0,
+            AlertLevel::Low => 0,
+        },
+        AlertKind::OutOfOrderRecords => match alert_level {
+            AlertLevel::High => 100,
+            AlertLevel::Medium => 10,
+            AlertLevel::Low => 0,
+        },
+        AlertKind::MissedCadenceIntervals => match alert_level {
+            AlertLevel::High => 100,
+            AlertLevel::Medium => 10,
+            AlertLevel::Low => 0,
+        },
+        AlertKind::QuietActivityPeriods => match alert_level {
+            AlertLevel::High => 10,
+            AlertLevel::Medium => 5,
+            AlertLevel::Low => 0,
+        },
+        AlertKind::VolumeDrop => match alert_level {
+            AlertLevel::High => 10,
+            AlertLevel::Medium => 5,
+            AlertLevel::Low => 0,
+        },
+        AlertKind::VolumeSpike => match alert_level {
+            AlertLevel::High => 10,
+            AlertLevel::Medium => 5,
+            AlertLevel::Low => 0,
+        },
+        AlertKind::CrossFileDupes => match alert_level {
+            AlertLevel::High => 100,
+            AlertLevel::Medium => 10,
+            AlertLevel::Low => 0,
+        },
     }
 }
 
 pub fn get_message_for_alert_comfy_table(
-    alert_level: AlertLevel,
     alert_type: AlertKind,
+    threshold: usize,
     number_of_files: usize,
 ) -> String {
     match alert_type {
         AlertKind::SusTimeGap => format!(
             "{} files had a largest time gap greater than {} standard deviations above the average time gap",
-            number_of_files,
-            get_alert_threshold_value(alert_level, alert_type)
+            number_of_files, threshold
+        ),
+        AlertKind::SusTimeGapPercentile => format!(
+            "{} files had a largest time gap greater than {} times the 99th percentile time gap",
+            number_of_files, threshold
         ),
         AlertKind::SusEventCount => format!(
             "{} files had an event count divisible by {}",
-            number_of_files,
-            get_alert_threshold_value(alert_level, alert_type)
+            number_of_files, threshold
         ),
         AlertKind::DupeEvents => format!(
             "{} files had greater than {} duplicate records",
-            number_of_files,
-            get_alert_threshold_value(alert_level, alert_type)
+            number_of_files, threshold
         ),
         AlertKind::RedactionEvents => format!(
             "{} files had greater than {} records with potential redactions",
-            number_of_files,
-            get_alert_threshold_value(alert_level, alert_type)
+            number_of_files, threshold
         ),
         AlertKind::JsonError => format!("{} files had JSON syntax errors", number_of_files),
         AlertKind::MultipartOverlap => {
             format!("{} files contain overlapping time ranges", number_of_files)
         }
+        AlertKind::EvtxRecordIdGap => format!(
+            "{} EVTX files had gaps in the event record ID sequence",
+            number_of_files
+        ),
+        AlertKind::EvtxLogCleared => format!(
+            "{} EVTX files contained a log cleared event (1102)",
+            number_of_files
+        ),
+        AlertKind::OutOfOrderRecords => format!(
+            "{} files had greater than {} records out of order",
+            number_of_files, threshold
+        ),
+        AlertKind::MissedCadenceIntervals => format!(
+            "{} periodic files had greater than {} missed intervals",
+            number_of_files, threshold
+        ),
+        AlertKind::QuietActivityPeriods => format!(
+            "{} files had greater than {} normally busy hours or weekdays go quiet for days in a row",
+            number_of_files, threshold
+        ),
+        AlertKind::VolumeDrop => format!(
+            "{} files had greater than {} windows with far fewer records than usual",
+            number_of_files, threshold
+        ),
+        AlertKind::VolumeSpike => format!(
+            "{} files had greater than {} windows with far more records than usual",
+            number_of_files, threshold
+        ),
+        AlertKind::CrossFileDupes => format!(
+            "{} files shared greater than {} records with an earlier input file",
+            number_of_files, threshold
+        ),
     }
 }
 
-pub fn get_message_for_alert_output_file(alert_level: AlertLevel, alert_type: AlertType) -> String {
-    match alert_type {
+pub fn get_message_for_alert_output_file(alert: &Alert) -> String {
+    let threshold = alert.threshold;
+    match alert.alert_type.clone() {
         AlertType::SusTimeGap => format!(
             "Largest time gap greater than {} standard deviations above the average time gap",
-            get_alert_threshold_value(alert_level, alert_type.kind())
+            threshold
         ),
-        AlertType::SusEventCount => format!(
-            "Event count was divisible by {}",
-            get_alert_threshold_value(alert_level, alert_type.kind())
-        ),
-        AlertType::DupeEvents => format!(
-            "Greater than {} duplicate records",
-            get_alert_threshold_value(alert_level, alert_type.kind())
+        AlertType::SusTimeGapPercentile => format!(
+            "Largest time gap greater than {} times the 99th percentile time gap",
+            threshold
         ),
+        AlertType::SusEventCount => format!("Event count was divisible by {}", threshold),
+        AlertType::DupeEvents => format!("Greater than {} duplicate records", threshold),
         AlertType::RedactionEvents => format!(
             "Greater than {} records with potential redactions",
-            get_alert_threshold_value(alert_level, alert_type.kind())
+            threshold
         ),
         AlertType::JsonError => {
             format!("File had json syntax errors that may interfere with parsing in other tools")
         }
         AlertType::MultipartOverlap(file1, file2) => format!("{} overlaps with {}", file1, file2),
+        AlertType::EvtxRecordIdGap => {
+            "Event record IDs were not sequential, records may have been removed".to_string()
+        }
+        AlertType::EvtxLogCleared => "File contained a log cleared event (1102)".to_string(),
+        AlertType::OutOfOrderRecords(count, largest_displacement) => format!(
+            "{} records were out of order, the worst by {} seconds",
+            count,
+            get_seconds_string_from_time_delta(largest_displacement)
+        ),
+        AlertType::MissedCadenceIntervals(count, cadence) => format!(
+            "{} intervals were missed in a log expected to be written every {} seconds",
+            count,
+            get_seconds_string_from_time_delta(cadence)
+        ),
+        AlertType::QuietActivityPeriods => format!(
+            "Greater than {} times a normally busy hour of the day or day of the week had no records for days in a row",
+            threshold
+        ),
+        AlertType::VolumeDrop(num_windows) => format!(
+            "{} windows had far fewer records than the rolling baseline",
+            num_windows
+        ),
+        AlertType::VolumeSpike(num_windows) => format!(
+            "{} windows had far more records than the rolling baseline",
+            num_windows
+        ),
+        AlertType::CrossFileDupes(file1, file2, num_shared_records) => format!(
+            "{} shares {} records with {}",
+            file2, num_shared_records, file1
+        ),
     }
 }
 
@@ -138,14 +358,17 @@ pub struct Alert {
     pub alert_level: AlertLevel,
     pub alert_type: AlertType,
     pub original_filepath: Option<String>,
+    pub threshold: usize, // The threshold for this level that was crossed, which can be changed by the alert config
 }
 
 impl Alert {
     pub fn new(alert_level: AlertLevel, alert_type: AlertType) -> Self {
+        let threshold = get_alert_threshold_value(alert_level, alert_type.kind());
         Self {
             alert_level,
             alert_type,
             original_filepath: None,
+            threshold,
         }
     }
     pub fn add_original_file_path(&mut self, file_path: String) {
@@ -155,12 +378,15 @@ impl Alert {
 
 pub fn generate_alerts(things_to_alert_on: PossibleAlertValues) -> Vec<Alert> {
     let mut alerts: Vec<Alert> = Vec::new();
+    let alert_rules = &things_to_alert_on.alert_rules;
 
     //Num records alerts
     if things_to_alert_on.num_records > 0 {
-        if let Some(level) =
-            get_alert_level_remainder_zero(things_to_alert_on.num_records, AlertType::SusEventCount)
-        {
+        if let Some(level) = get_alert_level_remainder_zero(
+            things_to_alert_on.num_records,
+            AlertType::SusEventCount,
+            alert_rules,
+        ) {
             alerts.push(Alert::new(level, AlertType::SusEventCount));
         };
     };
@@ -169,6 +395,7 @@ pub fn generate_alerts(things_to_alert_on: PossibleAlertValues) -> Vec<Alert> {
     if let Some(level) = get_alert_level_greater_than_threshold_values(
         things_to_alert_on.num_dupes,
         AlertType::DupeEvents,
+        alert_rules,
     ) {
         alerts.push(Alert::new(level, AlertType::DupeEvents));
     };
@@ -177,21 +404,28 @@ pub fn generate_alerts(things_to_alert_on: PossibleAlertValues) -> Vec<Alert> {
     if let Some(level) = get_alert_level_greater_than_threshold_values(
         things_to_alert_on.num_redactions,
         AlertType::RedactionEvents,
+        alert_rules,
     ) {
         alerts.push(Alert::new(level, AlertType::RedactionEvents));
     };
 
     //Time gap alerts
     if let Some(time_gap) = things_to_alert_on.largest_time_gap {
-        let standard_deviations_above_the_mean = ((time_gap.get_time_duration_number() as f64
-            - things_to_alert_on.mean)
-            / things_to_alert_on.std)
-            .floor() as usize;
-        if let Some(level) = get_alert_level_greater_than_threshold_values(
-            standard_deviations_above_the_mean,
-            AlertType::SusTimeGap,
-        ) {
-            alerts.push(Alert::new(level, AlertType::SusTimeGap));
+        let time_gap_alert_baseline = TimeGapAlertBaseline {
+            method: things_to_alert_on.time_gap_alert_method,
+            mean: things_to_alert_on.mean,
+            std: things_to_alert_on.std,
+            percentile_99: things_to_alert_on.percentile_99_time_gap,
+        };
+        if let Some(alert_value) =
+            time_gap_alert_baseline.get_alert_value(time_gap.get_time_duration_number() as f64)
+            && let Some(level) = get_alert_level_greater_than_threshold_values(
+                alert_value.floor() as usize,
+                time_gap_alert_baseline.get_alert_type(),
+                alert_rules,
+            )
+        {
+            alerts.push(Alert::new(level, time_gap_alert_baseline.get_alert_type()));
         };
     };
 
@@ -204,30 +438,191 @@ pub fn generate_alerts(things_to_alert_on: PossibleAlertValues) -> Vec<Alert> {
         alerts.push(Alert::new(AlertLevel::High, AlertType::JsonError));
     }
 
+    // EVTX record ID gaps
+    if let Some(level) = get_alert_level_greater_than_threshold_values(
+        things_to_alert_on.num_evtx_record_id_gaps,
+        AlertType::EvtxRecordIdGap,
+        alert_rules,
+    ) {
+        alerts.push(Alert::new(level, AlertType::EvtxRecordIdGap));
+    };
+
+    // EVTX log cleared events
+    if let Some(level) = get_alert_level_greater_than_threshold_values(
+        things_to_alert_on.num_evtx_log_cleared_events,
+        AlertType::EvtxLogCleared,
+        alert_rules,
+    ) {
+        alerts.push(Alert::new(level, AlertType::EvtxLogCleared));
+    };
+
+    // Records tolerated out of order
+    if let Some(largest_displacement) = things_to_alert_on.largest_out_of_order_displacement {
+        let alert_type = AlertType::OutOfOrderRecords(
+            things_to_alert_on.num_out_of_order_records,
+            largest_displacement,
+        );
+        if let Some(level) = get_alert_level_greater_than_threshold_values(
+            things_to_alert_on.num_out_of_order_records,
+            alert_type.clone(),
+            alert_rules,
+        ) {
+            alerts.push(Alert::new(level, alert_type));
+        };
+    };
+
+    // Missed intervals in periodic logs
+    if let Some(expected_cadence) = things_to_alert_on.expected_cadence {
+        let alert_type = AlertType::MissedCadenceIntervals(
+            things_to_alert_on.num_missed_cadence_intervals,
+            expected_cadence,
+        );
+        if let Some(level) = get_alert_level_greater_than_threshold_values(
+            things_to_alert_on.num_missed_cadence_intervals,
+            alert_type.clone(),
+            alert_rules,
+        ) {
+            alerts.push(Alert::new(level, alert_type));
+        };
+    };
+
+    // Normally busy hours or weekdays with no records
+    if let Some(level) = get_alert_level_greater_than_threshold_values(
+        things_to_alert_on.num_quiet_activity_periods,
+        AlertType::QuietActivityPeriods,
+        alert_rules,
+    ) {
+        alerts.push(Alert::new(level, AlertType::QuietActivityPeriods));
+    };
+
+    // Record rate far from the rolling baseline
+    if let Some(level) = get_alert_level_greater_than_threshold_values(
+        things_to_alert_on.num_volume_drops,
+        AlertType::VolumeDrop(things_to_alert_on.num_volume_drops),
+        alert_rules,
+    ) {
+        alerts.push(Alert::new(
+            level,
+            AlertType::VolumeDrop(things_to_alert_on.num_volume_drops),
+        ));
+    };
+    if let Some(level) = get_alert_level_greater_than_threshold_values(
+        things_to_alert_on.num_volume_spikes,
+        AlertType::VolumeSpike(things_to_alert_on.num_volume_spikes),
+        alert_rules,
+    ) {
+        alerts.push(Alert::new(
+            level,
+            AlertType::VolumeSpike(things_to_alert_on.num_volume_spikes),
+        ));
+    };
+
+    alerts.retain(|alert| !alert_rules.is_disabled(&alert.alert_type.kind()));
+    for alert in alerts.iter_mut() {
+        alert.threshold = alert_rules.get_threshold(alert.alert_level, &alert.alert_type.kind());
+    }
+
     alerts
 }
 
+/// Cross file duplicates are only known once every file is processed, so the alert is made separately from the rest
+pub fn get_cross_file_dupes_alert(
+    file1: String,
+    file2: String,
+    num_shared_records: usize,
+    alert_rules: &AlertRules,
+) -> Option<Alert> {
+    let alert_type = AlertType::CrossFileDupes(file1, file2, num_shared_records);
+    if alert_rules.is_disabled(&alert_type.kind()) {
+        return None;
+    }
+    let level = get_alert_level_greater_than_threshold_values(
+        num_shared_records,
+        alert_type.clone(),
+        alert_rules,
+    )?;
+    let mut alert = Alert::new(level, alert_type);
+    alert.threshold = alert_rules.get_threshold(level, &alert.alert_type.kind());
+    Some(alert)
+}
+
 fn get_alert_level_greater_than_threshold_values(
     value: usize,
     alert_type: AlertType,
+    alert_rules: &AlertRules,
 ) -> Option<AlertLevel> {
-    if value > get_alert_threshold_value(AlertLevel::High, alert_type.kind().clone()) {
+    if value > alert_rules.get_threshold(AlertLevel::High, &alert_type.kind()) {
         Some(AlertLevel::High)
-    } else if value > get_alert_threshold_value(AlertLevel::Medium, alert_type.kind().clone()) {
+    } else if value > alert_rules.get_threshold(AlertLevel::Medium, &alert_type.kind()) {
         Some(AlertLevel::Medium)
-    } else if value > get_alert_threshold_value(AlertLevel::Low, alert_type.kind().clone()) {
+    } else if value > alert_rules.get_threshold(AlertLevel::Low, &alert_type.kind()) {
         Some(AlertLevel::Low)
     } else {
         None
     }
 }
 
-fn get_alert_level_remainder_zero(n: usize, alert_type: AlertType) -> Option<AlertLevel> {
-    if n % get_alert_threshold_value(AlertLevel::High, alert_type.kind().clone()) == 0 {
+/// What time gaps are measured against for the time gap alert, so the alert and the count of gaps above its threshold agree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeGapAlertBaseline {
+    pub method: TimeGapAlertMethod,
+    pub mean: f64,
+    pub std: f64,
+    pub percentile_99: Option<f64>,
+}
+
+impl TimeGapAlertBaseline {
+    pub fn get_alert_type(&self) -> AlertType {
+        match self.method {
+            TimeGapAlertMethod::StandardDeviations => AlertType::SusTimeGap,
+            TimeGapAlertMethod::Percentile => AlertType::SusTimeGapPercentile,
+        }
+    }
+
+    /// Standard deviations above the mean, or multiples of the 99th percentile, for a gap in the Welford units
+    pub fn get_alert_value(&self, time_duration_number: f64) -> Option<f64> {
+        match self.method {
+            TimeGapAlertMethod::StandardDeviations if self.std != 0.0 => {
+                Some((time_duration_number - self.mean) / self.std)
+            }
+            TimeGapAlertMethod::StandardDeviations => None,
+            // Logs written many times a second can have a 99th percentile of zero, so compare to at least one second
+            TimeGapAlertMethod::Percentile => self.percentile_99.map(|percentile_99| {
+                time_duration_number
+                    / percentile_99.max(WELFORD_TIME_SIGNIFIGANCE.get_units_per_second())
+            }),
+        }
+    }
+
+    /// The shortest gap, in the Welford units, that would raise the time gap alert
+    pub fn get_minimum_time_gap_above_threshold(&self, alert_rules: &AlertRules) -> Option<f64> {
+        let alert_type = self.get_alert_type();
+        // The alert value is floored before it's compared, so it has to reach the next whole number
+        let minimum_alert_value =
+            (alert_rules.get_threshold(AlertLevel::Low, &alert_type.kind()) + 1) as f64;
+        match self.method {
+            TimeGapAlertMethod::StandardDeviations if self.std != 0.0 => {
+                Some(self.mean + minimum_alert_value * self.std)
+            }
+            TimeGapAlertMethod::StandardDeviations => None,
+            TimeGapAlertMethod::Percentile => self.percentile_99.map(|percentile_99| {
+                minimum_alert_value
+                    * percentile_99.max(WELFORD_TIME_SIGNIFIGANCE.get_units_per_second())
+            }),
+        }
+    }
+}
+
+fn get_alert_level_remainder_zero(
+    n: usize,
+    alert_type: AlertType,
+    alert_rules: &AlertRules,
+) -> Option<AlertLevel> {
+    if n % alert_rules.get_threshold(AlertLevel::High, &alert_type.kind()) == 0 {
         Some(AlertLevel::High)
-    } else if n % get_alert_threshold_value(AlertLevel::Medium, alert_type.kind().clone()) == 0 {
+    } else if n % alert_rules.get_threshold(AlertLevel::Medium, &alert_type.kind()) == 0 {
         Some(AlertLevel::Medium)
-    } else if n % get_alert_threshold_value(AlertLevel::Low, alert_type.kind().clone()) == 0 {
+    } else if n % alert_rules.get_threshold(AlertLevel::Low, &alert_type.kind()) == 0 {
         Some(AlertLevel::Low)
     } else {
         None
@@ -257,19 +652,35 @@ pub mod tests {
     #[test]
     fn test_get_alert_level_greater_than_threshold_values() {
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(200, AlertType::SusTimeGap),
+            get_alert_level_greater_than_threshold_values(
+                200,
+                AlertType::SusTimeGap,
+                &AlertRules::default()
+            ),
             Some(AlertLevel::High)
         );
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(60, AlertType::SusTimeGap),
+            get_alert_level_greater_than_threshold_values(
+                60,
+                AlertType::SusTimeGap,
+                &AlertRules::default()
+            ),
             Some(AlertLevel::Medium)
         );
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(15, AlertType::SusTimeGap),
+            get_alert_level_greater_than_threshold_values(
+                15,
+                AlertType::SusTimeGap,
+                &AlertRules::default()
+            ),
             Some(AlertLevel::Low)
         );
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(2, AlertType::SusTimeGap),
+            get_alert_level_greater_than_threshold_values(
+                2,
+                AlertType::SusTimeGap,
+                &AlertRules::default()
+            ),
             None
         );
     }
@@ -277,19 +688,19 @@ pub mod tests {
     #[test]
     fn test_get_alert_level_remainder_zero() {
         assert_eq!(
-            get_alert_level_remainder_zero(20000, AlertType::SusEventCount),
+            get_alert_level_remainder_zero(20000, AlertType::SusEventCount, &AlertRules::default()),
             Some(AlertLevel::High)
         );
         assert_eq!(
-            get_alert_level_remainder_zero(2000, AlertType::SusEventCount),
+            get_alert_level_remainder_zero(2000, AlertType::SusEventCount, &AlertRules::default()),
             Some(AlertLevel::Medium)
         );
         assert_eq!(
-            get_alert_level_remainder_zero(300, AlertType::SusEventCount),
+            get_alert_level_remainder_zero(300, AlertType::SusEventCount, &AlertRules::default()),
             Some(AlertLevel::Low)
         );
         assert_eq!(
-            get_alert_level_remainder_zero(70, AlertType::SusEventCount),
+            get_alert_level_remainder_zero(70, AlertType::SusEventCount, &AlertRules::default()),
             None
         );
     }
@@ -297,19 +708,35 @@ pub mod tests {
     #[test]
     fn test_get_alert_level_of_num_dupes() {
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(101, AlertType::DupeEvents),
+            get_alert_level_greater_than_threshold_values(
+                101,
+                AlertType::DupeEvents,
+                &AlertRules::default()
+            ),
             Some(AlertLevel::High)
         );
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(50, AlertType::DupeEvents),
+            get_alert_level_greater_than_threshold_values(
+                50,
+                AlertType::DupeEvents,
+                &AlertRules::default()
+            ),
             Some(AlertLevel::Medium)
         );
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(1, AlertType::DupeEvents),
+            get_alert_level_greater_than_threshold_values(
+                1,
+                AlertType::DupeEvents,
+                &AlertRules::default()
+            ),
             Some(AlertLevel::Low)
         );
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(0, AlertType::DupeEvents),
+            get_alert_level_greater_than_threshold_values(
+                0,
+                AlertType::DupeEvents,
+                &AlertRules::default()
+            ),
             None
         );
     }
@@ -317,19 +744,35 @@ pub mod tests {
     #[test]
     fn test_get_alert_level_of_num_redactions() {
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(150, AlertType::RedactionEvents),
+            get_alert_level_greater_than_threshold_values(
+                150,
+                AlertType::RedactionEvents,
+                &AlertRules::default()
+            ),
             Some(AlertLevel::High)
         );
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(20, AlertType::RedactionEvents),
+            get_alert_level_greater_than_threshold_values(
+                20,
+                AlertType::RedactionEvents,
+                &AlertRules::default()
+            ),
             Some(AlertLevel::Medium)
         );
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(1, AlertType::RedactionEvents),
+            get_alert_level_greater_than_threshold_values(
+                1,
+                AlertType::RedactionEvents,
+                &AlertRules::default()
+            ),
             Some(AlertLevel::Low)
         );
         assert_eq!(
-            get_alert_level_greater_than_threshold_values(0, AlertType::RedactionEvents),
+            get_alert_level_greater_than_threshold_values(
+                0,
+                AlertType::RedactionEvents,
+                &AlertRules::default()
+            ),
             None
         );
     }
@@ -341,9 +784,9 @@ pub mod tests {
             num_dupes: 20,
             num_redactions: 2,
             largest_time_gap: Some(dummy_timegap(60)),
-            errors: Vec::new(),
             mean: 10.0,
             std: 4.0,
+            ..Default::default()
         };
 
         let alerts = generate_alerts(input);
@@ -369,17 +812,131 @@ pub mod tests {
     #[test]
     fn test_generate_alerts_none_triggered() {
         let input = PossibleAlertValues {
-            num_records: 0,
-            num_dupes: 0,
-            num_redactions: 0,
             largest_time_gap: Some(dummy_timegap(60)),
-            errors: Vec::new(),
             mean: 50000.0,
             std: 10000.0,
+            ..Default::default()
         };
 
         let alerts = generate_alerts(input);
 
         assert_eq!(alerts.len(), 0);
     }
+
+    #[test]
+    fn test_generate_alerts_evtx() {
+        let input = PossibleAlertValues {
+            num_evtx_record_id_gaps: 1,
+            num_evtx_log_cleared_events: 1,
+            ..Default::default()
+        };
+
+        let alerts = generate_alerts(input);
+
+        assert_eq!(
+            alerts,
+            vec![
+                Alert::new(AlertLevel::High, AlertType::EvtxRecordIdGap),
+                Alert::new(AlertLevel::High, AlertType::EvtxLogCleared),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_alerts_out_of_order() {
+        let input = PossibleAlertValues {
+            num_out_of_order_records: 12,
+            largest_out_of_order_displacement: Some(TimeDelta::milliseconds(4500)),
+            ..Default::default()
+        };
+
+        let alerts = generate_alerts(input);
+
+        assert_eq!(
+            alerts,
+            vec![Alert::new(
+                AlertLevel::Medium,
+                AlertType::OutOfOrderRecords(12, TimeDelta::milliseconds(4500))
+            )]
+        );
+        assert_eq!(
+            get_message_for_alert_output_file(&alerts[0]),
+            "12 records were out of order, the worst by 4.500 seconds"
+        );
+    }
+
+    #[test]
+    fn test_generate_alerts_missed_cadence_intervals() {
+        let input = PossibleAlertValues {
+            num_missed_cadence_intervals: 3,
+            expected_cadence: Some(TimeDelta::seconds(60)),
+            ..Default::default()
+        };
+
+        let alerts = generate_alerts(input);
+
+        assert_eq!(
+            alerts,
+            vec![Alert::new(
+                AlertLevel::Low,
+                AlertType::MissedCadenceIntervals(3, TimeDelta::seconds(60))
+            )]
+        );
+        assert_eq!(
+            get_message_for_alert_output_file(&alerts[0]),
+            "3 intervals were missed in a log expected to be written every 60.000 seconds"
+        );
+    }
+
+    #[test]
+    fn test_generate_alerts_percentile_time_gap() {
+        let input = PossibleAlertValues {
+            percentile_99_time_gap: Some(60_000.0),
+            time_gap_alert_method: TimeGapAlertMethod::Percentile,
+            largest_time_gap: Some(dummy_timegap(6 * 60 * 60)), // 360 times the 99th percentile
+            mean: 1_000_000.0, // A mean and std this skewed would not alert on their own
+            std: 1_000_000.0,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            generate_alerts(input),
+            vec![Alert::new(
+                AlertLevel::Medium,
+                AlertType::SusTimeGapPercentile
+            )]
+        );
+    }
+
+    #[test]
+    fn test_generate_alerts_uses_configured_thresholds_and_disabled_kinds() {
+        let mut alert_rules = AlertRules::default();
+        alert_rules.apply(
+            &BTreeMap::from([(
+                AlertKind::DupeEvents,
+                AlertLevelThresholds {
+                    high: Some(1000),
+                    medium: Some(500),
+                    low: None,
+                },
+            )]),
+            &[AlertKind::SusEventCount],
+        );
+        let input = PossibleAlertValues {
+            num_records: 1000,
+            num_dupes: 200,
+            alert_rules,
+            ..Default::default()
+        };
+
+        let alerts = generate_alerts(input);
+
+        assert_eq!(alerts.len(), 1); // The event count of 1000 would alert by default
+        assert_eq!(alerts[0].alert_type, AlertType::DupeEvents);
+        assert_eq!(alerts[0].alert_level, AlertLevel::Low);
+        assert_eq!(
+            get_message_for_alert_output_file(&alerts[0]),
+            "Greater than 0 duplicate records"
+        );
+    }
 }
@@ -0,0 +1,519 @@
+use crate::basic_objects::*;
+use crate::errors::*;
+use crate::file_type_sniffer::SNIFF_BUFFER_SIZE;
+use crate::log_file_readers::{
+    get_compression_type_from_magic_bytes, open_file_with_decompression,
+    wrap_reader_with_decompression,
+};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::thread;
+use zip::ZipArchive;
+
+static TAR_MAGIC_OFFSET: usize = 257;
+static MEMBER_CHUNK_SIZE: usize = 64 * 1024;
+static MEMBER_CHUNKS_IN_FLIGHT: usize = 4;
+
+pub fn get_archive_type(file_path: &Path) -> Result<Option<ArchiveType>> {
+    let file = File::open(file_path).map_err(|e| {
+        LavaError::new(
+            format!("Unable to open file because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    let mut magic_bytes = Vec::new();
+    file.take(4).read_to_end(&mut magic_bytes).map_err(|e| {
+        LavaError::new(
+            format!("Unable to read file because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    if magic_bytes.starts_with(b"PK\x03\x04") || magic_bytes.starts_with(b"PK\x05\x06") {
+        return Ok(Some(ArchiveType::Zip));
+    }
+
+    // Tar has no compression of its own, so the ustar magic is checked after decompressing to also catch .tar.gz and friends
+    let mut tar_header = Vec::new();
+    open_file_with_decompression(file_path)?
+        .take(512)
+        .read_to_end(&mut tar_header)
+        .map_err(|e| {
+            LavaError::new(
+                format!("Unable to read file because of {e}"),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+    if tar_header.len() == 512 && tar_header[TAR_MAGIC_OFFSET..].starts_with(b"ustar") {
+        return Ok(Some(ArchiveType::Tar));
+    }
+    Ok(None)
+}
+
+/// Lists the files in the archive along with the start of each one, decompressed, so the log types can be sniffed in this one
+/// pass over the archive. Reopening every member just to sniff it would decompress a tar from the start for each member
+pub fn list_archive_members(
+    archive_path: &Path,
+    archive_type: ArchiveType,
+) -> Result<Vec<(ArchiveMember, Result<Vec<u8>>)>> {
+    let mut members: Vec<(ArchiveMember, Result<Vec<u8>>)> = Vec::new();
+    match archive_type {
+        ArchiveType::Zip => {
+            let mut archive = open_zip_archive(archive_path)?;
+            for index in 0..archive.len() {
+                let zip_file = archive.by_index(index).map_err(|e| {
+                    LavaError::new(
+                        format!("Unable to read zip entry {} because of {e}", index),
+                        LavaErrorLevel::Critical,
+                    )
+                })?;
+                if zip_file.is_file() {
+                    let member = ArchiveMember {
+                        archive_type,
+                        inner_path: zip_file.name().to_string(),
+                        size: zip_file.size(),
+                        modified_time: zip_file.last_modified().and_then(get_zip_modified_time),
+                    };
+                    members.push((member, read_start_of_member(zip_file)));
+                }
+            }
+        }
+        ArchiveType::Tar => {
+            let mut archive = tar::Archive::new(open_file_with_decompression(archive_path)?);
+            let entries = archive.entries().map_err(|e| {
+                LavaError::new(
+                    format!("Unable to read tar entries because of {e}"),
+                    LavaErrorLevel::Critical,
+                )
+            })?;
+            for entry_result in entries {
+                let entry = entry_result.map_err(|e| {
+                    LavaError::new(
+                        format!("Unable to read tar entry because of {e}"),
+                        LavaErrorLevel::Critical,
+                    )
+                })?;
+                if entry.header().entry_type().is_file() {
+                    let member = ArchiveMember {
+                        archive_type,
+                        inner_path: get_tar_entry_path(&entry)?,
+                        size: entry.size(),
+                        modified_time: get_tar_modified_time(&entry),
+                    };
+                    members.push((member, read_start_of_member(entry)));
+                }
+            }
+        }
+    }
+    Ok(members)
+}
+
+/// At most SNIFF_BUFFER_SIZE bytes from the start of the member, after any decompression of the member itself
+fn read_start_of_member<R: Read>(member_reader: R) -> Result<Vec<u8>> {
+    let mut reader = BufReader::new(member_reader);
+    let magic_bytes = reader.fill_buf().map_err(|e| {
+        LavaError::new(
+            format!("Unable to read archive member because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    let compression_type = get_compression_type_from_magic_bytes(magic_bytes);
+    let mut buffer = Vec::with_capacity(SNIFF_BUFFER_SIZE);
+    wrap_reader_with_decompression(reader, compression_type)?
+        .take(SNIFF_BUFFER_SIZE as u64)
+        .read_to_end(&mut buffer)
+        .map_err(|e| {
+            LavaError::new(
+                format!("Unable to read archive member because of {e}"),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+    Ok(buffer)
+}
+
+/// Streams a single member out of the archive. Nothing is ever extracted to disk, and the member is never held in memory as a
+/// whole. The zip and tar readers borrow the archive, so the member is read on its own thread and handed over in chunks
+pub fn open_archive_member(
+    archive_path: &Path,
+    member: &ArchiveMember,
+) -> Result<ArchiveMemberReader> {
+    let (sender, receiver) = sync_channel(MEMBER_CHUNKS_IN_FLIGHT);
+    let archive_path = archive_path.to_path_buf();
+    let member = member.clone();
+    thread::spawn(move || {
+        if let Err(e) = with_archive_member(&archive_path, &member, |reader| {
+            send_member_in_chunks(reader, &sender)
+        }) {
+            let _ = sender.send(Err(e));
+        }
+    });
+
+    // Wait for the first chunk so a missing member or a broken archive is reported when opening rather than on the first read
+    let first_chunk = match receiver.recv() {
+        Ok(Ok(chunk)) => chunk,
+        Ok(Err(e)) => return Err(e),
+        Err(_) => Vec::new(), // Empty member
+    };
+    Ok(ArchiveMemberReader {
+        receiver,
+        chunk: first_chunk,
+        position: 0,
+    })
+}
+
+/// Reading stops as soon as the ArchiveMemberReader is dropped, since sending the next chunk fails
+fn send_member_in_chunks(
+    reader: &mut dyn Read,
+    sender: &SyncSender<Result<Vec<u8>>>,
+) -> Result<()> {
+    loop {
+        let mut chunk = Vec::with_capacity(MEMBER_CHUNK_SIZE);
+        reader
+            .take(MEMBER_CHUNK_SIZE as u64)
+            .read_to_end(&mut chunk)
+            .map_err(|e| {
+                LavaError::new(
+                    format!("Unable to read archive member because of {e}"),
+                    LavaErrorLevel::Critical,
+                )
+            })?;
+        if chunk.is_empty() || sender.send(Ok(chunk)).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+/// Finds the member in the archive and hands a reader over it to the callback. The size in the archive's header isn't used
+/// for anything, since a corrupt or crafted archive can claim any size it likes
+fn with_archive_member(
+    archive_path: &Path,
+    member: &ArchiveMember,
+    callback: impl FnOnce(&mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    match member.archive_type {
+        ArchiveType::Zip => {
+            let mut archive = open_zip_archive(archive_path)?;
+            let mut zip_file = archive.by_name(&member.inner_path).map_err(|e| {
+                LavaError::new(
+                    format!(
+                        "Unable to find {} in zip archive because of {e}",
+                        member.inner_path
+                    ),
+                    LavaErrorLevel::Critical,
+                )
+            })?;
+            callback(&mut zip_file)
+        }
+        ArchiveType::Tar => {
+            let mut archive = tar::Archive::new(open_file_with_decompression(archive_path)?);
+            let entries = archive.entries().map_err(|e| {
+                LavaError::new(
+                    format!("Unable to read tar entries because of {e}"),
+                    LavaErrorLevel::Critical,
+                )
+            })?;
+            for entry_result in entries {
+                let mut entry = entry_result.map_err(|e| {
+                    LavaError::new(
+                        format!("Unable to read tar entry because of {e}"),
+                        LavaErrorLevel::Critical,
+                    )
+                })?;
+                if get_tar_entry_path(&entry)? == member.inner_path {
+                    return callback(&mut entry);
+                }
+            }
+            Err(LavaError::new(
+                format!("Unable to find {} in tar archive", member.inner_path),
+                LavaErrorLevel::Critical,
+            ))
+        }
+    }
+}
+
+/// The receiving end of a member being streamed out of its archive by open_archive_member
+pub struct ArchiveMemberReader {
+    receiver: Receiver<Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    position: usize,
+}
+
+impl Read for ArchiveMemberReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.position = 0;
+                }
+                Ok(Err(e)) => return Err(io::Error::other(e.reason)),
+                Err(_) => return Ok(0), // The thread is done, so the whole member has been read
+            }
+        }
+        let num_bytes = buf.len().min(self.chunk.len() - self.position);
+        buf[..num_bytes].copy_from_slice(&self.chunk[self.position..self.position + num_bytes]);
+        self.position += num_bytes;
+        Ok(num_bytes)
+    }
+}
+
+/// Zip stores modified times as local time with no timezone, so they are used as is
+fn get_zip_modified_time(modified_time: zip::DateTime) -> Option<NaiveDateTime> {
+    NaiveDate::from_ymd_opt(
+        modified_time.year().into(),
+        modified_time.month().into(),
+        modified_time.day().into(),
+    )?
+    .and_hms_opt(
+        modified_time.hour().into(),
+        modified_time.minute().into(),
+        modified_time.second().into(),
+    )
+}
+
+/// Tar stores seconds since the epoch, and 0 is what tools write when they don't set it
+fn get_tar_modified_time<R: Read>(entry: &tar::Entry<R>) -> Option<NaiveDateTime> {
+    let seconds = entry.header().mtime().ok().filter(|seconds| *seconds > 0)?;
+    let modified_time = DateTime::from_timestamp(i64::try_from(seconds).ok()?, 0)?;
+    Some(modified_time.with_timezone(&Local).naive_local())
+}
+
+fn open_zip_archive(archive_path: &Path) -> Result<ZipArchive<BufReader<File>>> {
+    let file = File::open(archive_path).map_err(|e| {
+        LavaError::new(
+            format!("Unable to open file because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    ZipArchive::new(BufReader::new(file)).map_err(|e| {
+        LavaError::new(
+            format!("Unable to open zip archive because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })
+}
+
+fn get_tar_entry_path<R: Read>(entry: &tar::Entry<R>) -> Result<String> {
+    Ok(entry
+        .path()
+        .map_err(|e| {
+            LavaError::new(
+                format!("Unable to read tar entry path because of {e}"),
+                LavaErrorLevel::Critical,
+            )
+        })?
+        .to_string_lossy()
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    static CSV_CONTENT: &str = "id,name,date\n1,John,2025-05-09 10:00:00\n";
+    static JSON_CONTENT: &str = "{\"time\":\"2025-05-09 10:00:00\"}\n";
+
+    fn build_zip() -> NamedTempFile {
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut writer = zip::ZipWriter::new(File::create(temp_file.path()).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("logs/", options).unwrap();
+        writer.start_file("logs/users.csv", options).unwrap();
+        writer.write_all(CSV_CONTENT.as_bytes()).unwrap();
+        writer.start_file("events", options).unwrap();
+        writer.write_all(JSON_CONTENT.as_bytes()).unwrap();
+        writer.finish().unwrap();
+        temp_file
+    }
+
+    fn build_tar_gz() -> NamedTempFile {
+        let temp_file = NamedTempFile::new().unwrap();
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(temp_file.path()).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        for (name, content) in [("logs/users.csv", CSV_CONTENT), ("events", JSON_CONTENT)] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn detects_and_lists_zip() {
+        let archive = build_zip();
+        assert_eq!(
+            get_archive_type(archive.path()).unwrap(),
+            Some(ArchiveType::Zip)
+        );
+        let members = list_archive_members(archive.path(), ArchiveType::Zip).unwrap();
+        let names: Vec<&str> = members.iter().map(|(m, _)| m.inner_path.as_str()).collect();
+        assert_eq!(names, vec!["logs/users.csv", "events"]);
+    }
+
+    #[test]
+    fn detects_and_lists_tar_gz() {
+        let archive = build_tar_gz();
+        assert_eq!(
+            get_archive_type(archive.path()).unwrap(),
+            Some(ArchiveType::Tar)
+        );
+        let members = list_archive_members(archive.path(), ArchiveType::Tar).unwrap();
+        let names: Vec<&str> = members.iter().map(|(m, _)| m.inner_path.as_str()).collect();
+        assert_eq!(names, vec!["logs/users.csv", "events"]);
+    }
+
+    fn read_member(archive_path: &Path, member: &ArchiveMember) -> Vec<u8> {
+        let mut content = Vec::new();
+        open_archive_member(archive_path, member)
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn reads_members_out_of_archives() {
+        for (archive, archive_type) in [
+            (build_zip(), ArchiveType::Zip),
+            (build_tar_gz(), ArchiveType::Tar),
+        ] {
+            let members = list_archive_members(archive.path(), archive_type).unwrap();
+            assert_eq!(
+                read_member(archive.path(), &members[0].0),
+                CSV_CONTENT.as_bytes()
+            );
+            assert_eq!(
+                read_member(archive.path(), &members[1].0),
+                JSON_CONTENT.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn listing_returns_the_start_of_each_member() {
+        for (archive, archive_type) in [
+            (build_zip(), ArchiveType::Zip),
+            (build_tar_gz(), ArchiveType::Tar),
+        ] {
+            let members = list_archive_members(archive.path(), archive_type).unwrap();
+            assert_eq!(members[0].1.as_ref().unwrap(), CSV_CONTENT.as_bytes());
+            assert_eq!(members[1].1.as_ref().unwrap(), JSON_CONTENT.as_bytes());
+        }
+    }
+
+    #[test]
+    fn start_of_big_member_is_bounded_and_streamed_in_chunks() {
+        let content = "a".repeat(MEMBER_CHUNK_SIZE * (MEMBER_CHUNKS_IN_FLIGHT + 3) + 5);
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut writer = zip::ZipWriter::new(File::create(temp_file.path()).unwrap());
+        writer
+            .start_file("big.log", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+        writer.finish().unwrap();
+
+        let (member, start) = list_archive_members(temp_file.path(), ArchiveType::Zip)
+            .unwrap()
+            .remove(0);
+        assert_eq!(start.unwrap().len(), SNIFF_BUFFER_SIZE);
+        assert_eq!(read_member(temp_file.path(), &member), content.as_bytes());
+
+        // Dropping the reader part way through has to stop the thread rather than leave it blocked
+        let mut reader = open_archive_member(temp_file.path(), &member).unwrap();
+        let mut buffer = [0u8; 10];
+        reader.read_exact(&mut buffer).unwrap();
+        drop(reader);
+    }
+
+    #[test]
+    fn claimed_member_size_is_not_trusted() {
+        for (archive, archive_type) in [
+            (build_zip(), ArchiveType::Zip),
+            (build_tar_gz(), ArchiveType::Tar),
+        ] {
+            let mut member = list_archive_members(archive.path(), archive_type)
+                .unwrap()
+                .remove(0)
+                .0;
+            member.size = u64::MAX;
+            assert_eq!(read_member(archive.path(), &member), CSV_CONTENT.as_bytes());
+        }
+    }
+
+    #[test]
+    fn missing_member_is_reported_when_opening() {
+        for (archive, archive_type) in [
+            (build_zip(), ArchiveType::Zip),
+            (build_tar_gz(), ArchiveType::Tar),
+        ] {
+            let member = ArchiveMember {
+                archive_type,
+                inner_path: "not/there.csv".to_string(),
+                size: 0,
+                modified_time: None,
+            };
+            assert!(open_archive_member(archive.path(), &member).is_err());
+        }
+    }
+
+    #[test]
+    fn listing_reads_the_modified_time_of_each_member() {
+        let zip_file = NamedTempFile::new().unwrap();
+        let mut writer = zip::ZipWriter::new(File::create(zip_file.path()).unwrap());
+        let options = zip::write::SimpleFileOptions::default().last_modified_time(
+            zip::DateTime::from_date_and_time(2019, 12, 31, 23, 59, 58).unwrap(),
+        );
+        writer.start_file("old.log", options).unwrap();
+        writer.write_all(CSV_CONTENT.as_bytes()).unwrap();
+        writer.finish().unwrap();
+        let (member, _) = list_archive_members(zip_file.path(), ArchiveType::Zip)
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            member.modified_time,
+            NaiveDate::from_ymd_opt(2019, 12, 31)
+                .unwrap()
+                .and_hms_opt(23, 59, 58)
+        );
+
+        let tar_file = NamedTempFile::new().unwrap();
+        let mut builder = tar::Builder::new(File::create(tar_file.path()).unwrap());
+        for (name, mtime) in [("old.log", 1_577_836_800), ("unset.log", 0)] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(CSV_CONTENT.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(mtime);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, CSV_CONTENT.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap();
+        let members = list_archive_members(tar_file.path(), ArchiveType::Tar).unwrap();
+        assert_eq!(
+            members[0].0.modified_time,
+            DateTime::from_timestamp(1_577_836_800, 0)
+                .map(|t| t.with_timezone(&Local).naive_local())
+        );
+        assert_eq!(members[1].0.modified_time, None);
+    }
+
+    #[test]
+    fn plain_file_is_not_an_archive() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(CSV_CONTENT.as_bytes()).unwrap();
+        assert_eq!(get_archive_type(temp_file.path()).unwrap(), None);
+    }
+}
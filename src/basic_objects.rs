@@ -1,11 +1,15 @@
-use crate::alerts::Alert;
+use crate::alert_config::AlertConfig;
+use crate::alerts::{Alert, AlertRules};
 use crate::date_regex::*;
-use crate::errors::LavaError;
+use crate::errors::{LavaError, LavaErrorLevel, Result};
 use crate::helpers::*;
-use chrono::{NaiveDateTime, TimeDelta};
+use chrono::offset::LocalResult;
+use chrono::{DurationRound, FixedOffset, NaiveDateTime, Offset, TimeDelta, TimeZone};
+use chrono_tz::Tz;
 use csv::StringRecord;
 use human_time::human_time;
 use num_format::{Locale, ToFormattedString};
+use sketches_ddsketch::DDSketch;
 use std::cmp::Ordering;
 use std::fmt;
 use std::path::PathBuf;
@@ -18,6 +22,15 @@ pub enum TimeSignifigance {
     Seconds,
     Milliseconds,
 }
+impl TimeSignifigance {
+    pub fn get_units_per_second(&self) -> f64 {
+        match self {
+            TimeSignifigance::Milliseconds => 1000.0,
+            TimeSignifigance::Seconds => 1.0,
+        }
+    }
+}
+
 impl fmt::Display for TimeSignifigance {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let as_str = match self {
@@ -38,6 +51,164 @@ pub struct ExecutionSettings {
     pub multipart_mode: bool,
     pub verbose_mode: bool,
     pub actually_write_to_files: bool,
+    pub syslog_year: Option<i32>, // Year of the first record for syslog timestamps without one
+    pub assumed_timezone: Option<AssumedTimezone>, // Timezone of timestamps that do not include an offset
+    pub out_of_order_tolerance: Option<OutOfOrderTolerance>, // Only set in tolerant mode
+    pub num_top_time_gaps: usize, // How many of the largest time gaps to keep for the gaps output
+    pub volume_window: VolumeWindow,
+    pub time_gap_alert_method: TimeGapAlertMethod,
+    pub alert_config: AlertConfig, // Threshold overrides and disabled alerts from --alert-config
+    pub json_report_path: Option<PathBuf>, // Where to write the JSON report, which is only written when set
+    pub write_html_report: bool,
+    pub hash_algorithms: Vec<HashAlgorithm>, // Not used in quick mode, which skips hashing
+    pub near_dupe_settings: Option<NearDupeSettings>, // Only set when near duplicate detection is on
+    pub cross_file_dupes: bool, // Compare record hashes across every input file once they are all processed
+    pub dupe_detection_mode: DupeDetectionMode,
+}
+
+/// How record hashes are kept while looking for duplicates, since keeping all of them in memory doesn't fit for very large files
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DupeDetectionMode {
+    #[default]
+    InMemory,
+    Spill {
+        memory_budget_bytes: usize,
+    }, // Exact, sorted runs are written to temporary files once the budget is used up
+    Approximate {
+        false_positive_rate: f64,
+    }, // Bloom filter, so some records may be counted as duplicates that aren't
+}
+
+impl DupeDetectionMode {
+    /// Takes the name from --dupe-mode and the settings that go with it
+    pub fn parse(input: &str, memory_budget_mb: usize, false_positive_rate: f64) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "memory" => Some(Self::InMemory),
+            "spill" => Some(Self::Spill {
+                memory_budget_bytes: memory_budget_mb.saturating_mul(1024 * 1024), // A budget too big to count in bytes is no budget at all
+            }),
+            "approximate" => Some(Self::Approximate {
+                false_positive_rate,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DupeDetectionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DupeDetectionMode::InMemory => write!(f, "memory"),
+            DupeDetectionMode::Spill { .. } => write!(f, "spill"),
+            DupeDetectionMode::Approximate { .. } => write!(f, "approximate"),
+        }
+    }
+}
+
+/// Fields left out when checking for records that are duplicates apart from volatile fields like sequence IDs.
+/// Column names, or JSON pointers for JSON and EVTX records, added to the fields that are found to be volatile
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NearDupeSettings {
+    pub ignored_fields: Vec<String>,
+}
+
+/// Hash of the bytes of each file on disk that is written to the output. SHA256 is also used for the decompressed content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "md5" => Some(Self::Md5),
+            "sha1" => Some(Self::Sha1),
+            "sha256" => Some(Self::Sha256),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashAlgorithm::Md5 => write!(f, "md5"),
+            HashAlgorithm::Sha1 => write!(f, "sha1"),
+            HashAlgorithm::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
+
+/// How the largest time gap is judged for the suspicious time gap alert
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TimeGapAlertMethod {
+    #[default]
+    StandardDeviations, // Standard deviations above the mean gap
+    Percentile, // Multiples of the 99th percentile gap, which outliers don't drag around
+}
+
+impl TimeGapAlertMethod {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "std" => Some(Self::StandardDeviations),
+            "percentile" => Some(Self::Percentile),
+            _ => None,
+        }
+    }
+}
+
+/// How far out of order a record can be, and how many of them there can be, before a file is treated as not sorted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutOfOrderTolerance {
+    pub jitter_window: TimeDelta,
+    pub max_out_of_order_records: usize,
+}
+
+/// Size of the windows records are counted in for the volume series
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum VolumeWindow {
+    Minute,
+    #[default]
+    Hour,
+    Day,
+}
+
+impl VolumeWindow {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "minute" => Some(Self::Minute),
+            "hour" => Some(Self::Hour),
+            "day" => Some(Self::Day),
+            _ => None,
+        }
+    }
+
+    pub fn get_duration(&self) -> TimeDelta {
+        match self {
+            Self::Minute => TimeDelta::minutes(1),
+            Self::Hour => TimeDelta::hours(1),
+            Self::Day => TimeDelta::days(1),
+        }
+    }
+
+    pub fn get_window_start(&self, timestamp: NaiveDateTime) -> NaiveDateTime {
+        timestamp
+            .duration_trunc(self.get_duration())
+            .unwrap_or(timestamp)
+    }
+}
+
+impl fmt::Display for VolumeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let as_str = match self {
+            VolumeWindow::Minute => "minute",
+            VolumeWindow::Hour => "hour",
+            VolumeWindow::Day => "day",
+        };
+        write!(f, "{}", as_str)
+    }
 }
 
 impl ExecutionSettings {
@@ -52,16 +223,118 @@ impl ExecutionSettings {
             quick_mode: quick_mode,
             regexes: PREBUILT_DATE_REGEXES.clone(),
             actually_write_to_files: false,
+            hash_algorithms: vec![HashAlgorithm::Sha256],
             ..Default::default()
         }
     }
 }
 
-#[derive(PartialEq, Debug)]
+/// Timezone that timestamps without an offset are in. A named zone follows its daylight saving time rules
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssumedTimezone {
+    Offset(FixedOffset),
+    Named(Tz),
+}
+
+impl AssumedTimezone {
+    /// Accepts an offset like -05:00 or UTC, or an IANA name like America/New_York
+    pub fn parse(input: &str) -> Option<Self> {
+        if let Some(offset) = parse_utc_offset(input) {
+            return Some(Self::Offset(offset));
+        }
+        input.trim().parse::<Tz>().ok().map(Self::Named)
+    }
+
+    /// A time that happens twice when the clocks fall back is read as the first occurrence, unless prefer_later is set.
+    /// A time skipped when the clocks spring forward can't be in a real log, so it just gets the offset from the day before
+    pub fn convert_local_to_utc(
+        &self,
+        local_datetime: NaiveDateTime,
+        prefer_later: bool,
+    ) -> Option<NaiveDateTime> {
+        match self {
+            Self::Offset(offset) => offset
+                .from_local_datetime(&local_datetime)
+                .single()
+                .map(|datetime| datetime.naive_utc()),
+            Self::Named(tz) => match tz.from_local_datetime(&local_datetime) {
+                LocalResult::Single(datetime) => Some(datetime.naive_utc()),
+                LocalResult::Ambiguous(earlier, later) => match prefer_later {
+                    true => Some(later.naive_utc()),
+                    false => Some(earlier.naive_utc()),
+                },
+                LocalResult::None => {
+                    let offset_before = tz
+                        .offset_from_utc_datetime(&(local_datetime - TimeDelta::days(1)))
+                        .fix();
+                    Some(
+                        local_datetime - TimeDelta::seconds(offset_before.local_minus_utc() as i64),
+                    )
+                }
+            },
+        }
+    }
+
+    pub fn convert_utc_to_local(&self, utc_datetime: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Self::Offset(offset) => offset.from_utc_datetime(&utc_datetime).naive_local(),
+            Self::Named(tz) => tz.from_utc_datetime(&utc_datetime).naive_local(),
+        }
+    }
+
+    /// If the local time of this UTC timestamp happens twice because the clocks fell back, returns the other occurrence
+    pub fn get_other_reading_of_repeated_local_time(
+        &self,
+        utc_datetime: NaiveDateTime,
+    ) -> Option<NaiveDateTime> {
+        let Self::Named(tz) = self else {
+            return None;
+        };
+        let local_datetime = tz.from_utc_datetime(&utc_datetime).naive_local();
+        match tz.from_local_datetime(&local_datetime) {
+            LocalResult::Ambiguous(earlier, later) => match earlier.naive_utc() == utc_datetime {
+                true => Some(later.naive_utc()),
+                false => Some(earlier.naive_utc()),
+            },
+            _ => None,
+        }
+    }
+
+    /// The offset in effect at a UTC timestamp and its abbreviation, ex EST. Only named zones have one that changes
+    pub fn get_named_offset_at(
+        &self,
+        utc_datetime: NaiveDateTime,
+    ) -> Option<(FixedOffset, String)> {
+        let Self::Named(tz) = self else {
+            return None;
+        };
+        let offset = tz.offset_from_utc_datetime(&utc_datetime);
+        Some((offset.fix(), offset.to_string()))
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
 pub enum LogType {
     Csv,
     Json,
     Unstructured,
+    Evtx,
+    Syslog,
+    W3c,
+}
+
+impl fmt::Display for LogType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let as_str = match self {
+            LogType::Csv => "CSV",
+            LogType::Json => "JSONL",
+            LogType::Unstructured => "Unstructured",
+            LogType::Evtx => "EVTX",
+            LogType::Syslog => "Syslog",
+            LogType::W3c => "W3C Extended",
+        };
+        write!(f, "{}", as_str)
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -74,19 +347,97 @@ pub enum TimeDirection {
 pub enum AlertOutputType {
     Duplicate,
     Redaction,
+    EvtxFinding,
+    OutOfOrder,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum CompressionType {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl fmt::Display for CompressionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let as_str = match self {
+            CompressionType::Gzip => "gzip",
+            CompressionType::Bzip2 => "bzip2",
+            CompressionType::Xz => "xz",
+            CompressionType::Zstd => "zstd",
+        };
+        write!(f, "{}", as_str)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct LogTypeDetection {
+    pub confidence: f64, // 0.0 to 1.0
+    pub reason: String,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ArchiveType {
+    Zip,
+    Tar, // Includes compressed tars like .tar.gz
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct ArchiveMember {
+    pub archive_type: ArchiveType,
+    pub inner_path: String,
+    pub size: u64, // As claimed by the archive, so only used for display
+    pub modified_time: Option<NaiveDateTime>, // From the member's own header, in local time like a file's modified time
 }
 
 #[derive(PartialEq, Debug)]
 pub struct LogFile {
     pub log_type: LogType,
-    pub file_path: PathBuf,
+    pub file_path: PathBuf, // For archive members this is the path to the archive itself
+    pub type_detection: Option<LogTypeDetection>, // None when the log type was provided rather than sniffed
+    pub archive_member: Option<ArchiveMember>,
+}
+
+impl LogFile {
+    /// The path of the log itself, which is the path inside the archive for archive members
+    pub fn get_inner_path(&self) -> PathBuf {
+        match &self.archive_member {
+            Some(member) => PathBuf::from(&member.inner_path),
+            None => self.file_path.clone(),
+        }
+    }
+
+    /// Path used in the outputs, archive members look like archive.zip!/inner/path.csv
+    pub fn get_display_path(&self) -> String {
+        match &self.archive_member {
+            Some(member) => format!(
+                "{}!/{}",
+                self.file_path.to_string_lossy(),
+                member.inner_path.trim_start_matches('/')
+            ),
+            None => self.file_path.to_string_lossy().to_string(),
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct PossibleAlertValues {
     pub num_records: usize,
     pub num_dupes: usize,
     pub num_redactions: usize,
+    pub num_evtx_record_id_gaps: usize,
+    pub num_evtx_log_cleared_events: usize,
+    pub num_out_of_order_records: usize,
+    pub largest_out_of_order_displacement: Option<TimeDelta>,
+    pub num_missed_cadence_intervals: usize,
+    pub expected_cadence: Option<TimeDelta>,
+    pub num_quiet_activity_periods: usize,
+    pub num_volume_drops: usize,
+    pub num_volume_spikes: usize,
+    pub percentile_99_time_gap: Option<f64>,
+    pub time_gap_alert_method: TimeGapAlertMethod,
+    pub alert_rules: AlertRules,
     pub largest_time_gap: Option<TimeGap>,
     pub errors: Vec<LavaError>,
     pub mean: f64,
@@ -96,9 +447,16 @@ pub struct PossibleAlertValues {
 #[derive(Debug, Default)]
 pub struct ProcessedLogFile {
     pub sha256hash: Option<String>,
+    pub decompressed_sha256hash: Option<String>,
+    pub md5hash: Option<String>,
+    pub sha1hash: Option<String>,
     pub filename: Option<String>,
     pub file_path: Option<String>,
     pub size: Option<String>,
+    pub compression: Option<String>,
+    pub log_type: Option<String>,
+    pub log_type_confidence: Option<f64>,
+    pub log_type_reason: Option<String>,
     pub first_data_row_used: Option<String>,
     pub time_header: Option<String>,
     pub time_format: Option<String>,
@@ -107,10 +465,30 @@ pub struct ProcessedLogFile {
     pub largest_gap: Option<TimeGap>,
     pub mean_time_gap: Option<f64>,
     pub variance_time_gap: Option<f64>,
+    pub time_gap_sketch: Option<TimeGapSketch>,
     pub total_num_records: usize,
     pub timestamp_num_records: usize,
     pub num_dupes: Option<usize>,
+    pub num_near_dupe_clusters: Option<usize>, // Only set when near duplicate detection is on
+    pub near_dupe_dropped_fields: Vec<String>,
+    pub dupe_detection_mode: Option<String>, // How duplicates were found, ex exact with the number of spilled runs
+    pub num_cross_file_dupes: Option<usize>, // Distinct records also found in another input file, only set with --cross-file-dupes
+    pub record_hashes: Vec<u128>, // Distinct record hashes kept for the cross file comparison, emptied once it is done
     pub num_redactions: Option<usize>,
+    pub num_evtx_record_id_gaps: Option<usize>, // Only set for EVTX files
+    pub num_evtx_log_cleared_events: Option<usize>,
+    pub num_out_of_order_records: Option<usize>, // Only set in tolerant mode
+    pub largest_out_of_order_displacement: Option<TimeDelta>,
+    pub approx_num_time_gaps_above_threshold: Option<usize>, // Counted from the time gap sketch, so gaps within 1% of the threshold may be off
+    pub expected_cadence: Option<TimeDelta>,                 // Only set for periodic logs
+    pub num_missed_cadence_intervals: Option<usize>,
+    pub num_quiet_activity_periods: Option<usize>,
+    pub num_volume_drops: Option<usize>,
+    pub num_volume_spikes: Option<usize>,
+    pub dst_transitions: Vec<String>,
+    pub largest_time_gaps: Vec<IndexedTimeGap>, // Top time gaps, largest first, for the HTML timeline
+    pub hour_of_day_counts: Option<[usize; 24]>,
+    pub day_of_week_counts: Option<[usize; 7]>, // Monday first
     pub errors: Vec<LavaError>,
     pub alerts: Vec<Alert>,
 }
@@ -133,7 +511,19 @@ impl ProcessedLogFile {
             self.filename.as_deref().unwrap_or("").to_string(),
             self.file_path.as_deref().unwrap_or("").to_string(),
             self.sha256hash.as_deref().unwrap_or("").to_string(),
+            self.decompressed_sha256hash
+                .as_deref()
+                .unwrap_or("")
+                .to_string(),
+            self.md5hash.as_deref().unwrap_or("").to_string(),
+            self.sha1hash.as_deref().unwrap_or("").to_string(),
             self.size.as_deref().unwrap_or("").to_string(),
+            self.compression.as_deref().unwrap_or("").to_string(),
+            self.log_type.as_deref().unwrap_or("").to_string(),
+            self.log_type_confidence
+                .map(|v| format!("{:.2}", v))
+                .unwrap_or_default(),
+            self.log_type_reason.as_deref().unwrap_or("").to_string(),
             self.first_data_row_used
                 .as_deref()
                 .unwrap_or("")
@@ -168,13 +558,63 @@ impl ProcessedLogFile {
             self.variance_time_gap
                 .map(|v| v.sqrt().to_string())
                 .unwrap_or_default(),
+            self.get_time_gap_percentile_string(50.0),
+            self.get_time_gap_percentile_string(90.0),
+            self.get_time_gap_percentile_string(99.0),
+            self.time_gap_sketch
+                .as_ref()
+                .and_then(|sketch| sketch.get_max())
+                .map(|v| v.to_string())
+                .unwrap_or_default(),
             self.get_num_std_devs_above_mean().unwrap_or("".to_string()),
+            self.approx_num_time_gaps_above_threshold
+                .map(|v| v.to_formatted_string(&Locale::en))
+                .unwrap_or_default(),
+            self.expected_cadence
+                .map(get_seconds_string_from_time_delta)
+                .unwrap_or_default(),
+            self.num_missed_cadence_intervals
+                .map(|v| v.to_formatted_string(&Locale::en))
+                .unwrap_or_default(),
+            self.num_quiet_activity_periods
+                .map(|v| v.to_formatted_string(&Locale::en))
+                .unwrap_or_default(),
+            self.num_volume_drops
+                .map(|v| v.to_formatted_string(&Locale::en))
+                .unwrap_or_default(),
+            self.num_volume_spikes
+                .map(|v| v.to_formatted_string(&Locale::en))
+                .unwrap_or_default(),
             self.num_dupes
                 .map(|v| v.to_formatted_string(&Locale::en))
                 .unwrap_or_default(),
+            self.dupe_detection_mode
+                .as_deref()
+                .unwrap_or("")
+                .to_string(),
+            self.num_near_dupe_clusters
+                .map(|v| v.to_formatted_string(&Locale::en))
+                .unwrap_or_default(),
+            self.near_dupe_dropped_fields.join("; "),
+            self.num_cross_file_dupes
+                .map(|v| v.to_formatted_string(&Locale::en))
+                .unwrap_or_default(),
             self.num_redactions
                 .map(|v| v.to_formatted_string(&Locale::en))
                 .unwrap_or_default(),
+            self.num_evtx_record_id_gaps
+                .map(|v| v.to_formatted_string(&Locale::en))
+                .unwrap_or_default(),
+            self.num_evtx_log_cleared_events
+                .map(|v| v.to_formatted_string(&Locale::en))
+                .unwrap_or_default(),
+            self.num_out_of_order_records
+                .map(|v| v.to_formatted_string(&Locale::en))
+                .unwrap_or_default(),
+            self.largest_out_of_order_displacement
+                .map(get_seconds_string_from_time_delta)
+                .unwrap_or_default(),
+            self.dst_transitions.join("; "),
             error_message,
         ]
     }
@@ -246,6 +686,14 @@ impl ProcessedLogFile {
         }
     }
 
+    fn get_time_gap_percentile_string(&self, percentile: f64) -> String {
+        self.time_gap_sketch
+            .as_ref()
+            .and_then(|sketch| sketch.get_percentile(percentile))
+            .map(|v| format!("{:.2}", v))
+            .unwrap_or_default()
+    }
+
     fn get_num_std_devs_above_mean(&self) -> Option<String> {
         Some(
             ((self.largest_gap?.get_time_duration_number() as f64 - self.mean_time_gap?)
@@ -254,7 +702,7 @@ impl ProcessedLogFile {
         )
     }
 
-    fn convert_time_delta_to_number_of_hours(tdelta: TimeDelta) -> String {
+    pub fn convert_time_delta_to_number_of_hours(tdelta: TimeDelta) -> String {
         let total_seconds = tdelta.num_seconds().abs(); // make it positive for display
 
         let hours = total_seconds / 3600;
@@ -264,7 +712,7 @@ impl ProcessedLogFile {
         format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
     }
 
-    fn convert_time_delta_to_human_time(chrono_duration: TimeDelta) -> Option<String> {
+    pub fn convert_time_delta_to_human_time(chrono_duration: TimeDelta) -> Option<String> {
         // Convert chrono::Duration to std::time::Duration
         let std_duration = if let Some(dur) = chrono_duration.to_std().ok() {
             dur
@@ -303,9 +751,9 @@ pub struct ProcessedLogFileComboEssentials {
     pub time_gap_var: f64,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct LogFileRecord {
-    pub hash_of_entire_record: u64,
+    pub hash_of_entire_record: u128,
     pub raw_record: StringRecord,
     pub timestamp: Option<NaiveDateTime>,
     pub index: usize,
@@ -330,9 +778,11 @@ impl LogFileRecord {
         let mut base_record = match alert_type {
             AlertOutputType::Duplicate => StringRecord::from(vec![
                 self.index.to_string(),
-                format!("{:x}", self.hash_of_entire_record),
+                format_record_hash(self.hash_of_entire_record),
             ]),
-            AlertOutputType::Redaction => {
+            AlertOutputType::Redaction
+            | AlertOutputType::EvtxFinding
+            | AlertOutputType::OutOfOrder => {
                 StringRecord::from(vec![self.index.to_string(), rule_name.unwrap()])
             }
         };
@@ -400,12 +850,208 @@ impl TimeGap {
     }
 }
 
+/// Streaming quantile sketch of time gaps, in the same units as the Welford stats. Gap distributions are heavy-tailed, so
+/// percentiles describe them better than the mean, and sketches from multipart files can be merged
+#[derive(Clone, Default)]
+pub struct TimeGapSketch {
+    sketch: DDSketch,
+}
+
+impl fmt::Debug for TimeGapSketch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TimeGapSketch {{ count: {} }}", self.sketch.count())
+    }
+}
+
+impl TimeGapSketch {
+    pub fn add(&mut self, time_gap: &TimeGap) {
+        self.sketch.add(time_gap.get_time_duration_number() as f64);
+    }
+
+    pub fn merge(&mut self, other: &TimeGapSketch) -> Result<()> {
+        self.sketch.merge(&other.sketch).map_err(|e| {
+            LavaError::new(
+                format!("Unable to combine time gap percentiles because of {e}"),
+                LavaErrorLevel::Medium,
+            )
+        })
+    }
+
+    /// Between 0 and 100, accurate to within 1% of the real value
+    pub fn get_percentile(&self, percentile: f64) -> Option<f64> {
+        self.sketch.quantile(percentile / 100.0).ok().flatten()
+    }
+
+    pub fn get_max(&self) -> Option<f64> {
+        self.sketch.max()
+    }
+
+    pub fn count(&self) -> usize {
+        self.sketch.count()
+    }
+
+    /// Number of gaps at least this long, counting gaps within 1% of it either way since that's as close as the sketch keeps them
+    pub fn count_at_least(&self, time_duration_number: f64) -> usize {
+        let count = self.count();
+        let get_value_at_rank = |rank: usize| {
+            // Aim for the middle of the rank so floating point error can't round down to the one before it
+            let quantile = if count > 1 {
+                ((rank as f64 + 0.5) / (count - 1) as f64).min(1.0)
+            } else {
+                1.0
+            };
+            self.sketch
+                .quantile(quantile)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        };
+        // Values only go up with rank, so binary search for the first one that's long enough
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if get_value_at_rank(middle) >= time_duration_number {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+        count - low
+    }
+}
+
+/// A time gap along with the indexes of the records on either side of it, kept for the top time gaps output
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub struct IndexedTimeGap {
+    pub time_gap: TimeGap,
+    pub beginning_index: usize, // Index of the record at the beginning time, which comes after the end in descending files
+    pub end_index: usize,
+}
+
+impl IndexedTimeGap {
+    pub fn get_num_std_devs_above_mean(&self, mean: f64, std: f64) -> Option<f64> {
+        if std == 0.0 {
+            return None;
+        }
+        Some((self.time_gap.get_time_duration_number() as f64 - mean) / std)
+    }
+
+    pub fn get_output_row(
+        &self,
+        rank: usize,
+        mean: f64,
+        std: f64,
+        above_alert_threshold: bool,
+    ) -> StringRecord {
+        StringRecord::from(vec![
+            rank.to_string(),
+            self.time_gap
+                .beginning_time
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            self.time_gap
+                .end_time
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            self.beginning_index.to_string(),
+            self.end_index.to_string(),
+            ProcessedLogFile::convert_time_delta_to_number_of_hours(self.time_gap.gap),
+            ProcessedLogFile::convert_time_delta_to_human_time(self.time_gap.gap)
+                .unwrap_or_default(),
+            self.get_num_std_devs_above_mean(mean, std)
+                .map(|v| format!("{:.2}", v))
+                .unwrap_or_default(),
+            above_alert_threshold.to_string(),
+        ])
+    }
+}
+
+/// A gap in a periodic log long enough that records which should have been written are missing
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct MissedCadenceInterval {
+    pub indexed_time_gap: IndexedTimeGap,
+    pub num_missed_intervals: usize,
+}
+
+impl MissedCadenceInterval {
+    pub fn get_output_row(&self) -> StringRecord {
+        let time_gap = self.indexed_time_gap.time_gap;
+        StringRecord::from(vec![
+            time_gap
+                .beginning_time
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            time_gap.end_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            self.indexed_time_gap.beginning_index.to_string(),
+            self.indexed_time_gap.end_index.to_string(),
+            get_seconds_string_from_time_delta(time_gap.gap),
+            self.num_missed_intervals.to_string(),
+        ])
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct IdentifiedTimeInformation {
     pub column_name: Option<String>,
     pub column_index: Option<usize>,
+    pub composite_column_indices: Vec<usize>, // Every column that makes up the timestamp when it is split across several, ex Date+Time
     pub regex_info: DateRegex,
     pub direction: Option<TimeDirection>,
+    pub assumed_timezone: Option<AssumedTimezone>,
+}
+
+impl IdentifiedTimeInformation {
+    /// Timestamps are normalized to UTC so files from different timezones can be compared. Without a column the string is
+    /// a whole unstructured line, so an offset only counts when it is written right up against the timestamp
+    pub fn get_utc_timestamp_from_string(
+        &self,
+        string_to_extract_from: String,
+    ) -> Result<Option<NaiveDateTime>> {
+        Ok(
+            match self
+                .regex_info
+                .get_local_timestamp_and_offset_from_string_containing_date(
+                    string_to_extract_from,
+                    self.column_name.is_some(),
+                )? {
+                None => None,
+                Some((local_datetime, Some(offset))) => offset
+                    .from_local_datetime(&local_datetime)
+                    .single()
+                    .map(|datetime| datetime.naive_utc()),
+                Some((local_datetime, None)) => self.convert_local_timestamp_to_utc(local_datetime),
+            },
+        )
+    }
+
+    /// For timestamps without an offset. A descending file reaches the second occurrence of a repeated hour first, so that is the one it prefers
+    pub fn convert_local_timestamp_to_utc(
+        &self,
+        local_datetime: NaiveDateTime,
+    ) -> Option<NaiveDateTime> {
+        match &self.assumed_timezone {
+            None => Some(local_datetime),
+            Some(assumed_timezone) => assumed_timezone.convert_local_to_utc(
+                local_datetime,
+                self.direction == Some(TimeDirection::Descending),
+            ),
+        }
+    }
+
+    /// For CSVs. A timestamp split across columns is joined back together with spaces
+    pub fn get_timestamp_string_from_record(&self, record: &StringRecord) -> Option<String> {
+        if self.composite_column_indices.is_empty() {
+            return record
+                .get(self.column_index?)
+                .map(|value| value.to_string());
+        }
+        let parts: Option<Vec<&str>> = self
+            .composite_column_indices
+            .iter()
+            .map(|index| record.get(*index))
+            .collect();
+        Some(parts?.join(" "))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -34,7 +34,7 @@ fn test_get_record_to_output_duplicate() {
     let expected = {
         let mut sr = StringRecord::from(vec![
             index.to_string(),
-            format!("{:x}", record.hash_of_entire_record),
+            format_record_hash(record.hash_of_entire_record),
         ]);
         sr.extend(raw.iter());
         sr
@@ -0,0 +1,389 @@
+use crate::errors::*;
+use crate::processing_objects::{DuplicateOccurrences, TrackedRecord};
+use chrono::{DateTime, NaiveDateTime};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+// Key, value and the hash map's own bookkeeping for each record in the duplicate tracker
+pub static ESTIMATED_BYTES_PER_TRACKED_RECORD: usize =
+    size_of::<u128>() + size_of::<TrackedRecord>() + 16;
+static INITIAL_BLOOM_FILTER_CAPACITY: usize = 1 << 20;
+static BLOOM_FILTER_TIGHTENING_RATIO: f64 = 0.5; // Each new filter gets this fraction of the last one's false positive rate
+
+/// Writes the duplicate tracker out as runs sorted by hash whenever it outgrows the memory budget, then merges the runs
+/// back together at the end so the counts are still exact. Temporary files are removed when this is dropped
+#[derive(Default)]
+pub struct DuplicateTrackerSpiller {
+    temp_dir: Option<TempDir>,
+    run_paths: Vec<PathBuf>,
+}
+
+impl DuplicateTrackerSpiller {
+    pub fn get_num_runs(&self) -> usize {
+        self.run_paths.len()
+    }
+
+    /// Empties the tracker into a new run, leaving it as it was if the run couldn't be written
+    pub fn spill(&mut self, duplicate_tracker: &mut HashMap<u128, TrackedRecord>) -> Result<()> {
+        if duplicate_tracker.is_empty() {
+            return Ok(());
+        }
+        if self.temp_dir.is_none() {
+            self.temp_dir = Some(TempDir::new().map_err(|e| {
+                LavaError::new(
+                    format!("Unable to create a temporary directory for duplicate detection because of {e}"),
+                    LavaErrorLevel::Medium,
+                )
+            })?);
+        }
+        let run_path = self
+            .temp_dir
+            .as_ref()
+            .unwrap()
+            .path()
+            .join(format!("run_{}.bin", self.run_paths.len()));
+        let mut entries: Vec<(&u128, &TrackedRecord)> = duplicate_tracker.iter().collect();
+        entries.sort_unstable_by_key(|(hash_of_record, _)| **hash_of_record);
+
+        let file = File::create(&run_path).map_err(|e| spill_error(&e))?;
+        let mut writer = BufWriter::new(file);
+        for (hash_of_record, tracked_record) in entries {
+            write_run_entry(
+                &mut writer,
+                *hash_of_record,
+                &tracked_record.get_occurrences(),
+            )
+            .map_err(|e| spill_error(&e))?;
+        }
+        writer.flush().map_err(|e| spill_error(&e))?;
+
+        self.run_paths.push(run_path);
+        duplicate_tracker.clear();
+        duplicate_tracker.shrink_to_fit();
+        Ok(())
+    }
+
+    /// Runs the callback on every distinct record in hash order, with its occurrences from every run combined and the indexes of the
+    /// repeats that were only found by the merge because the earlier occurrence was in an earlier run
+    pub fn merge<F>(&self, mut callback: F) -> Result<()>
+    where
+        F: FnMut(u128, DuplicateOccurrences, &[usize]),
+    {
+        let mut run_readers: Vec<BufReader<File>> = Vec::new();
+        for run_path in self.run_paths.iter() {
+            run_readers.push(BufReader::new(
+                File::open(run_path).map_err(|e| spill_error(&e))?,
+            ));
+        }
+        // Runs were written in file order, so popping by hash then run number keeps occurrences of a record in file order
+        let mut next_entries: BinaryHeap<Reverse<(u128, usize)>> = BinaryHeap::new();
+        let mut current_entries: Vec<Option<DuplicateOccurrences>> = vec![None; run_readers.len()];
+        for (run_number, run_reader) in run_readers.iter_mut().enumerate() {
+            if let Some((hash_of_record, occurrences)) =
+                read_run_entry(run_reader).map_err(|e| spill_error(&e))?
+            {
+                next_entries.push(Reverse((hash_of_record, run_number)));
+                current_entries[run_number] = Some(occurrences);
+            }
+        }
+
+        let mut merged: Option<(u128, DuplicateOccurrences)> = None;
+        let mut cross_run_repeat_indexes: Vec<usize> = Vec::new();
+        while let Some(Reverse((hash_of_record, run_number))) = next_entries.pop() {
+            let occurrences = current_entries[run_number].take().unwrap(); // Set whenever the run is in the heap
+            if let Some((hash_of_record, occurrences)) =
+                read_run_entry(&mut run_readers[run_number]).map_err(|e| spill_error(&e))?
+            {
+                next_entries.push(Reverse((hash_of_record, run_number)));
+                current_entries[run_number] = Some(occurrences);
+            }
+            match merged.as_mut() {
+                Some((merged_hash, merged_occurrences)) if *merged_hash == hash_of_record => {
+                    cross_run_repeat_indexes.push(occurrences.first_index);
+                    merged_occurrences.merge_later_occurrences(occurrences);
+                }
+                _ => {
+                    if let Some((merged_hash, merged_occurrences)) = merged.take() {
+                        callback(merged_hash, merged_occurrences, &cross_run_repeat_indexes);
+                        cross_run_repeat_indexes.clear();
+                    }
+                    merged = Some((hash_of_record, occurrences));
+                }
+            }
+        }
+        if let Some((merged_hash, merged_occurrences)) = merged {
+            callback(merged_hash, merged_occurrences, &cross_run_repeat_indexes);
+        }
+        Ok(())
+    }
+}
+
+fn spill_error(e: &std::io::Error) -> LavaError {
+    LavaError::new(
+        format!("Unable to use the duplicate detection spill file because of {e}"),
+        LavaErrorLevel::Medium,
+    )
+}
+
+fn write_run_entry<W: Write>(
+    writer: &mut W,
+    hash_of_record: u128,
+    occurrences: &DuplicateOccurrences,
+) -> std::io::Result<()> {
+    writer.write_all(&hash_of_record.to_le_bytes())?;
+    for value in [
+        occurrences.first_index,
+        occurrences.last_index,
+        occurrences.num_occurrences,
+        occurrences.num_contiguous_repeats,
+        occurrences.repeat_indexes.len(),
+    ] {
+        writer.write_all(&(value as u64).to_le_bytes())?;
+    }
+    for repeat_index in occurrences.repeat_indexes.iter() {
+        writer.write_all(&(*repeat_index as u64).to_le_bytes())?;
+    }
+    for timestamp in [occurrences.min_timestamp, occurrences.max_timestamp] {
+        match timestamp {
+            Some(timestamp) => {
+                writer.write_all(&[1])?;
+                writer.write_all(&timestamp.and_utc().timestamp_micros().to_le_bytes())?;
+            }
+            None => writer.write_all(&[0])?,
+        }
+    }
+    Ok(())
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> std::io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_u128<R: Read>(reader: &mut R) -> std::io::Result<u128> {
+    let mut bytes = [0u8; 16];
+    reader.read_exact(&mut bytes)?;
+    Ok(u128::from_le_bytes(bytes))
+}
+
+fn read_timestamp<R: Read>(reader: &mut R) -> std::io::Result<Option<NaiveDateTime>> {
+    let mut flag = [0u8; 1];
+    reader.read_exact(&mut flag)?;
+    if flag[0] == 0 {
+        return Ok(None);
+    }
+    let timestamp_micros = read_u64(reader)? as i64;
+    Ok(DateTime::from_timestamp_micros(timestamp_micros).map(|timestamp| timestamp.naive_utc()))
+}
+
+/// None at the end of the run
+fn read_run_entry<R: Read>(
+    reader: &mut R,
+) -> std::io::Result<Option<(u128, DuplicateOccurrences)>> {
+    let hash_of_record = match read_u128(reader) {
+        Ok(hash_of_record) => hash_of_record,
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let first_index = read_u64(reader)? as usize;
+    let last_index = read_u64(reader)? as usize;
+    let num_occurrences = read_u64(reader)? as usize;
+    let num_contiguous_repeats = read_u64(reader)? as usize;
+    let num_repeat_indexes = read_u64(reader)? as usize;
+    let mut repeat_indexes: Vec<usize> = Vec::with_capacity(num_repeat_indexes);
+    for _ in 0..num_repeat_indexes {
+        repeat_indexes.push(read_u64(reader)? as usize);
+    }
+    Ok(Some((
+        hash_of_record,
+        DuplicateOccurrences {
+            first_index,
+            last_index,
+            num_occurrences,
+            repeat_indexes,
+            min_timestamp: read_timestamp(reader)?,
+            max_timestamp: read_timestamp(reader)?,
+            num_contiguous_repeats,
+        },
+    )))
+}
+
+/// Bloom filter that adds a bigger filter with a tighter false positive rate each time the last one fills up, so it doesn't need
+/// to know how many records are coming and the false positive rate of the whole thing stays under the one asked for
+pub struct ScalableBloomFilter {
+    false_positive_rate: f64,
+    filters: Vec<BloomFilter>,
+}
+
+impl ScalableBloomFilter {
+    pub fn new(false_positive_rate: f64) -> Self {
+        Self {
+            false_positive_rate,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Adds the hash, returning true if it may have been added before
+    pub fn insert(&mut self, hash_of_record: u128) -> bool {
+        if self
+            .filters
+            .iter()
+            .any(|filter| filter.contains(hash_of_record))
+        {
+            return true;
+        }
+        if self.filters.last().is_none_or(|filter| filter.is_full()) {
+            let num_filters = self.filters.len() as i32;
+            self.filters.push(BloomFilter::new(
+                INITIAL_BLOOM_FILTER_CAPACITY << num_filters,
+                self.false_positive_rate
+                    * (1.0 - BLOOM_FILTER_TIGHTENING_RATIO)
+                    * BLOOM_FILTER_TIGHTENING_RATIO.powi(num_filters),
+            ));
+        }
+        self.filters.last_mut().unwrap().insert(hash_of_record);
+        false
+    }
+}
+
+struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+    capacity: usize,
+    num_items: usize,
+}
+
+impl BloomFilter {
+    fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        let ln_2 = std::f64::consts::LN_2;
+        let num_bits =
+            ((-(capacity as f64) * false_positive_rate.ln() / (ln_2 * ln_2)).ceil() as u64).max(64);
+        Self {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            num_hashes: (-false_positive_rate.log2()).ceil().max(1.0) as u32,
+            capacity,
+            num_items: 0,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.num_items >= self.capacity
+    }
+
+    /// Double hashing, with the two halves of the record hash as the two independent hashes
+    fn get_bit_positions(&self, hash_of_record: u128) -> impl Iterator<Item = u64> + '_ {
+        let first_hash = hash_of_record as u64;
+        let second_hash = (hash_of_record >> 64) as u64 | 1;
+        (0..self.num_hashes as u64)
+            .map(move |i| first_hash.wrapping_add(i.wrapping_mul(second_hash)) % self.num_bits)
+    }
+
+    fn contains(&self, hash_of_record: u128) -> bool {
+        self.get_bit_positions(hash_of_record)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    fn insert(&mut self, hash_of_record: u128) {
+        let bit_positions: Vec<u64> = self.get_bit_positions(hash_of_record).collect();
+        for bit in bit_positions {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.num_items += 1;
+    }
+}
+
+#[cfg(test)]
+mod bounded_dupes_tests {
+    use super::*;
+    use crate::helpers::hash_csv_record;
+    use csv::StringRecord;
+
+    fn tracked_record(first_index: usize, repeat_indexes: Vec<usize>) -> TrackedRecord {
+        match repeat_indexes.is_empty() {
+            true => TrackedRecord::SeenOnce {
+                index: first_index,
+                timestamp: None,
+            },
+            false => TrackedRecord::Repeated(Box::new(occurrences(first_index, repeat_indexes))),
+        }
+    }
+
+    fn occurrences(first_index: usize, repeat_indexes: Vec<usize>) -> DuplicateOccurrences {
+        DuplicateOccurrences {
+            first_index,
+            last_index: *repeat_indexes.last().unwrap_or(&first_index),
+            num_occurrences: 1 + repeat_indexes.len(),
+            repeat_indexes,
+            min_timestamp: None,
+            max_timestamp: None,
+            num_contiguous_repeats: 0,
+        }
+    }
+
+    #[test]
+    fn merged_runs_combine_occurrences_across_runs() {
+        let mut spiller = DuplicateTrackerSpiller::default();
+        let mut duplicate_tracker: HashMap<u128, TrackedRecord> = HashMap::new();
+        duplicate_tracker.insert(1, tracked_record(0, vec![2]));
+        duplicate_tracker.insert(2, tracked_record(1, Vec::new()));
+        spiller.spill(&mut duplicate_tracker).unwrap();
+        assert!(duplicate_tracker.is_empty());
+        duplicate_tracker.insert(1, tracked_record(3, Vec::new()));
+        duplicate_tracker.insert(3, tracked_record(4, Vec::new()));
+        spiller.spill(&mut duplicate_tracker).unwrap();
+        assert_eq!(spiller.get_num_runs(), 2);
+
+        let mut merged: Vec<(u128, DuplicateOccurrences, Vec<usize>)> = Vec::new();
+        spiller
+            .merge(|hash_of_record, occurrences, cross_run_repeat_indexes| {
+                merged.push((
+                    hash_of_record,
+                    occurrences,
+                    cross_run_repeat_indexes.to_vec(),
+                ))
+            })
+            .unwrap();
+
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].0, 1);
+        assert_eq!(merged[0].1.num_occurrences, 3);
+        assert_eq!(merged[0].1.repeat_indexes, vec![2, 3]);
+        assert_eq!(merged[0].1.num_contiguous_repeats, 1); // 3 comes right after 2
+        assert_eq!(merged[0].2, vec![3]);
+        assert_eq!(merged[1].1.num_occurrences, 1);
+        assert!(merged[2].2.is_empty());
+    }
+
+    #[test]
+    fn run_entries_round_trip() {
+        let mut entry = occurrences(5, vec![7, 9]);
+        entry.min_timestamp = Some(
+            NaiveDateTime::parse_from_str("2024-05-01 14:00:00", "%Y-%m-%d %H:%M:%S").unwrap(),
+        );
+        let mut bytes: Vec<u8> = Vec::new();
+        write_run_entry(&mut bytes, 42, &entry).unwrap();
+        let mut reader = bytes.as_slice();
+        assert_eq!(read_run_entry(&mut reader).unwrap(), Some((42, entry)));
+        assert_eq!(read_run_entry(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn bloom_filter_finds_repeats_within_false_positive_rate() {
+        let mut filter = ScalableBloomFilter::new(0.01);
+        let num_records: usize = 200_000;
+        let num_false_positives = (0..num_records)
+            .map(|i| hash_csv_record(&StringRecord::from(vec![i.to_string()])))
+            .filter(|hash_of_record| filter.insert(*hash_of_record))
+            .count();
+        assert!((num_false_positives as f64) < num_records as f64 * 0.01);
+        assert!(filter.insert(hash_csv_record(&StringRecord::from(vec!["0"]))));
+        assert!(filter.insert(hash_csv_record(&StringRecord::from(vec!["5"]))));
+    }
+}
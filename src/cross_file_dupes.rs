@@ -0,0 +1,199 @@
+use crate::alerts::get_cross_file_dupes_alert;
+use crate::basic_objects::*;
+use crate::errors::*;
+use crate::helpers::write_rows_to_file;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+pub static CROSS_FILE_DUPES_FILENAME: &str = "LAVA_Cross_File_Duplicates.csv";
+
+/// Two input files, by their position in the results, and how many distinct records are in both
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedRecordsBetweenFiles {
+    pub file_index_1: usize,
+    pub file_index_2: usize,
+    pub num_shared_records: usize,
+}
+
+/// Compares the record hashes kept for every file, which are used up. Sets how many records of each file are also in another one,
+/// and adds an alert to the later file of each pair so the same records exported twice or overlapping rotated chunks are caught
+pub fn find_records_shared_between_files(
+    results: &mut [ProcessedLogFile],
+    log_files: &[LogFile],
+    execution_settings: &ExecutionSettings,
+) -> Vec<SharedRecordsBetweenFiles> {
+    // First file each record was seen in, then any others, so records in only one file don't allocate
+    let mut files_by_record_hash: HashMap<u128, (usize, Vec<usize>)> = HashMap::new();
+    for (file_index, processed_log_file) in results.iter_mut().enumerate() {
+        for record_hash in std::mem::take(&mut processed_log_file.record_hashes) {
+            files_by_record_hash
+                .entry(record_hash)
+                .and_modify(|(_, other_file_indexes)| other_file_indexes.push(file_index))
+                .or_insert((file_index, Vec::new()));
+        }
+    }
+
+    let mut num_shared_records_by_pair: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    let mut num_cross_file_dupes_by_file: Vec<usize> = vec![0; results.len()];
+    for (first_file_index, other_file_indexes) in files_by_record_hash.values() {
+        if other_file_indexes.is_empty() {
+            continue;
+        }
+        let file_indexes: Vec<usize> = std::iter::once(*first_file_index)
+            .chain(other_file_indexes.iter().copied())
+            .collect();
+        for (i, file_index_1) in file_indexes.iter().enumerate() {
+            num_cross_file_dupes_by_file[*file_index_1] += 1;
+            for file_index_2 in file_indexes[i + 1..].iter() {
+                *num_shared_records_by_pair
+                    .entry((*file_index_1, *file_index_2))
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
+    for (processed_log_file, num_cross_file_dupes) in results
+        .iter_mut()
+        .zip(num_cross_file_dupes_by_file)
+        .filter(|(processed_log_file, _)| processed_log_file.num_dupes.is_some())
+    {
+        processed_log_file.num_cross_file_dupes = Some(num_cross_file_dupes);
+    }
+
+    let shared_records_between_files: Vec<SharedRecordsBetweenFiles> = num_shared_records_by_pair
+        .into_iter()
+        .map(
+            |((file_index_1, file_index_2), num_shared_records)| SharedRecordsBetweenFiles {
+                file_index_1,
+                file_index_2,
+                num_shared_records,
+            },
+        )
+        .collect();
+    for shared_records in shared_records_between_files.iter() {
+        let alert_rules = match log_files.get(shared_records.file_index_2) {
+            Some(log_file) => execution_settings.alert_config.get_rules_for_file(log_file),
+            None => execution_settings.alert_config.get_default_rules(),
+        };
+        if let Some(alert) = get_cross_file_dupes_alert(
+            get_filename(&results[shared_records.file_index_1]),
+            get_filename(&results[shared_records.file_index_2]),
+            shared_records.num_shared_records,
+            &alert_rules,
+        ) {
+            results[shared_records.file_index_2].alerts.push(alert);
+        }
+    }
+    shared_records_between_files
+}
+
+fn get_filename(processed_log_file: &ProcessedLogFile) -> String {
+    processed_log_file.filename.clone().unwrap_or_default()
+}
+
+pub fn write_shared_records_between_files_to_file(
+    output_dir: &Path,
+    results: &[ProcessedLogFile],
+    shared_records_between_files: &[SharedRecordsBetweenFiles],
+) -> Result<()> {
+    write_rows_to_file(
+        &output_dir.join(CROSS_FILE_DUPES_FILENAME),
+        &[
+            "File 1",
+            "File 2",
+            "Shared Records",
+            "Records in File 1",
+            "Records in File 2",
+        ],
+        shared_records_between_files.iter().map(|shared_records| {
+            let file_1 = &results[shared_records.file_index_1];
+            let file_2 = &results[shared_records.file_index_2];
+            [
+                file_1.file_path.clone().unwrap_or_default(),
+                file_2.file_path.clone().unwrap_or_default(),
+                shared_records.num_shared_records.to_string(),
+                file_1.total_num_records.to_string(),
+                file_2.total_num_records.to_string(),
+            ]
+        }),
+    )
+}
+
+#[cfg(test)]
+mod cross_file_dupes_tests {
+    use super::*;
+    use crate::alerts::AlertType;
+
+    fn processed_log_file(filename: &str, record_hashes: Vec<u128>) -> ProcessedLogFile {
+        ProcessedLogFile {
+            filename: Some(filename.to_string()),
+            file_path: Some(format!("/logs/{}", filename)),
+            total_num_records: record_hashes.len(),
+            num_dupes: Some(0),
+            record_hashes,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn counts_records_shared_by_each_pair_of_files() {
+        let mut results = vec![
+            processed_log_file("a.csv", vec![1, 2, 3, 4]),
+            processed_log_file("b.csv", vec![3, 4, 5]),
+            processed_log_file("c.csv", vec![4, 6]),
+            processed_log_file("d.csv", vec![7]),
+        ];
+        let shared_records_between_files =
+            find_records_shared_between_files(&mut results, &[], &ExecutionSettings::default());
+
+        assert_eq!(
+            shared_records_between_files,
+            vec![
+                SharedRecordsBetweenFiles {
+                    file_index_1: 0,
+                    file_index_2: 1,
+                    num_shared_records: 2,
+                },
+                SharedRecordsBetweenFiles {
+                    file_index_1: 0,
+                    file_index_2: 2,
+                    num_shared_records: 1,
+                },
+                SharedRecordsBetweenFiles {
+                    file_index_1: 1,
+                    file_index_2: 2,
+                    num_shared_records: 1,
+                },
+            ]
+        );
+        let num_cross_file_dupes: Vec<Option<usize>> = results
+            .iter()
+            .map(|processed_log_file| processed_log_file.num_cross_file_dupes)
+            .collect();
+        assert_eq!(
+            num_cross_file_dupes,
+            vec![Some(2), Some(2), Some(1), Some(0)]
+        );
+        assert!(
+            results
+                .iter()
+                .all(|processed_log_file| processed_log_file.record_hashes.is_empty())
+        );
+    }
+
+    #[test]
+    fn alert_is_added_to_the_later_file() {
+        let mut results = vec![
+            processed_log_file("a.csv", vec![1, 2]),
+            processed_log_file("b.csv", vec![1, 2]),
+        ];
+        find_records_shared_between_files(&mut results, &[], &ExecutionSettings::default());
+
+        assert!(results[0].alerts.is_empty());
+        assert_eq!(results[1].alerts.len(), 1);
+        assert_eq!(
+            results[1].alerts[0].alert_type,
+            AlertType::CrossFileDupes("a.csv".to_string(), "b.csv".to_string(), 2)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::date_string_mutations::*;
 use crate::errors::*;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeDelta};
+use once_cell::sync::Lazy;
 use phf::phf_map;
 use regex::Regex;
 use serde::Deserialize;
@@ -14,6 +15,37 @@ static FUNCTION_MAP: phf::Map<&'static str, fn(&str) -> String> = phf_map! {
     "append_1970_to_the_left" => append_1970_to_the_left,
 };
 
+// An offset written right after the matched timestamp, ex 2023-01-01T01:00:00-0400. Structured values can also have a space
+// before it, ex 2023-01-01 01:00:00 +02:00 or 2023-01-01 01:00:00 UTC
+static TRAILING_OFFSET_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(Z\b|UTC\b|[+-]\d{2}:?\d{2}\b)").unwrap());
+
+/// Parses offsets like Z, UTC, +02:00, -0400 and +05
+pub fn parse_utc_offset(input: &str) -> Option<FixedOffset> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("Z") || input.eq_ignore_ascii_case("UTC") {
+        return FixedOffset::east_opt(0);
+    }
+    let sign = match input.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = input[1..].replace(':', "");
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes): (i32, i32) = match digits.len() {
+        1 | 2 => (digits.parse().ok()?, 0),
+        4 => (digits[..2].parse().ok()?, digits[2..].parse().ok()?),
+        _ => return None,
+    };
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
 #[derive(Deserialize)]
 pub struct RawDateRegex {
     pub pretty_format: String,
@@ -39,15 +71,36 @@ impl DateRegex {
             function_to_call: None, // Choosing to not allow users to supply the function to call part
         }
     }
+    /// The timestamp converted to UTC. Timestamps without an offset are treated as already being in UTC
     pub fn get_timestamp_object_from_string_contianing_date(
         &self,
         string_to_extract_from: String,
     ) -> Result<Option<NaiveDateTime>> {
+        Ok(self
+            .get_local_timestamp_and_offset_from_string_containing_date(
+                string_to_extract_from,
+                true,
+            )?
+            .map(|(local_datetime, offset)| match offset {
+                Some(offset) => {
+                    local_datetime - TimeDelta::seconds(offset.local_minus_utc() as i64)
+                }
+                None => local_datetime,
+            }))
+    }
+
+    /// The timestamp as written, along with its offset if the format has one or one is written right after the match.
+    /// Whole log lines shouldn't allow a space before the offset, since the next word of the message could be a number like -1234
+    pub fn get_local_timestamp_and_offset_from_string_containing_date(
+        &self,
+        string_to_extract_from: String,
+        offset_can_follow_a_space: bool,
+    ) -> Result<Option<(NaiveDateTime, Option<FixedOffset>)>> {
         if let Some(captures) = self.regex.captures(&string_to_extract_from) {
             // Get the matched string (the datetime)
-            if let Some(datetime_str) = captures.get(0) {
+            if let Some(datetime_match) = captures.get(0) {
                 let datetime_str: String = match &self.function_to_call {
-                    None => datetime_str.as_str().to_string(),
+                    None => datetime_match.as_str().to_string(),
                     Some(function_to_call) => {
                         let actual_mutation_function = FUNCTION_MAP
                             .get(function_to_call)
@@ -56,10 +109,26 @@ impl DateRegex {
                                 LavaErrorLevel::Critical,
                             ))?;
 
-                        actual_mutation_function(datetime_str.as_str())
+                        actual_mutation_function(datetime_match.as_str())
                     }
                 };
 
+                if self.strftime_format_has_offset() {
+                    let parsed_datetime =
+                        DateTime::parse_from_str(&datetime_str, &self.strftime_format).map_err(
+                            |e| {
+                                LavaError::new(
+                                    format!("DateTime::parse_from_str was unable to the parse timestamp because {e}"),
+                                    LavaErrorLevel::Critical,
+                                )
+                            },
+                        )?;
+                    return Ok(Some((
+                        parsed_datetime.naive_local(),
+                        Some(*parsed_datetime.offset()),
+                    )));
+                }
+
                 // Now, parse the extracted datetime string into NaiveDateTime using the strftime_format
                 let parsed_datetime =
                     NaiveDateTime::parse_from_str(&datetime_str, &self.strftime_format).map_err(
@@ -70,11 +139,35 @@ impl DateRegex {
                             )
                         },
                     )?;
-                return Ok(Some(parsed_datetime));
+                let after_match = &string_to_extract_from[datetime_match.end()..];
+                let trailing_offset = TRAILING_OFFSET_REGEX
+                    .find(match after_match.strip_prefix(' ') {
+                        Some(after_space) if offset_can_follow_a_space => after_space,
+                        _ => after_match,
+                    })
+                    .and_then(|offset_match| parse_utc_offset(offset_match.as_str()));
+                let offset = match trailing_offset {
+                    Some(offset) => Some(offset),
+                    None if self.strftime_format_is_utc() => FixedOffset::east_opt(0),
+                    None => None,
+                };
+                return Ok(Some((parsed_datetime, offset)));
             }
         }
         return Ok(None); // regex did not capture any portion of the string
     }
+
+    fn strftime_format_has_offset(&self) -> bool {
+        ["%z", "%:z", "%::z", "%:::z", "%#z"]
+            .iter()
+            .any(|specifier| self.strftime_format.contains(specifier))
+    }
+
+    /// Formats that end in a literal Z and epoch times are always UTC, so an assumed timezone does not apply to them
+    fn strftime_format_is_utc(&self) -> bool {
+        self.strftime_format.ends_with('Z') || self.strftime_format.contains("%s")
+    }
+
     pub fn string_contains_date(&self, string_to_verify: &str) -> bool {
         if self.regex.is_match(&string_to_verify) {
             return true;
@@ -19,3 +19,125 @@ fn test_date_regex_from_raw() {
         .expect("Failed to get timestamp");
     assert_eq!(expected_timestamp, actual_timestamp);
 }
+
+#[test]
+fn test_trailing_offset_is_normalized_to_utc() {
+    let re = DateRegex::new_from_raw_date_regex(RawDateRegex {
+        pretty_format: "YYYY-MM-DDTHH:MM:SS".to_string(),
+        strftime_format: "%Y-%m-%dT%H:%M:%S".to_string(),
+        regex: "\\d{4}-\\d{2}-\\d{2}T\\d{2}:\\d{2}:\\d{2}".to_string(),
+        function_to_call: None,
+    });
+    let actual_timestamp = re
+        .get_timestamp_object_from_string_contianing_date("2023-01-01T22:30:00-0400".to_string())
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        actual_timestamp,
+        NaiveDateTime::parse_from_str("2023-01-02 02:30:00", "%Y-%m-%d %H:%M:%S").unwrap()
+    );
+}
+
+#[test]
+fn test_trailing_utc_after_a_space_is_an_offset() {
+    let re = DateRegex::new_from_raw_date_regex(RawDateRegex {
+        pretty_format: "YYYY-MM-DD HH:MM:SS".to_string(),
+        strftime_format: "%Y-%m-%d %H:%M:%S".to_string(),
+        regex: "\\d{4}-\\d{2}-\\d{2} \\d{2}:\\d{2}:\\d{2}".to_string(),
+        function_to_call: None,
+    });
+    for input in ["2023-01-01 01:00:00 UTC", "2023-01-01 01:00:00 Z"] {
+        let (_, offset) = re
+            .get_local_timestamp_and_offset_from_string_containing_date(input.to_string(), true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(offset, FixedOffset::east_opt(0));
+    }
+}
+
+#[test]
+fn test_offset_is_only_returned_when_written() {
+    let re = DateRegex::new_from_raw_date_regex(RawDateRegex {
+        pretty_format: "YYYY-MM-DD HH:MM:SS".to_string(),
+        strftime_format: "%Y-%m-%d %H:%M:%S".to_string(),
+        regex: "\\d{4}-\\d{2}-\\d{2} \\d{2}:\\d{2}:\\d{2}".to_string(),
+        function_to_call: None,
+    });
+
+    let (naive, offset) = re
+        .get_local_timestamp_and_offset_from_string_containing_date(
+            "2023-01-01 01:00:00".to_string(),
+            true,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(naive.to_string(), "2023-01-01 01:00:00");
+    assert_eq!(offset, None);
+
+    let (local, offset) = re
+        .get_local_timestamp_and_offset_from_string_containing_date(
+            "2023-01-01 01:00:00 +02:00".to_string(),
+            true,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(local.to_string(), "2023-01-01 01:00:00");
+    assert_eq!(offset, FixedOffset::east_opt(7200));
+}
+
+#[test]
+fn test_offset_after_a_space_in_a_line_is_part_of_the_message() {
+    let re = DateRegex::new_from_raw_date_regex(RawDateRegex {
+        pretty_format: "YYYY-MM-DD HH:MM:SS".to_string(),
+        strftime_format: "%Y-%m-%d %H:%M:%S".to_string(),
+        regex: "\\d{4}-\\d{2}-\\d{2} \\d{2}:\\d{2}:\\d{2}".to_string(),
+        function_to_call: None,
+    });
+    for line in [
+        "2023-01-01 01:00:00 -1234 bytes left",
+        "2023-01-01 01:00:00 +0100",
+    ] {
+        let (_, offset) = re
+            .get_local_timestamp_and_offset_from_string_containing_date(line.to_string(), false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(offset, None);
+    }
+
+    let (_, offset) = re
+        .get_local_timestamp_and_offset_from_string_containing_date(
+            "2023-01-01 01:00:00+02:00 user logged in".to_string(),
+            false,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(offset, FixedOffset::east_opt(7200));
+}
+
+#[test]
+fn test_strftime_format_with_offset() {
+    let re = DateRegex::new_from_raw_date_regex(RawDateRegex {
+        pretty_format: "DD/Mon/YYYY:HH:MM:SS +ZZZZ".to_string(),
+        strftime_format: "%d/%b/%Y:%H:%M:%S %z".to_string(),
+        regex: "\\d{2}/[A-Za-z]{3}/\\d{4}:\\d{2}:\\d{2}:\\d{2} [+-]\\d{4}".to_string(),
+        function_to_call: None,
+    });
+    let actual_timestamp = re
+        .get_timestamp_object_from_string_contianing_date(
+            "127.0.0.1 - - [10/Oct/2000:13:55:36 -0700] \"GET /\"".to_string(),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(actual_timestamp.to_string(), "2000-10-10 20:55:36");
+}
+
+#[test]
+fn test_parse_utc_offset() {
+    assert_eq!(parse_utc_offset("UTC"), FixedOffset::east_opt(0));
+    assert_eq!(parse_utc_offset("Z"), FixedOffset::east_opt(0));
+    assert_eq!(parse_utc_offset("+02:00"), FixedOffset::east_opt(7200));
+    assert_eq!(parse_utc_offset("-0430"), FixedOffset::east_opt(-16200));
+    assert_eq!(parse_utc_offset("+5"), FixedOffset::east_opt(18000));
+    assert_eq!(parse_utc_offset("America/New_York"), None);
+    assert_eq!(parse_utc_offset("+02:75"), None);
+}
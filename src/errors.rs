@@ -1,9 +1,10 @@
+use serde::Serialize;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, LavaError>;
 use std::fmt;
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub enum LavaErrorLevel {
     Critical, // This is going to mean you stop processing, anythign else will get propogated at the end, but it will still attempt to keep doing other processsing
     High,
@@ -23,7 +24,7 @@ impl fmt::Display for LavaErrorLevel {
     }
 }
 
-#[derive(Debug, Error, Clone)]
+#[derive(Debug, Error, Clone, Serialize)]
 #[error("{reason}")]
 pub struct LavaError {
     pub reason: String,
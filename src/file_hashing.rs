@@ -0,0 +1,214 @@
+use crate::basic_objects::HashAlgorithm;
+use crate::errors::*;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::io::Read;
+use std::rc::Rc;
+
+pub static HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileHashes {
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+}
+
+/// Runs every selected algorithm over the same bytes
+#[derive(Default)]
+struct MultiHasher {
+    md5: Option<Md5>,
+    sha1: Option<Sha1>,
+    sha256: Option<Sha256>,
+    reached_end: bool,
+}
+
+impl MultiHasher {
+    fn new(hash_algorithms: &[HashAlgorithm]) -> Self {
+        Self {
+            md5: hash_algorithms.contains(&HashAlgorithm::Md5).then(Md5::new),
+            sha1: hash_algorithms
+                .contains(&HashAlgorithm::Sha1)
+                .then(Sha1::new),
+            sha256: hash_algorithms
+                .contains(&HashAlgorithm::Sha256)
+                .then(Sha256::new),
+            reached_end: false,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.md5.is_none() && self.sha1.is_none() && self.sha256.is_none()
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        if let Some(md5) = self.md5.as_mut() {
+            md5.update(bytes);
+        }
+        if let Some(sha1) = self.sha1.as_mut() {
+            sha1.update(bytes);
+        }
+        if let Some(sha256) = self.sha256.as_mut() {
+            sha256.update(bytes);
+        }
+    }
+
+    fn finalize(self) -> FileHashes {
+        FileHashes {
+            md5: self.md5.map(|md5| format!("{:x}", md5.finalize())),
+            sha1: self.sha1.map(|sha1| format!("{:x}", sha1.finalize())),
+            sha256: self.sha256.map(|sha256| format!("{:x}", sha256.finalize())),
+        }
+    }
+}
+
+/// Passes reads through and hashes every byte on the way
+struct HashingReader<R: Read> {
+    inner: R,
+    hasher: Rc<RefCell<MultiHasher>>,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes_read = self.inner.read(buf)?;
+        let mut hasher = self.hasher.borrow_mut();
+        if bytes_read == 0 && !buf.is_empty() {
+            hasher.reached_end = true;
+        } else {
+            hasher.update(&buf[..bytes_read]);
+        }
+        Ok(bytes_read)
+    }
+}
+
+/// Hashes filled in by the reader that feeds the record stream, so the file doesn't have to be read again just to hash it.
+/// The hashes are only used if the stream read all the way to the end of the file
+pub struct StreamHashers {
+    raw: Rc<RefCell<MultiHasher>>,
+    decompressed: Rc<RefCell<MultiHasher>>,
+}
+
+impl StreamHashers {
+    pub fn new(hash_algorithms: &[HashAlgorithm]) -> Self {
+        Self {
+            raw: Rc::new(RefCell::new(MultiHasher::new(hash_algorithms))),
+            decompressed: Rc::new(RefCell::new(MultiHasher::new(
+                &hash_algorithms
+                    .iter()
+                    .copied()
+                    .filter(|hash_algorithm| *hash_algorithm == HashAlgorithm::Sha256)
+                    .collect::<Vec<HashAlgorithm>>(),
+            ))),
+        }
+    }
+
+    /// For the bytes as they are on disk, before any decompression
+    pub fn hash_raw_bytes<R: Read + 'static>(&self, reader: R) -> Box<dyn Read> {
+        wrap_reader(reader, &self.raw)
+    }
+
+    pub fn hash_decompressed_bytes<R: Read + 'static>(&self, reader: R) -> Box<dyn Read> {
+        wrap_reader(reader, &self.decompressed)
+    }
+
+    /// The raw and decompressed hashes, each None if the stream didn't get to the end of it
+    pub fn finish(&self) -> (Option<FileHashes>, Option<FileHashes>) {
+        let finish_hasher = |hasher: &Rc<RefCell<MultiHasher>>| {
+            let hasher = hasher.take();
+            (hasher.reached_end && !hasher.is_empty()).then(|| hasher.finalize())
+        };
+        (finish_hasher(&self.raw), finish_hasher(&self.decompressed))
+    }
+}
+
+fn wrap_reader<R: Read + 'static>(reader: R, hasher: &Rc<RefCell<MultiHasher>>) -> Box<dyn Read> {
+    if hasher.borrow().is_empty() {
+        return Box::new(reader);
+    }
+    Box::new(HashingReader {
+        inner: reader,
+        hasher: Rc::clone(hasher),
+    })
+}
+
+/// Reads the whole reader for when a file couldn't be hashed during the stream
+pub fn get_file_hashes_of_reader<R: Read>(
+    mut reader: R,
+    hash_algorithms: &[HashAlgorithm],
+) -> Result<FileHashes> {
+    let mut hasher = MultiHasher::new(hash_algorithms);
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    loop {
+        let bytes_read = reader.read(&mut buffer).map_err(|e| {
+            LavaError::new(
+                format!("Unable to read bytes during hashing because of {e}"),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod file_hashing_tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    static CONTENT: &[u8] = b"id,name\n1,John\n";
+    static ALL_ALGORITHMS: [HashAlgorithm; 3] = [
+        HashAlgorithm::Md5,
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Sha256,
+    ];
+
+    #[test]
+    fn hashes_match_known_values() {
+        let file_hashes = get_file_hashes_of_reader(b"abc".as_slice(), &ALL_ALGORITHMS).unwrap();
+        assert_eq!(
+            file_hashes.md5.as_deref(),
+            Some("900150983cd24fb0d6963f7d28e17f72")
+        );
+        assert_eq!(
+            file_hashes.sha1.as_deref(),
+            Some("a9993e364706816aba3e25717850c26c9cd0d89d")
+        );
+        assert_eq!(
+            file_hashes.sha256.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        let sha256_only =
+            get_file_hashes_of_reader(b"abc".as_slice(), &[HashAlgorithm::Sha256]).unwrap();
+        assert_eq!(sha256_only.md5, None);
+        assert_eq!(sha256_only.sha1, None);
+    }
+
+    #[test]
+    fn stream_hashes_match_a_separate_read() {
+        let stream_hashers = StreamHashers::new(&ALL_ALGORITHMS);
+        let reader = BufReader::new(stream_hashers.hash_raw_bytes(CONTENT));
+        assert_eq!(reader.lines().count(), 2);
+
+        let (raw, decompressed) = stream_hashers.finish();
+        assert_eq!(
+            raw,
+            Some(get_file_hashes_of_reader(CONTENT, &ALL_ALGORITHMS).unwrap())
+        );
+        assert_eq!(decompressed, None);
+    }
+
+    #[test]
+    fn stream_that_stops_early_has_no_hashes() {
+        let stream_hashers = StreamHashers::new(&ALL_ALGORITHMS);
+        let mut reader = BufReader::with_capacity(4, stream_hashers.hash_raw_bytes(CONTENT));
+        let mut first_line = String::new();
+        reader.read_line(&mut first_line).unwrap();
+        drop(reader);
+        assert_eq!(stream_hashers.finish(), (None, None));
+    }
+}
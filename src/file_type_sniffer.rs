@@ -0,0 +1,393 @@
+use crate::basic_objects::*;
+use crate::errors::*;
+use crate::handlers::syslog_handlers::line_is_syslog;
+use crate::handlers::w3c_handlers::line_is_w3c_fields_directive;
+use crate::log_file_readers::open_log_file;
+use serde_json::Value;
+use std::io::Read;
+use std::path::Path;
+
+pub static SNIFF_BUFFER_SIZE: usize = 8192;
+static MAX_LINES_TO_SNIFF: usize = 50;
+static CONFIDENCE_NEEDED_TO_TRUST_CONTENT: f64 = 0.9;
+static EVTX_MAGIC_BYTES: &[u8] = b"ElfFile\0";
+
+pub fn detect_log_type(log_file: &LogFile) -> (LogType, LogTypeDetection) {
+    detect_log_type_from_start_of_file(log_file, read_start_of_file(log_file))
+}
+
+/// For when the start of the file has already been read, like archive members which are read while the archive is listed
+pub fn detect_log_type_from_start_of_file(
+    log_file: &LogFile,
+    start_of_file: Result<Vec<u8>>,
+) -> (LogType, LogTypeDetection) {
+    let extension_hint = get_log_type_from_extension(&log_file.get_inner_path());
+    match start_of_file {
+        Ok(buffer) => decide_log_type(&buffer, extension_hint),
+        Err(e) => {
+            // Can't look inside the file, so the extension is all we have. Errors opening it will be picked up during processing
+            let (log_type, reason) = match extension_hint {
+                Some(log_type) => (
+                    log_type.clone(),
+                    format!(
+                        "Could not read file to sniff content ({}), used the extension",
+                        e
+                    ),
+                ),
+                None => (
+                    LogType::Unstructured,
+                    format!(
+                        "Could not read file to sniff content ({}) and there was no recognized extension",
+                        e
+                    ),
+                ),
+            };
+            (
+                log_type,
+                LogTypeDetection {
+                    confidence: 0.0,
+                    reason,
+                },
+            )
+        }
+    }
+}
+
+fn get_log_type_from_extension(file_path: &Path) -> Option<LogType> {
+    let mut extension = file_path.extension()?.to_string_lossy().to_lowercase();
+    if ["gz", "bz2", "xz", "zst"].contains(&extension.as_str()) {
+        // Look at the extension under the compression one, ex access.csv.gz
+        extension = Path::new(file_path.file_stem()?)
+            .extension()?
+            .to_string_lossy()
+            .to_lowercase();
+    }
+    match extension.as_str() {
+        "csv" => Some(LogType::Csv),
+        "json" | "jsonl" => Some(LogType::Json),
+        "evtx" => Some(LogType::Evtx),
+        _ => None,
+    }
+}
+
+fn read_start_of_file(log_file: &LogFile) -> Result<Vec<u8>> {
+    let reader = open_log_file(log_file)?;
+    let mut buffer = Vec::with_capacity(SNIFF_BUFFER_SIZE);
+    reader
+        .take(SNIFF_BUFFER_SIZE as u64)
+        .read_to_end(&mut buffer)
+        .map_err(|e| {
+            LavaError::new(
+                format!("Unable to read file because of {e}"),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+    Ok(buffer)
+}
+
+pub fn decide_log_type(
+    buffer: &[u8],
+    extension_hint: Option<LogType>,
+) -> (LogType, LogTypeDetection) {
+    let (sniffed_type, confidence, reason) = sniff_buffer(buffer);
+
+    if confidence >= CONFIDENCE_NEEDED_TO_TRUST_CONTENT {
+        return (sniffed_type, LogTypeDetection { confidence, reason });
+    }
+    if let Some(extension_type) = extension_hint {
+        if extension_type == sniffed_type {
+            // Content and extension agree, so that is worth more than either on their own
+            return (
+                sniffed_type,
+                LogTypeDetection {
+                    confidence: (confidence + CONFIDENCE_NEEDED_TO_TRUST_CONTENT) / 2.0,
+                    reason: format!("{}, agrees with the extension", reason),
+                },
+            );
+        }
+        return (
+            extension_type,
+            LogTypeDetection {
+                confidence: 0.5,
+                reason: format!("Content was inconclusive ({}), used the extension", reason),
+            },
+        );
+    }
+    (
+        LogType::Unstructured,
+        LogTypeDetection {
+            confidence: if sniffed_type == LogType::Unstructured {
+                confidence
+            } else {
+                1.0 - confidence
+            },
+            reason: format!(
+                "Content was not consistently structured ({}), treating as unstructured",
+                reason
+            ),
+        },
+    )
+}
+
+fn sniff_buffer(buffer: &[u8]) -> (LogType, f64, String) {
+    if buffer.is_empty() {
+        return (LogType::Unstructured, 0.0, "File was empty".to_string());
+    }
+    if buffer.starts_with(EVTX_MAGIC_BYTES) {
+        return (
+            LogType::Evtx,
+            1.0,
+            "Found the EVTX file header magic bytes".to_string(),
+        );
+    }
+    if buffer.contains(&0u8) {
+        return (
+            LogType::Unstructured,
+            1.0,
+            "Found NUL bytes, content looks binary".to_string(),
+        );
+    }
+
+    let text = String::from_utf8_lossy(buffer);
+    let mut lines: Vec<&str> = text.lines().collect();
+    if buffer.len() == SNIFF_BUFFER_SIZE && lines.len() > 1 {
+        lines.pop(); // the last line was most likely cut off by the end of the buffer
+    }
+    let lines: Vec<&str> = lines
+        .into_iter()
+        .filter(|line| !line.trim().is_empty())
+        .take(MAX_LINES_TO_SNIFF)
+        .collect();
+    if lines.is_empty() {
+        return (
+            LogType::Unstructured,
+            0.0,
+            "No non-empty lines in the start of the file".to_string(),
+        );
+    }
+
+    // The #Fields directive is unambiguous, and the space separated lines under it would otherwise look unstructured
+    if lines.iter().any(|line| line_is_w3c_fields_directive(line)) {
+        return (
+            LogType::W3c,
+            1.0,
+            "Found a W3C extended log #Fields directive".to_string(),
+        );
+    }
+
+    let json_ratio = get_ratio_of_lines_that_are_json_objects(&lines);
+    if json_ratio >= CONFIDENCE_NEEDED_TO_TRUST_CONTENT {
+        return (
+            LogType::Json,
+            json_ratio,
+            format!(
+                "{:.0}% of {} sampled lines parsed as JSON objects",
+                json_ratio * 100.0,
+                lines.len()
+            ),
+        );
+    }
+
+    // Checked before CSV since syslog messages often have commas in them
+    let syslog_ratio = get_ratio_of_lines_that_are_syslog(&lines);
+    if syslog_ratio >= CONFIDENCE_NEEDED_TO_TRUST_CONTENT {
+        return (
+            LogType::Syslog,
+            syslog_ratio,
+            format!(
+                "{:.0}% of {} sampled lines had a syslog header",
+                syslog_ratio * 100.0,
+                lines.len()
+            ),
+        );
+    }
+
+    if let Some((field_count, csv_ratio)) = get_most_common_csv_field_count(&lines)
+        && field_count > 1
+        && lines.len() > 1
+    {
+        // Plenty of plaintext logs have exactly one comma per line (ex "Jan 1, 2024 ..."), so two fields alone isn't enough to be sure
+        let confidence = match field_count {
+            2 => csv_ratio * 0.8,
+            _ => csv_ratio,
+        };
+        return (
+            LogType::Csv,
+            confidence,
+            format!(
+                "{:.0}% of {} sampled lines had {} comma separated fields",
+                csv_ratio * 100.0,
+                lines.len(),
+                field_count
+            ),
+        );
+    }
+
+    if json_ratio > 0.0 {
+        return (
+            LogType::Json,
+            json_ratio,
+            format!(
+                "Only {:.0}% of {} sampled lines parsed as JSON objects",
+                json_ratio * 100.0,
+                lines.len()
+            ),
+        );
+    }
+    (
+        LogType::Unstructured,
+        CONFIDENCE_NEEDED_TO_TRUST_CONTENT,
+        format!(
+            "None of {} sampled lines were JSON objects or had consistent delimiters",
+            lines.len()
+        ),
+    )
+}
+
+fn get_ratio_of_lines_that_are_json_objects(lines: &[&str]) -> f64 {
+    let num_objects = lines
+        .iter()
+        .filter(|line| {
+            matches!(
+                serde_json::from_str::<Value>(line.trim()),
+                Ok(Value::Object(_))
+            )
+        })
+        .count();
+    num_objects as f64 / lines.len() as f64
+}
+
+fn get_ratio_of_lines_that_are_syslog(lines: &[&str]) -> f64 {
+    let num_syslog = lines.iter().filter(|line| line_is_syslog(line)).count();
+    num_syslog as f64 / lines.len() as f64
+}
+
+/// Returns the most common number of fields per line and the ratio of lines that have it
+fn get_most_common_csv_field_count(lines: &[&str]) -> Option<(usize, f64)> {
+    let sample = lines.join("\n");
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .has_headers(false)
+        .from_reader(sample.as_bytes());
+
+    let mut field_counts: Vec<usize> = Vec::new();
+    for result in reader.records() {
+        match result {
+            Ok(record) => field_counts.push(record.len()),
+            Err(_) => field_counts.push(0), // Malformed quoting, counts against it being a CSV
+        }
+    }
+    if field_counts.is_empty() {
+        return None;
+    }
+
+    let mut most_common: (usize, usize) = (0, 0);
+    for field_count in field_counts.iter() {
+        let occurences = field_counts.iter().filter(|n| *n == field_count).count();
+        if occurences > most_common.1 {
+            most_common = (*field_count, occurences);
+        }
+    }
+    Some((
+        most_common.0,
+        most_common.1 as f64 / field_counts.len() as f64,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_jsonl_without_extension() {
+        let data = "{\"a\":1,\"time\":\"2024-01-01T00:00:00Z\"}\n{\"a\":2,\"time\":\"2024-01-01T00:00:01Z\"}\n";
+        let (log_type, detection) = decide_log_type(data.as_bytes(), None);
+        assert_eq!(log_type, LogType::Json);
+        assert_eq!(detection.confidence, 1.0);
+    }
+
+    #[test]
+    fn sniffs_csv_without_extension() {
+        let data = "id,name,date\n1,John,2025-05-09 10:00:00\n2,Jane,2025-05-10 11:00:00\n";
+        let (log_type, detection) = decide_log_type(data.as_bytes(), None);
+        assert_eq!(log_type, LogType::Csv);
+        assert_eq!(detection.confidence, 1.0);
+    }
+
+    #[test]
+    fn sniffs_csv_with_garbage_above_header() {
+        let data = "garbage\nid,name,date\n1,John,2025-05-09 10:00:00\n2,Jane,2025-05-10 11:00:00\n3,Jim,2025-05-10 11:00:00\n4,Jen,2025-05-10 11:00:00\n5,Joe,2025-05-10 11:00:00\n6,Jay,2025-05-10 11:00:00\n7,Jan,2025-05-10 11:00:00\n8,Jon,2025-05-10 11:00:00\n";
+        let (log_type, _) = decide_log_type(data.as_bytes(), None);
+        assert_eq!(log_type, LogType::Csv);
+    }
+
+    #[test]
+    fn sniffs_csv_disguised_as_log_extension() {
+        let data = "id,name,date\n1,John,2025-05-09 10:00:00\n2,Jane,2025-05-10 11:00:00\n";
+        let (log_type, _) = decide_log_type(
+            data.as_bytes(),
+            get_log_type_from_extension(Path::new("export.log")),
+        );
+        assert_eq!(log_type, LogType::Csv);
+    }
+
+    #[test]
+    fn sniffs_unstructured() {
+        let data = "2025-05-09 10:00:00 service started\n2025-05-09 10:00:01 listening on port 80\n2025-05-09 10:00:05 connection from 10.0.0.1\n";
+        let (log_type, _) = decide_log_type(data.as_bytes(), None);
+        assert_eq!(log_type, LogType::Unstructured);
+    }
+
+    #[test]
+    fn sniffs_w3c() {
+        let data = "#Software: Microsoft Internet Information Services 10.0\n#Version: 1.0\n#Date: 2024-01-01 00:00:00\n#Fields: date time s-ip cs-method cs-uri-stem\n2024-01-01 00:00:01 10.0.0.1 GET /index.html\n";
+        let (log_type, detection) = decide_log_type(data.as_bytes(), None);
+        assert_eq!(log_type, LogType::W3c);
+        assert_eq!(detection.confidence, 1.0);
+    }
+
+    #[test]
+    fn sniffs_syslog() {
+        let data = "Oct 18 13:02:11 web01 sshd[812]: Accepted publickey for root, port 22\nOct 18 13:02:12 web01 CRON[900]: (root) CMD (run-parts /etc/cron.hourly)\n<34>1 2025-10-18T13:02:13Z web01 app - - - done, exiting\n";
+        let (log_type, detection) = decide_log_type(data.as_bytes(), None);
+        assert_eq!(log_type, LogType::Syslog);
+        assert_eq!(detection.confidence, 1.0);
+    }
+
+    #[test]
+    fn falls_back_to_extension_when_content_inconclusive() {
+        let data = "{\"a\":1}\n{\"a\":\nthis is not json\n";
+        let (log_type, detection) = decide_log_type(
+            data.as_bytes(),
+            get_log_type_from_extension(Path::new("a.jsonl")),
+        );
+        assert_eq!(log_type, LogType::Json);
+        assert!(detection.confidence < CONFIDENCE_NEEDED_TO_TRUST_CONTENT);
+    }
+
+    #[test]
+    fn looks_under_compression_extension() {
+        assert_eq!(
+            get_log_type_from_extension(Path::new("access.csv.gz")),
+            Some(LogType::Csv)
+        );
+        assert_eq!(get_log_type_from_extension(Path::new("syslog.2.gz")), None);
+    }
+
+    #[test]
+    fn sniffs_evtx_header() {
+        let mut data = b"ElfFile\0".to_vec();
+        data.extend([0u8; 120]);
+        let (log_type, detection) = decide_log_type(&data, None);
+        assert_eq!(log_type, LogType::Evtx);
+        assert_eq!(detection.confidence, 1.0);
+    }
+
+    #[test]
+    fn empty_file_uses_extension() {
+        let (log_type, detection) =
+            decide_log_type(b"", get_log_type_from_extension(Path::new("a.csv")));
+        assert_eq!(log_type, LogType::Csv);
+        assert!(detection.reason.contains("used the extension"));
+    }
+}
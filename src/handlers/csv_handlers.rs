@@ -1,66 +1,35 @@
 use crate::basic_objects::*;
 use crate::errors::*;
+use crate::file_hashing::StreamHashers;
 use crate::helpers::{get_file_stem, print_if_verbose_mode_on};
+use crate::log_file_readers::{open_log_file, open_log_file_with_hashing};
 use crate::processing_objects::*;
 use chrono::NaiveDateTime;
 use csv::Reader;
 use csv::ReaderBuilder;
 use csv::StringRecord;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::io::BufRead;
 #[cfg(test)]
 mod csv_handler_tests;
 
-pub fn get_header_info(log_file: &LogFile) -> Result<HeaderInfo> {
-    let file = File::open(&log_file.file_path).map_err(|e| {
-        LavaError::new(
-            format!("Unable to read csv file because of {e}"),
-            LavaErrorLevel::Critical,
-        )
-    })?;
-    let mut reader = BufReader::new(file);
-
-    get_header_info_functionality(&mut reader)
-}
-
-pub fn get_header_info_functionality<R: BufRead + Seek>(reader: &mut R) -> Result<HeaderInfo> {
-    let header_row = get_index_of_header(reader)?;
-    reader.seek(SeekFrom::Start(0)).map_err(|e| {
-        LavaError::new(
-            format!("Could not seek back to file header because of {}", e),
-            LavaErrorLevel::Critical,
-        )
-    })?;
-
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .flexible(true)
-        .has_headers(false)
-        .from_reader(reader);
+// A column holding nothing but the offset, which is only combined with the date and time columns before it when it looks exactly like this
+static UTC_OFFSET_COLUMN_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:Z|UTC|[+-]\d{2}:?\d{2})$").unwrap());
 
-    let record = csv_reader
-        .records()
-        .nth(header_row)
-        .ok_or_else(|| LavaError::new("No input parameter found.", LavaErrorLevel::Critical))?
-        .map_err(|e| {
-            LavaError::new(
-                format!("Failed to parse CSV record at header row because of {}", e),
-                LavaErrorLevel::Critical,
-            )
-        })?;
-    Ok(HeaderInfo {
-        first_data_row: header_row + 1,
-        headers: record,
-    })
+pub fn get_header_info(log_file: &LogFile) -> Result<HeaderInfo> {
+    get_header_info_functionality(&mut open_log_file(log_file)?)
 }
 
-pub fn get_index_of_header<R: BufRead>(reader: &mut R) -> Result<usize> {
+/// The header is always one of the records read to find it, so the file only has to be read once and doesn't need to seek
+pub fn get_header_info_functionality<R: BufRead>(reader: &mut R) -> Result<HeaderInfo> {
     let mut rdr = csv::ReaderBuilder::new()
         .flexible(true)
         .has_headers(false)
         .from_reader(reader);
 
-    let mut field_counts: Vec<(usize, usize)> = Vec::new();
-
+    let mut first_records: Vec<StringRecord> = Vec::new();
     for (index, result) in rdr.records().enumerate().take(7) {
         let record: StringRecord = result.map_err(|e| {
             LavaError::new(
@@ -68,19 +37,30 @@ pub fn get_index_of_header<R: BufRead>(reader: &mut R) -> Result<usize> {
                 LavaErrorLevel::Critical,
             )
         })?;
-        field_counts.push((index, record.len()));
+        first_records.push(record);
     }
-    // println!("{:?}", field_counts);
 
-    let (_, expected_field_count) = field_counts.last().ok_or_else(|| {
-        LavaError::new(
-            "No records found in first 7 lines.",
-            LavaErrorLevel::Critical,
-        )
-    })?;
+    let header_row = get_index_of_header(&first_records)?;
+    Ok(HeaderInfo {
+        first_data_row: header_row + 1,
+        headers: first_records.swap_remove(header_row),
+    })
+}
+
+pub fn get_index_of_header(first_records: &[StringRecord]) -> Result<usize> {
+    let expected_field_count =
+        first_records
+            .last()
+            .map(|record| record.len())
+            .ok_or_else(|| {
+                LavaError::new(
+                    "No records found in first 7 lines.",
+                    LavaErrorLevel::Critical,
+                )
+            })?;
 
-    for (index, field_count) in field_counts.iter().rev() {
-        if field_count < expected_field_count {
+    for (index, record) in first_records.iter().enumerate().rev() {
+        if record.len() < expected_field_count {
             return Ok(index + 1);
         }
     }
@@ -89,15 +69,8 @@ pub fn get_index_of_header<R: BufRead>(reader: &mut R) -> Result<usize> {
 
 pub fn get_reader_from_certain_index(
     header_index: usize,
-    log_file: &LogFile,
-) -> Result<Reader<BufReader<File>>> {
-    let file = File::open(&log_file.file_path).map_err(|e| {
-        LavaError::new(
-            format!("Unable to read csv file because of {e}"),
-            LavaErrorLevel::Critical,
-        )
-    })?;
-    let mut buf_reader = BufReader::new(file);
+    mut buf_reader: Box<dyn BufRead>,
+) -> Result<Reader<Box<dyn BufRead>>> {
     for _ in 0..header_index {
         let mut dummy = String::new();
         buf_reader.read_line(&mut dummy).map_err(|e| {
@@ -120,7 +93,8 @@ pub fn try_to_get_timestamp_hit_for_csv(
     header_info: HeaderInfo,
 ) -> Result<Option<IdentifiedTimeInformation>> {
     print_if_verbose_mode_on(format!("Trying to get hit for {:?}", log_file.file_path));
-    let mut reader = get_reader_from_certain_index(header_info.first_data_row, log_file)?;
+    let mut reader =
+        get_reader_from_certain_index(header_info.first_data_row, open_log_file(log_file)?)?;
 
     let record: csv::StringRecord = reader
         .records()
@@ -146,7 +120,26 @@ pub fn try_to_get_timestamp_hit_for_csv_functionality(
     record: csv::StringRecord,
     execution_settings: &ExecutionSettings,
 ) -> Result<Option<IdentifiedTimeInformation>> {
-    if let Some(field_to_use) = &execution_settings.timestamp_field {
+    if let Some(field_to_use) = &execution_settings.timestamp_field
+        && field_to_use.contains('+')
+    {
+        let mut column_indices: Vec<usize> = Vec::new();
+        for field_name in field_to_use.split('+') {
+            match headers
+                .iter()
+                .position(|field| field.trim() == field_name.trim())
+            {
+                Some(index) => column_indices.push(index),
+                None => return Ok(None),
+            }
+        }
+        return Ok(try_to_get_timestamp_hit_for_composite_columns(
+            &headers,
+            &record,
+            column_indices,
+            execution_settings,
+        ));
+    } else if let Some(field_to_use) = &execution_settings.timestamp_field {
         for (i, field) in headers.iter().enumerate() {
             if field.trim() == field_to_use {
                 for date_regex in execution_settings.regexes.iter() {
@@ -159,7 +152,9 @@ pub fn try_to_get_timestamp_hit_for_csv_functionality(
                         return Ok(Some(IdentifiedTimeInformation {
                             column_name: Some(headers.get(i).unwrap().to_string()),
                             column_index: Some(i),
+                            composite_column_indices: Vec::new(),
                             direction: None,
+                            assumed_timezone: execution_settings.assumed_timezone,
                             regex_info: date_regex.clone(),
                         }));
                     }
@@ -173,22 +168,70 @@ pub fn try_to_get_timestamp_hit_for_csv_functionality(
                     return Ok(Some(IdentifiedTimeInformation {
                         column_name: Some(headers.get(i).unwrap().to_string()),
                         column_index: Some(i),
+                        composite_column_indices: Vec::new(),
                         direction: None,
+                        assumed_timezone: execution_settings.assumed_timezone,
                         regex_info: date_regex.clone(),
                     }));
                 }
             }
         }
+        // No single column had a full timestamp, so try exports that split it up like Date,Time or Date,Time,Offset
+        for i in 1..record.len() {
+            let mut column_indices = vec![i - 1, i];
+            if record
+                .get(i + 1)
+                .is_some_and(|value| UTC_OFFSET_COLUMN_REGEX.is_match(value.trim()))
+            {
+                column_indices.push(i + 1);
+            }
+            if let Some(timestamp_hit) = try_to_get_timestamp_hit_for_composite_columns(
+                &headers,
+                &record,
+                column_indices,
+                execution_settings,
+            ) {
+                return Ok(Some(timestamp_hit));
+            }
+        }
     }
     Ok(None)
 }
 
+fn try_to_get_timestamp_hit_for_composite_columns(
+    headers: &csv::StringRecord,
+    record: &csv::StringRecord,
+    column_indices: Vec<usize>,
+    execution_settings: &ExecutionSettings,
+) -> Option<IdentifiedTimeInformation> {
+    let column_names: Option<Vec<&str>> = column_indices
+        .iter()
+        .map(|index| headers.get(*index).map(|header| header.trim()))
+        .collect();
+    let mut timestamp_hit = IdentifiedTimeInformation {
+        column_name: Some(column_names?.join("+")),
+        column_index: column_indices.first().copied(),
+        composite_column_indices: column_indices,
+        direction: None,
+        assumed_timezone: execution_settings.assumed_timezone,
+        regex_info: execution_settings.regexes.first()?.clone(),
+    };
+    let joined_value = timestamp_hit.get_timestamp_string_from_record(record)?;
+    let date_regex = execution_settings
+        .regexes
+        .iter()
+        .find(|date_regex| date_regex.string_contains_date(&joined_value))?;
+    timestamp_hit.regex_info = date_regex.clone();
+    Some(timestamp_hit)
+}
+
 pub fn set_time_direction_by_scanning_csv_file(
     log_file: &LogFile,
     timestamp_hit: &mut IdentifiedTimeInformation,
     header_info: HeaderInfo,
 ) -> Result<()> {
-    let mut rdr = get_reader_from_certain_index(header_info.first_data_row, log_file)?;
+    let mut rdr =
+        get_reader_from_certain_index(header_info.first_data_row, open_log_file(log_file)?)?;
     let mut direction_checker = TimeDirectionChecker::default();
     for result in rdr.records() {
         // I think I should just include the index in the timestamp hit
@@ -198,21 +241,20 @@ pub fn set_time_direction_by_scanning_csv_file(
                 LavaErrorLevel::Critical,
             )
         })?;
-        let value = record
-            .get(timestamp_hit.column_index.unwrap())
+        let value = timestamp_hit
+            .get_timestamp_string_from_record(&record)
             .ok_or_else(|| {
                 LavaError::new("Index of date field not found", LavaErrorLevel::Critical)
-            })?; // unwrap is safe here because for CSVs, there will always be a column index
+            })?;
 
         let current_datetime: NaiveDateTime = timestamp_hit
-            .regex_info
-            .get_timestamp_object_from_string_contianing_date(value.to_string())?
+            .get_utc_timestamp_from_string(value)?
             .ok_or_else(|| {
-                LavaError::new(
-                    "No timestamp found when scanning for direction.",
-                    LavaErrorLevel::Critical,
-                )
-            })?;
+            LavaError::new(
+                "No timestamp found when scanning for direction.",
+                LavaErrorLevel::Critical,
+            )
+        })?;
 
         if let Some(direction) = direction_checker.process_timestamp(current_datetime) {
             timestamp_hit.direction = Some(direction);
@@ -227,6 +269,7 @@ pub fn stream_csv_file(
     timestamp_hit: &Option<IdentifiedTimeInformation>,
     execution_settings: &ExecutionSettings,
     header_info: HeaderInfo,
+    stream_hashers: &StreamHashers,
 ) -> Result<LogRecordProcessor> {
     // not sure we want to include the whole hashset in this? Maybe only inlcude results
     let mut processing_object = LogRecordProcessor::new(
@@ -236,7 +279,10 @@ pub fn stream_csv_file(
         Some(header_info.headers),
     );
 
-    let mut rdr = get_reader_from_certain_index(header_info.first_data_row, log_file)?;
+    let mut rdr = get_reader_from_certain_index(
+        header_info.first_data_row,
+        open_log_file_with_hashing(log_file, stream_hashers)?,
+    )?;
     for (index, result) in rdr.records().enumerate() {
         // I think I should just include the index in the timestamp hit
         let record = result.map_err(|e| {
@@ -248,14 +294,12 @@ pub fn stream_csv_file(
         let current_datetime = match timestamp_hit {
             None => None,
             Some(timestamp_hit) => {
-                let value = record
-                    .get(timestamp_hit.column_index.unwrap())
+                let value = timestamp_hit
+                    .get_timestamp_string_from_record(&record)
                     .ok_or_else(|| {
                         LavaError::new("Index of date field not found", LavaErrorLevel::Critical)
                     })?;
-                timestamp_hit
-                    .regex_info
-                    .get_timestamp_object_from_string_contianing_date(value.to_string())?
+                timestamp_hit.get_utc_timestamp_from_string(value)?
             }
         };
 
@@ -1,11 +1,41 @@
 use super::*;
+use crate::alert_config::AlertConfig;
 use crate::basic_objects::HeaderInfo;
 use crate::date_regex::DateRegex;
 use csv::StringRecord;
 use regex::Regex;
-use std::io::Cursor;
+use std::io::{BufReader, Cursor, Write};
 use std::path::PathBuf;
 
+#[test]
+fn test_get_header_info_from_gzipped_file() {
+    let data = "\
+        Exported by some tool\n\
+        id,name,date\n\
+        1,John,2025-05-09 10:00:00\n\
+        2,Jane,2025-05-10 11:00:00\n";
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data.as_bytes()).unwrap();
+    let mut temp_file = tempfile::Builder::new()
+        .suffix(".csv.gz")
+        .tempfile()
+        .unwrap();
+    temp_file.write_all(&encoder.finish().unwrap()).unwrap();
+    let log_file = LogFile {
+        log_type: LogType::Csv,
+        file_path: temp_file.path().to_path_buf(),
+        type_detection: None,
+        archive_member: None,
+    };
+
+    let expected = HeaderInfo {
+        first_data_row: 2,
+        headers: StringRecord::from(vec!["id", "name", "date"]),
+    };
+
+    assert_eq!(expected, get_header_info(&log_file).unwrap());
+}
+
 #[test]
 fn test_get_header_info_on_row_0() {
     let data = "\
@@ -201,6 +231,19 @@ fn get_csv_timestamp_hit_finds_valid_timestamp() {
         multipart_mode: false,
         verbose_mode: true,
         actually_write_to_files: false,
+        syslog_year: None,
+        assumed_timezone: None,
+        out_of_order_tolerance: None,
+        num_top_time_gaps: 0,
+        volume_window: VolumeWindow::Hour,
+        time_gap_alert_method: TimeGapAlertMethod::StandardDeviations,
+        alert_config: AlertConfig::default(),
+        json_report_path: None,
+        write_html_report: false,
+        hash_algorithms: vec![HashAlgorithm::Sha256],
+        near_dupe_settings: None,
+        cross_file_dupes: false,
+        dupe_detection_mode: DupeDetectionMode::InMemory,
     };
     let result =
         try_to_get_timestamp_hit_for_csv_functionality(headers.clone(), record.clone(), &test_args)
@@ -231,6 +274,19 @@ fn get_csv_timestamp_hit_does_not_find_valid_timestamp() {
         multipart_mode: false,
         verbose_mode: true,
         actually_write_to_files: false,
+        syslog_year: None,
+        assumed_timezone: None,
+        out_of_order_tolerance: None,
+        num_top_time_gaps: 0,
+        volume_window: VolumeWindow::Hour,
+        time_gap_alert_method: TimeGapAlertMethod::StandardDeviations,
+        alert_config: AlertConfig::default(),
+        json_report_path: None,
+        write_html_report: false,
+        hash_algorithms: vec![HashAlgorithm::Sha256],
+        near_dupe_settings: None,
+        cross_file_dupes: false,
+        dupe_detection_mode: DupeDetectionMode::InMemory,
     };
     let result =
         try_to_get_timestamp_hit_for_csv_functionality(headers.clone(), record.clone(), &test_args);
@@ -262,6 +318,19 @@ fn get_csv_timestamp_hit_finds_valid_different_timestamp() {
         multipart_mode: false,
         verbose_mode: true,
         actually_write_to_files: false,
+        syslog_year: None,
+        assumed_timezone: None,
+        out_of_order_tolerance: None,
+        num_top_time_gaps: 0,
+        volume_window: VolumeWindow::Hour,
+        time_gap_alert_method: TimeGapAlertMethod::StandardDeviations,
+        alert_config: AlertConfig::default(),
+        json_report_path: None,
+        write_html_report: false,
+        hash_algorithms: vec![HashAlgorithm::Sha256],
+        near_dupe_settings: None,
+        cross_file_dupes: false,
+        dupe_detection_mode: DupeDetectionMode::InMemory,
     };
     let result =
         try_to_get_timestamp_hit_for_csv_functionality(headers.clone(), record.clone(), &test_args)
@@ -272,3 +341,85 @@ fn get_csv_timestamp_hit_finds_valid_different_timestamp() {
     assert_eq!(result.column_index, Some(3));
     assert_eq!(result.regex_info.pretty_format, "YYYY-MM-DD HH:MM:SS");
 }
+
+#[test]
+fn get_csv_timestamp_hit_finds_adjacent_date_and_time_columns() {
+    let headers = StringRecord::from(vec!["id", "Date", "Time", "message"]);
+    let record = StringRecord::from(vec!["1", "2024-05-10", "10:23:00", "test log"]);
+
+    let test_args = ExecutionSettings::create_integration_test_object(None, false);
+    let result =
+        try_to_get_timestamp_hit_for_csv_functionality(headers, record.clone(), &test_args)
+            .unwrap()
+            .unwrap();
+
+    assert_eq!(result.column_name, Some("Date+Time".to_string()));
+    assert_eq!(result.composite_column_indices, vec![1, 2]);
+    assert_eq!(
+        result.get_timestamp_string_from_record(&record),
+        Some("2024-05-10 10:23:00".to_string())
+    );
+}
+
+#[test]
+fn get_csv_timestamp_hit_uses_provided_composite_columns() {
+    let headers = StringRecord::from(vec!["Time", "message", "Date"]);
+    let record = StringRecord::from(vec!["10:23:00", "test log", "2024-05-10"]);
+
+    let test_args =
+        ExecutionSettings::create_integration_test_object(Some("Date+Time".to_string()), false);
+    let result =
+        try_to_get_timestamp_hit_for_csv_functionality(headers, record.clone(), &test_args)
+            .unwrap()
+            .unwrap();
+
+    assert_eq!(result.column_name, Some("Date+Time".to_string()));
+    assert_eq!(result.column_index, Some(2));
+    assert_eq!(result.composite_column_indices, vec![2, 0]);
+    assert_eq!(result.regex_info.pretty_format, "YYYY-MM-DD HH:MM:SS");
+}
+
+#[test]
+fn get_csv_timestamp_hit_provided_composite_column_missing() {
+    let headers = StringRecord::from(vec!["Date", "Time"]);
+    let record = StringRecord::from(vec!["2024-05-10", "10:23:00"]);
+
+    let test_args =
+        ExecutionSettings::create_integration_test_object(Some("Date+Clock".to_string()), false);
+    let result = try_to_get_timestamp_hit_for_csv_functionality(headers, record, &test_args);
+
+    assert!(result.unwrap().is_none());
+}
+
+#[test]
+fn get_csv_timestamp_hit_combines_date_time_and_offset_columns() {
+    let headers = StringRecord::from(vec!["Date", "Time", "TZ", "message"]);
+    let record = StringRecord::from(vec!["2024-05-10", "10:23:00", "-0400", "test log"]);
+
+    let test_args = ExecutionSettings::create_integration_test_object(None, false);
+    let result =
+        try_to_get_timestamp_hit_for_csv_functionality(headers, record.clone(), &test_args)
+            .unwrap()
+            .unwrap();
+
+    assert_eq!(result.column_name, Some("Date+Time+TZ".to_string()));
+    assert_eq!(result.composite_column_indices, vec![0, 1, 2]);
+    let value = result.get_timestamp_string_from_record(&record).unwrap();
+    assert_eq!(
+        result.get_utc_timestamp_from_string(value).unwrap(),
+        NaiveDateTime::parse_from_str("2024-05-10 14:23:00", "%Y-%m-%d %H:%M:%S").ok()
+    );
+}
+
+#[test]
+fn get_csv_timestamp_hit_ignores_column_after_time_that_is_not_an_offset() {
+    let headers = StringRecord::from(vec!["Date", "Time", "Delta"]);
+    let record = StringRecord::from(vec!["2024-05-10", "10:23:00", "-1"]);
+
+    let test_args = ExecutionSettings::create_integration_test_object(None, false);
+    let result = try_to_get_timestamp_hit_for_csv_functionality(headers, record, &test_args)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(result.composite_column_indices, vec![0, 1]);
+}
@@ -0,0 +1,239 @@
+use crate::basic_objects::*;
+use crate::errors::*;
+use crate::handlers::json_handlers::convert_arrow_path_to_json_pointer;
+use crate::helpers::get_file_stem;
+use crate::log_file_readers::{get_log_file_compression_type, open_log_file};
+use crate::processing_objects::*;
+use csv::StringRecord;
+use evtx::err::EvtxError;
+use evtx::{EvtxParser, SerializedEvtxRecord};
+use serde_json::Value;
+use std::io::Read;
+
+pub static EVTX_TIME_CREATED_POINTER: &str = "/Event/System/TimeCreated/#attributes/SystemTime";
+static EVTX_EVENT_ID_POINTER: &str = "/Event/System/EventID";
+
+type EvtxRecordResult = std::result::Result<SerializedEvtxRecord<Value>, EvtxError>;
+
+/// Runs the callback on every record in order, stopping early if the callback returns false
+fn for_each_evtx_record<F>(log_file: &LogFile, mut callback: F) -> Result<()>
+where
+    F: FnMut(usize, EvtxRecordResult) -> Result<bool>,
+{
+    if log_file.archive_member.is_none() && get_log_file_compression_type(log_file)?.is_none() {
+        let mut parser = EvtxParser::from_path(&log_file.file_path).map_err(|e| {
+            LavaError::new(
+                format!("Unable to open EVTX file because of {e}"),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        for (index, record) in parser.records_json_value().enumerate() {
+            if !callback(index, record)? {
+                break;
+            }
+        }
+    } else {
+        // The parser needs to seek between chunks, so compressed files and archive members are read into memory first
+        let mut content: Vec<u8> = Vec::new();
+        open_log_file(log_file)?
+            .read_to_end(&mut content)
+            .map_err(|e| {
+                LavaError::new(
+                    format!("Unable to read EVTX file because of {e}"),
+                    LavaErrorLevel::Critical,
+                )
+            })?;
+        let mut parser = EvtxParser::from_buffer(content).map_err(|e| {
+            LavaError::new(
+                format!("Unable to open EVTX file because of {e}"),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        for (index, record) in parser.records_json_value().enumerate() {
+            if !callback(index, record)? {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn get_timestamp_pointer(execution_settings: &ExecutionSettings) -> String {
+    match &execution_settings.timestamp_field {
+        Some(field_to_use) => convert_arrow_path_to_json_pointer(field_to_use),
+        None => EVTX_TIME_CREATED_POINTER.to_string(),
+    }
+}
+
+fn get_string_at_pointer(event: &Value, pointer: &str) -> Option<String> {
+    match event.pointer(pointer)? {
+        Value::String(string) => Some(string.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// EventID is a plain number unless the event has Qualifiers, in which case it is an object holding the number in #text
+pub fn get_event_id(event: &Value) -> Option<u64> {
+    match event.pointer(EVTX_EVENT_ID_POINTER)? {
+        Value::Number(number) => number.as_u64(),
+        Value::Object(map) => match map.get("#text")? {
+            Value::Number(number) => number.as_u64(),
+            Value::String(string) => string.parse().ok(),
+            _ => None,
+        },
+        Value::String(string) => string.parse().ok(),
+        _ => None,
+    }
+}
+
+pub fn try_to_get_timestamp_hit_for_evtx(
+    log_file: &LogFile,
+    execution_settings: &ExecutionSettings,
+) -> Result<Option<IdentifiedTimeInformation>> {
+    let timestamp_pointer = get_timestamp_pointer(execution_settings);
+    let mut timestamp_hit: Option<IdentifiedTimeInformation> = None;
+    for_each_evtx_record(log_file, |_, record_result| {
+        let record = match record_result {
+            Ok(record) => record,
+            Err(_) => return Ok(true), // Keep looking, the error will be recorded while streaming
+        };
+        if let Some(timestamp_string) = get_string_at_pointer(&record.data, &timestamp_pointer) {
+            for date_regex in execution_settings.regexes.iter() {
+                if date_regex.string_contains_date(&timestamp_string) {
+                    timestamp_hit = Some(IdentifiedTimeInformation {
+                        column_name: Some(timestamp_pointer.clone()),
+                        column_index: None,
+                        composite_column_indices: Vec::new(),
+                        direction: None,
+                        assumed_timezone: execution_settings.assumed_timezone,
+                        regex_info: date_regex.clone(),
+                    });
+                    break;
+                }
+            }
+        }
+        Ok(false)
+    })?;
+    Ok(timestamp_hit)
+}
+
+pub fn set_time_direction_by_scanning_evtx_file(
+    log_file: &LogFile,
+    timestamp_hit: &mut IdentifiedTimeInformation,
+) -> Result<()> {
+    let timestamp_pointer = timestamp_hit.column_name.clone().ok_or_else(|| {
+        LavaError::new(
+            "No JSON path to timestamp field found during scanning for direction phase.",
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    let mut direction_checker = TimeDirectionChecker::default();
+    let mut direction: Option<TimeDirection> = None;
+    for_each_evtx_record(log_file, |_, record_result| {
+        let record = match record_result {
+            Ok(record) => record,
+            Err(_) => return Ok(true),
+        };
+        if let Some(timestamp_string) = get_string_at_pointer(&record.data, &timestamp_pointer)
+            && let Some(current_datetime) =
+                timestamp_hit.get_utc_timestamp_from_string(timestamp_string)?
+        {
+            direction = direction_checker.process_timestamp(current_datetime);
+        }
+        Ok(direction.is_none())
+    })?;
+    if direction.is_some() {
+        timestamp_hit.direction = direction;
+    }
+    Ok(())
+}
+
+pub fn stream_evtx_file(
+    log_file: &LogFile,
+    timestamp_hit: &Option<IdentifiedTimeInformation>,
+    execution_settings: &ExecutionSettings,
+) -> Result<LogRecordProcessor> {
+    let mut processing_object = LogRecordProcessor::new(
+        timestamp_hit,
+        execution_settings,
+        get_file_stem(log_file)?,
+        None,
+    );
+    for_each_evtx_record(log_file, |index, record_result| {
+        let record = match record_result {
+            Ok(record) => record,
+            Err(e) => {
+                processing_object.add_error(LavaError::new(
+                    format!(
+                        "Unable to parse EVTX record at index {} because of {}",
+                        index, e
+                    ),
+                    LavaErrorLevel::Medium,
+                ));
+                return Ok(true);
+            }
+        };
+        let current_datetime = match timestamp_hit {
+            None => None,
+            Some(timestamp_hit) => match get_string_at_pointer(
+                &record.data,
+                timestamp_hit.column_name.as_ref().unwrap(),
+            ) {
+                Some(timestamp_string) => {
+                    timestamp_hit.get_utc_timestamp_from_string(timestamp_string)?
+                }
+                None => None,
+            },
+        };
+        let log_file_record = LogFileRecord::new(
+            index,
+            current_datetime,
+            StringRecord::from(vec![record.data.to_string()]),
+        );
+        processing_object.process_evtx_record(
+            &log_file_record,
+            record.event_record_id,
+            get_event_id(&record.data),
+        )?;
+        processing_object.process_record(log_file_record)?;
+        Ok(true)
+    })?;
+    processing_object.finish_processing_evtx_record_ids();
+    Ok(processing_object)
+}
+
+#[cfg(test)]
+mod evtx_handler_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn gets_plain_event_id() {
+        let event = json!({"Event": {"System": {"EventID": 4624}}});
+        assert_eq!(get_event_id(&event), Some(4624));
+    }
+
+    #[test]
+    fn gets_event_id_with_qualifiers() {
+        let event = json!({"Event": {"System": {"EventID": {"#attributes": {"Qualifiers": 0}, "#text": 1102}}}});
+        assert_eq!(get_event_id(&event), Some(1102));
+    }
+
+    #[test]
+    fn default_timestamp_pointer_is_time_created() {
+        let event = json!({"Event": {"System": {"TimeCreated": {"#attributes": {"SystemTime": "2024-01-01T12:00:00.123456Z"}}}}});
+        let execution_settings = ExecutionSettings::create_integration_test_object(None, false);
+        let pointer = get_timestamp_pointer(&execution_settings);
+        assert_eq!(
+            get_string_at_pointer(&event, &pointer),
+            Some("2024-01-01T12:00:00.123456Z".to_string())
+        );
+        assert!(
+            execution_settings
+                .regexes
+                .iter()
+                .any(|r| r.string_contains_date("2024-01-01T12:00:00.123456Z"))
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use crate::basic_objects::*;
 use crate::errors::*;
+use crate::file_hashing::StreamHashers;
 use crate::helpers::get_file_stem;
+use crate::log_file_readers::{open_log_file, open_log_file_with_hashing};
 use crate::processing_objects::*;
 use csv::StringRecord;
 use serde_json::Value;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 
 fn parse_json_line_into_json(line: &str, index: usize) -> Result<Value> {
     let trimmed = line.trim();
@@ -41,7 +42,10 @@ pub fn collect_json_values_with_paths(value: &Value) -> Vec<JsonValue> {
 fn collect_helper<'a>(value: &'a Value, path: &mut Vec<String>, result: &mut Vec<JsonValue>) {
     match value {
         Value::Object(map) => {
-            for (key, val) in map {
+            // The evtx crate turns on serde_json's preserve_order, so keys are sorted to pick the same field however the record is written
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_unstable_by_key(|(key, _)| *key);
+            for (key, val) in entries {
                 path.push(key.clone());
                 collect_helper(val, path, result);
                 path.pop();
@@ -83,13 +87,7 @@ pub fn try_to_get_timestamp_hit_for_json(
     log_file: &LogFile,
     execution_settings: &ExecutionSettings,
 ) -> Result<Option<IdentifiedTimeInformation>> {
-    let file = File::open(&log_file.file_path).map_err(|e| {
-        LavaError::new(
-            format!("Unable to read log file because of {e}"),
-            LavaErrorLevel::Critical,
-        )
-    })?;
-    let reader = BufReader::new(file);
+    let reader = open_log_file(log_file)?;
 
     let mut lines = reader.lines();
     if let Some(line_result) = lines.next() {
@@ -129,7 +127,9 @@ fn try_to_get_timestamp_hit_for_json_functionality(
                             return Ok(Some(IdentifiedTimeInformation {
                                 column_name: Some(correct_formatted_path),
                                 column_index: None,
+                                composite_column_indices: Vec::new(),
                                 direction: None,
+                                assumed_timezone: execution_settings.assumed_timezone,
                                 regex_info: date_regex.clone(),
                             }));
                         }
@@ -143,7 +143,9 @@ fn try_to_get_timestamp_hit_for_json_functionality(
                             return Ok(Some(IdentifiedTimeInformation {
                                 column_name: Some(json_key.path.clone()),
                                 column_index: None,
+                                composite_column_indices: Vec::new(),
                                 direction: None,
+                                assumed_timezone: execution_settings.assumed_timezone,
                                 regex_info: date_regex.clone(),
                             }));
                         }
@@ -159,13 +161,7 @@ pub fn set_time_direction_by_scanning_json_file(
     log_file: &LogFile,
     timestamp_hit: &mut IdentifiedTimeInformation,
 ) -> Result<()> {
-    let file = File::open(&log_file.file_path).map_err(|e| {
-        LavaError::new(
-            format!("Unable to open the log file because of {e}"),
-            LavaErrorLevel::Critical,
-        )
-    })?;
-    let reader = BufReader::new(file);
+    let reader = open_log_file(log_file)?;
     let mut direction_checker = TimeDirectionChecker::default();
     for (index, line_result) in reader.lines().enumerate() {
         let line = line_result.map_err(|e| {
@@ -206,9 +202,8 @@ pub fn set_time_direction_by_scanning_json_file(
                 ));
             }
         };
-        if let Some(current_datetime) = timestamp_hit
-            .regex_info
-            .get_timestamp_object_from_string_contianing_date(timestamp_str.clone())?
+        if let Some(current_datetime) =
+            timestamp_hit.get_utc_timestamp_from_string(timestamp_str.clone())?
         {
             if let Some(direction) = direction_checker.process_timestamp(current_datetime) {
                 timestamp_hit.direction = Some(direction);
@@ -223,6 +218,7 @@ pub fn stream_json_file(
     log_file: &LogFile,
     timestamp_hit: &Option<IdentifiedTimeInformation>,
     execution_settings: &ExecutionSettings,
+    stream_hashers: &StreamHashers,
 ) -> Result<LogRecordProcessor> {
     let mut processing_object = LogRecordProcessor::new(
         timestamp_hit,
@@ -230,14 +226,7 @@ pub fn stream_json_file(
         get_file_stem(log_file)?,
         None,
     );
-    let file = File::open(&log_file.file_path).map_err(|e| {
-        LavaError::new(
-            format!("Unable to open log file because of {e}"),
-            LavaErrorLevel::Critical,
-        )
-    })?;
-
-    let reader = BufReader::new(file);
+    let reader = open_log_file_with_hashing(log_file, stream_hashers)?;
     for (index, line_result) in reader.lines().enumerate() {
         let line = line_result.map_err(|e| {
             LavaError::new(
@@ -267,14 +256,11 @@ pub fn stream_json_file(
                     serialized_line.pointer(timestamp_hit.column_name.as_ref().unwrap())
                 {
                     match value_of_key {
-                        Value::String(string) => timestamp_hit
-                            .regex_info
-                            .get_timestamp_object_from_string_contianing_date(string.clone())?,
+                        Value::String(string) => {
+                            timestamp_hit.get_utc_timestamp_from_string(string.clone())?
+                        }
                         Value::Number(number) => timestamp_hit
-                            .regex_info
-                            .get_timestamp_object_from_string_contianing_date(
-                                number.to_string().clone(),
-                            )?,
+                            .get_utc_timestamp_from_string(number.to_string().clone())?,
                         _ => {
                             return Err(LavaError::new(
                                 format!(
@@ -302,6 +288,7 @@ pub fn stream_json_file(
 mod json_handler_tests {
 
     use super::*;
+    use crate::alert_config::AlertConfig;
     use crate::date_regex::DateRegex;
     use regex::Regex;
     use serde_json::json;
@@ -329,6 +316,8 @@ mod json_handler_tests {
         let log_file = LogFile {
             log_type: LogType::Json, // Assuming a variant exists
             file_path: file_path.clone(),
+            type_detection: None,
+            archive_member: None,
         };
 
         let mut identified_time_info = try_to_get_timestamp_hit_for_json(
@@ -369,6 +358,8 @@ mod json_handler_tests {
         let log_file = LogFile {
             log_type: LogType::Json, // Assuming a variant exists
             file_path: file_path.clone(),
+            type_detection: None,
+            archive_member: None,
         };
 
         let mut identified_time_info = try_to_get_timestamp_hit_for_json(
@@ -409,6 +400,8 @@ mod json_handler_tests {
         let log_file = LogFile {
             log_type: LogType::Json, // Assuming a variant exists
             file_path: file_path.clone(),
+            type_detection: None,
+            archive_member: None,
         };
 
         let mut identified_time_info = try_to_get_timestamp_hit_for_json(
@@ -444,6 +437,8 @@ mod json_handler_tests {
         let log_file = LogFile {
             log_type: LogType::Json, // Assuming a variant exists
             file_path: file_path.clone(),
+            type_detection: None,
+            archive_member: None,
         };
 
         let mut identified_time_info = try_to_get_timestamp_hit_for_json(
@@ -512,6 +507,18 @@ mod json_handler_tests {
         assert_eq!(3, converted.len())
     }
 
+    #[test]
+    fn test_json_into_vec_converter_sorts_keys() {
+        let response =
+            parse_json_line_into_json(r#"{"user": {"time": 1}, "timestamp": 2, "id": 3}"#, 1)
+                .unwrap();
+        let paths: Vec<String> = collect_json_values_with_paths(&response)
+            .into_iter()
+            .map(|json_value| json_value.path)
+            .collect();
+        assert_eq!(paths, vec!["/id", "/timestamp", "/user/time"]);
+    }
+
     #[test]
     fn test_json_pathgrabber() {
         let json_str = r#"
@@ -551,6 +558,19 @@ mod json_handler_tests {
             multipart_mode: false,
             verbose_mode: true,
             actually_write_to_files: false,
+            syslog_year: None,
+            assumed_timezone: None,
+            out_of_order_tolerance: None,
+            num_top_time_gaps: 0,
+            volume_window: VolumeWindow::Hour,
+            time_gap_alert_method: TimeGapAlertMethod::StandardDeviations,
+            alert_config: AlertConfig::default(),
+            json_report_path: None,
+            write_html_report: false,
+            hash_algorithms: vec![HashAlgorithm::Sha256],
+            near_dupe_settings: None,
+            cross_file_dupes: false,
+            dupe_detection_mode: DupeDetectionMode::InMemory,
         };
 
         let result =
@@ -586,6 +606,19 @@ mod json_handler_tests {
             multipart_mode: false,
             verbose_mode: true,
             actually_write_to_files: false,
+            syslog_year: None,
+            assumed_timezone: None,
+            out_of_order_tolerance: None,
+            num_top_time_gaps: 0,
+            volume_window: VolumeWindow::Hour,
+            time_gap_alert_method: TimeGapAlertMethod::StandardDeviations,
+            alert_config: AlertConfig::default(),
+            json_report_path: None,
+            write_html_report: false,
+            hash_algorithms: vec![HashAlgorithm::Sha256],
+            near_dupe_settings: None,
+            cross_file_dupes: false,
+            dupe_detection_mode: DupeDetectionMode::InMemory,
         };
 
         let result =
@@ -0,0 +1,626 @@
+use crate::basic_objects::*;
+use crate::date_regex::DateRegex;
+use crate::errors::*;
+use crate::file_hashing::StreamHashers;
+use crate::helpers::get_file_stem;
+use crate::log_file_readers::{open_log_file, open_log_file_with_hashing};
+use crate::processing_objects::*;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeDelta};
+use csv::StringRecord;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::io::BufRead;
+
+pub static SYSLOG_HEADERS: [&str; 8] = [
+    "Priority",
+    "Timestamp",
+    "Host",
+    "App",
+    "Process ID",
+    "Message ID",
+    "Structured Data",
+    "Message",
+];
+
+// <34>Oct 11 22:14:15 mymachine su[123]: 'su root' failed for lonvick on /dev/pts/8
+static RFC3164_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:<(\d{1,3})>)?([A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2})\s+(\S+)\s+(?:([^\s:\[]+)(?:\[([^\]]*)\])?:\s?)?(.*)$").unwrap()
+});
+
+// <165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3"] An application event
+static RFC5424_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^<(\d{1,3})>1 (\S+) (\S+) (\S+) (\S+) (\S+) ?(.*)$").unwrap());
+
+static RFC3164_DATE_REGEX: Lazy<DateRegex> = Lazy::new(|| DateRegex {
+    pretty_format: "Mon DD HH:MM:SS (syslog, year inferred)".to_string(),
+    strftime_format: "%Y %b %e %H:%M:%S".to_string(),
+    regex: Regex::new(r"([A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2})").unwrap(),
+    function_to_call: None,
+});
+
+static RFC5424_DATE_REGEX: Lazy<DateRegex> = Lazy::new(|| DateRegex {
+    pretty_format: "YYYY-MM-DDTHH:MM:SS+HH:MM (RFC 5424 syslog, converted to UTC)".to_string(),
+    strftime_format: "%+".to_string(),
+    regex: Regex::new(r"(\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d{1,6})?(?:Z|[+-]\d{2}:\d{2}))")
+        .unwrap(),
+    function_to_call: None,
+});
+
+// If a timestamp moves this far in the wrong direction it is treated as the year rolling over rather than the file being out of order
+static YEAR_ROLLOVER_THRESHOLD_DAYS: i64 = 180;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyslogFormat {
+    Rfc3164,
+    Rfc5424,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyslogMessage {
+    pub format: SyslogFormat,
+    pub priority: Option<String>,
+    pub timestamp: String,
+    pub host: String,
+    pub app: String,
+    pub process_id: String,
+    pub message_id: String,
+    pub structured_data: Vec<(String, String)>, // (SD-ID.param, value)
+    pub structured_data_problem: Option<String>, // Set when the structured data is malformed and was left in the message
+    pub message: String,
+}
+
+impl SyslogMessage {
+    pub fn to_string_record(&self) -> StringRecord {
+        let structured_data = self
+            .structured_data
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<String>>()
+            .join("; ");
+        StringRecord::from(vec![
+            self.priority.clone().unwrap_or_default(),
+            self.timestamp.clone(),
+            self.host.clone(),
+            self.app.clone(),
+            self.process_id.clone(),
+            self.message_id.clone(),
+            structured_data,
+            self.message.clone(),
+        ])
+    }
+}
+
+fn nil_to_empty(value: &str) -> String {
+    match value {
+        "-" => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+pub fn line_is_syslog(line: &str) -> bool {
+    RFC5424_REGEX.is_match(line) || RFC3164_REGEX.is_match(line)
+}
+
+pub fn parse_syslog_line(line: &str) -> Option<SyslogMessage> {
+    if let Some(captures) = RFC5424_REGEX.captures(line) {
+        let (structured_data, message, structured_data_problem) =
+            parse_structured_data(&captures[7]);
+        return Some(SyslogMessage {
+            format: SyslogFormat::Rfc5424,
+            priority: Some(captures[1].to_string()),
+            timestamp: nil_to_empty(&captures[2]),
+            host: nil_to_empty(&captures[3]),
+            app: nil_to_empty(&captures[4]),
+            process_id: nil_to_empty(&captures[5]),
+            message_id: nil_to_empty(&captures[6]),
+            structured_data,
+            structured_data_problem,
+            message: message.trim_start_matches('\u{feff}').to_string(), // MSG may start with a UTF-8 BOM
+        });
+    }
+    let captures = RFC3164_REGEX.captures(line)?;
+    Some(SyslogMessage {
+        format: SyslogFormat::Rfc3164,
+        priority: captures.get(1).map(|m| m.as_str().to_string()),
+        timestamp: captures[2].to_string(),
+        host: captures[3].to_string(),
+        app: captures
+            .get(4)
+            .map(|m| m.as_str().to_string())
+            .unwrap_or_default(),
+        process_id: captures
+            .get(5)
+            .map(|m| m.as_str().to_string())
+            .unwrap_or_default(),
+        message_id: String::new(),
+        structured_data: Vec::new(),
+        structured_data_problem: None,
+        message: captures[6].to_string(),
+    })
+}
+
+/// Splits the RFC 5424 STRUCTURED-DATA off the front of the rest of the line, returning the params, the MSG, and what was wrong
+/// with the structured data if it couldn't be parsed. Parsing stops at the first malformed SD-ELEMENT, which is left at the
+/// start of the MSG so nothing on the line is lost
+fn parse_structured_data(rest: &str) -> (Vec<(String, String)>, String, Option<String>) {
+    let mut params: Vec<(String, String)> = Vec::new();
+    if let Some(message) = rest.strip_prefix('-') {
+        return (params, message.trim_start().to_string(), None);
+    }
+    let chars: Vec<char> = rest.chars().collect();
+    let mut position = 0;
+    while position < chars.len() && chars[position] == '[' {
+        let element_start = position;
+        match parse_sd_element(&chars, &mut position) {
+            Ok(element_params) => params.extend(element_params),
+            Err(problem) => {
+                let message: String = chars[element_start..].iter().collect();
+                return (params, message, Some(problem));
+            }
+        }
+    }
+    let message: String = chars[position..].iter().collect();
+    (params, message.trim_start().to_string(), None)
+}
+
+/// Parses one [SD-ID PARAM-NAME="value" ...] starting at the opening bracket, leaving the position just past the closing one
+fn parse_sd_element(
+    chars: &[char],
+    position: &mut usize,
+) -> std::result::Result<Vec<(String, String)>, String> {
+    let mut params: Vec<(String, String)> = Vec::new();
+    *position += 1;
+    let mut sd_id = String::new();
+    while *position < chars.len() && !matches!(chars[*position], ' ' | ']') {
+        sd_id.push(chars[*position]);
+        *position += 1;
+    }
+    loop {
+        while *position < chars.len() && chars[*position] == ' ' {
+            *position += 1;
+        }
+        match chars.get(*position) {
+            None => return Err(format!("{} is missing its closing ]", sd_id)),
+            Some(']') => {
+                *position += 1;
+                return Ok(params);
+            }
+            Some(_) => (),
+        }
+        let mut param_name = String::new();
+        while *position < chars.len() && !matches!(chars[*position], '=' | ' ' | ']') {
+            param_name.push(chars[*position]);
+            *position += 1;
+        }
+        if chars.get(*position) != Some(&'=') || chars.get(*position + 1) != Some(&'"') {
+            return Err(format!(
+                "{} in {} is not followed by =\"",
+                param_name, sd_id
+            ));
+        }
+        *position += 2;
+        let mut param_value = String::new();
+        loop {
+            match chars.get(*position) {
+                None => {
+                    return Err(format!(
+                        "the value of {} in {} is missing its closing quote",
+                        param_name, sd_id
+                    ));
+                }
+                Some('"') => {
+                    *position += 1;
+                    break;
+                }
+                Some('\\') if matches!(chars.get(*position + 1), Some('"' | '\\' | ']')) => {
+                    param_value.push(chars[*position + 1]);
+                    *position += 2;
+                }
+                Some(c) => {
+                    param_value.push(*c);
+                    *position += 1;
+                }
+            }
+        }
+        params.push((format!("{}.{}", sd_id, param_name), param_value));
+    }
+}
+
+/// BSD syslog timestamps have no year. The year starts at --year if provided, otherwise the year the file was last modified
+/// (or the year before, if that would put the first record after the modified time). It is then bumped whenever the
+/// timestamps jump backwards by months, which is the year rolling over in an ascending file.
+#[derive(Debug, Clone)]
+pub struct SyslogYearInferrer {
+    provided_year: Option<i32>,
+    file_modified_time: Option<NaiveDateTime>,
+    current_year: Option<i32>,
+    previous: Option<NaiveDateTime>,
+}
+
+impl SyslogYearInferrer {
+    pub fn new(provided_year: Option<i32>, file_modified_time: Option<NaiveDateTime>) -> Self {
+        Self {
+            provided_year,
+            file_modified_time,
+            current_year: None,
+            previous: None,
+        }
+    }
+
+    pub fn infer(&mut self, timestamp_without_year: &str) -> Option<NaiveDateTime> {
+        let year = match self.current_year {
+            Some(year) => year,
+            None => self.get_starting_year(timestamp_without_year),
+        };
+        let mut candidate = parse_timestamp_with_year(timestamp_without_year, year)?;
+        let mut year = year;
+        if let Some(previous) = self.previous {
+            if candidate + TimeDelta::days(YEAR_ROLLOVER_THRESHOLD_DAYS) < previous {
+                year += 1;
+                candidate = parse_timestamp_with_year(timestamp_without_year, year)?;
+            } else if candidate - TimeDelta::days(YEAR_ROLLOVER_THRESHOLD_DAYS) > previous {
+                year -= 1; // Descending file crossing back into the previous year
+                candidate = parse_timestamp_with_year(timestamp_without_year, year)?;
+            }
+        }
+        self.current_year = Some(year);
+        self.previous = Some(candidate);
+        Some(candidate)
+    }
+
+    fn get_starting_year(&self, timestamp_without_year: &str) -> i32 {
+        if let Some(provided_year) = self.provided_year {
+            return provided_year;
+        }
+        match self.file_modified_time {
+            Some(modified_time) => {
+                match parse_timestamp_with_year(timestamp_without_year, modified_time.year()) {
+                    Some(candidate) if candidate > modified_time => modified_time.year() - 1,
+                    _ => modified_time.year(),
+                }
+            }
+            None => Local::now().year(),
+        }
+    }
+}
+
+fn parse_timestamp_with_year(timestamp_without_year: &str, year: i32) -> Option<NaiveDateTime> {
+    let with_year = format!("{} {}", year, timestamp_without_year);
+    let parsed =
+        NaiveDateTime::parse_from_str(&with_year, &RFC3164_DATE_REGEX.strftime_format).ok()?;
+    // Feb 29 only parses in leap years, so make sure chrono didn't land somewhere else
+    NaiveDate::from_ymd_opt(year, parsed.month(), parsed.day())?;
+    Some(parsed)
+}
+
+/// Archive members use their own modified time from the archive, and the archive's when the archive doesn't have one
+fn get_file_modified_time(log_file: &LogFile) -> Option<NaiveDateTime> {
+    if let Some(modified_time) = log_file
+        .archive_member
+        .as_ref()
+        .and_then(|member| member.modified_time)
+    {
+        return Some(modified_time);
+    }
+    let modified = std::fs::metadata(&log_file.file_path)
+        .ok()?
+        .modified()
+        .ok()?;
+    Some(DateTime::<Local>::from(modified).naive_local())
+}
+
+/// Both formats end up in UTC. BSD timestamps have no offset, so they go through the assumed timezone of the timestamp hit if there is one
+fn get_timestamp_of_syslog_message(
+    message: &SyslogMessage,
+    year_inferrer: &mut SyslogYearInferrer,
+    timestamp_hit: Option<&IdentifiedTimeInformation>,
+) -> Option<NaiveDateTime> {
+    match message.format {
+        SyslogFormat::Rfc3164 => {
+            let local_datetime = year_inferrer.infer(&message.timestamp)?;
+            match timestamp_hit {
+                None => Some(local_datetime),
+                Some(timestamp_hit) => timestamp_hit.convert_local_timestamp_to_utc(local_datetime),
+            }
+        }
+        SyslogFormat::Rfc5424 => DateTime::parse_from_rfc3339(&message.timestamp)
+            .ok()
+            .map(|datetime| datetime.naive_utc()),
+    }
+}
+
+pub fn try_to_get_timestamp_hit_for_syslog(
+    log_file: &LogFile,
+    execution_settings: &ExecutionSettings,
+) -> Result<Option<IdentifiedTimeInformation>> {
+    let reader = open_log_file(log_file)?;
+    let mut year_inferrer = SyslogYearInferrer::new(
+        execution_settings.syslog_year,
+        get_file_modified_time(log_file),
+    );
+    for (index, line_result) in reader.lines().enumerate() {
+        let line = line_result.map_err(|e| {
+            LavaError::new(
+                format!("Error reading line because of {} at index {}", e, index),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        if let Some(message) = parse_syslog_line(&line)
+            && get_timestamp_of_syslog_message(&message, &mut year_inferrer, None).is_some()
+        {
+            let regex_info = match message.format {
+                SyslogFormat::Rfc3164 => RFC3164_DATE_REGEX.clone(),
+                SyslogFormat::Rfc5424 => RFC5424_DATE_REGEX.clone(),
+            };
+            return Ok(Some(IdentifiedTimeInformation {
+                column_name: Some("Timestamp".to_string()),
+                column_index: Some(1),
+                composite_column_indices: Vec::new(),
+                direction: None,
+                assumed_timezone: execution_settings.assumed_timezone,
+                regex_info,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+pub fn set_time_direction_by_scanning_syslog_file(
+    log_file: &LogFile,
+    timestamp_hit: &mut IdentifiedTimeInformation,
+    execution_settings: &ExecutionSettings,
+) -> Result<()> {
+    let reader = open_log_file(log_file)?;
+    let mut year_inferrer = SyslogYearInferrer::new(
+        execution_settings.syslog_year,
+        get_file_modified_time(log_file),
+    );
+    let mut direction_checker = TimeDirectionChecker::default();
+    for (index, line_result) in reader.lines().enumerate() {
+        let line = line_result.map_err(|e| {
+            LavaError::new(
+                format!("Error reading line because of {} at index {}", e, index),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        if let Some(message) = parse_syslog_line(&line)
+            && let Some(current_datetime) =
+                get_timestamp_of_syslog_message(&message, &mut year_inferrer, Some(timestamp_hit))
+            && let Some(direction) = direction_checker.process_timestamp(current_datetime)
+        {
+            timestamp_hit.direction = Some(direction);
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+pub fn stream_syslog_file(
+    log_file: &LogFile,
+    timestamp_hit: &Option<IdentifiedTimeInformation>,
+    execution_settings: &ExecutionSettings,
+    stream_hashers: &StreamHashers,
+) -> Result<LogRecordProcessor> {
+    let mut processing_object = LogRecordProcessor::new(
+        timestamp_hit,
+        execution_settings,
+        get_file_stem(log_file)?,
+        Some(StringRecord::from(SYSLOG_HEADERS.to_vec())),
+    );
+    let mut year_inferrer = SyslogYearInferrer::new(
+        execution_settings.syslog_year,
+        get_file_modified_time(log_file),
+    );
+    let reader = open_log_file_with_hashing(log_file, stream_hashers)?;
+    for (index, line_result) in reader.lines().enumerate() {
+        let line = line_result.map_err(|e| {
+            LavaError::new(
+                format!("Error reading line because of {} at index {}", e, index),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (record, current_datetime) = match parse_syslog_line(&line) {
+            Some(message) => {
+                if let Some(problem) = &message.structured_data_problem {
+                    processing_object.add_error(LavaError::new(
+                        format!(
+                            "Unable to parse structured data at index {} because {}",
+                            index, problem
+                        ),
+                        LavaErrorLevel::Low,
+                    ));
+                }
+                let current_datetime = match timestamp_hit {
+                    None => None,
+                    Some(timestamp_hit) => get_timestamp_of_syslog_message(
+                        &message,
+                        &mut year_inferrer,
+                        Some(timestamp_hit),
+                    ),
+                };
+                (message.to_string_record(), current_datetime)
+            }
+            None => {
+                // Keep the line in the message column so it is still checked for dupes and redactions
+                let mut record = StringRecord::from(vec![""; SYSLOG_HEADERS.len() - 1]);
+                record.push_field(&line);
+                (record, None)
+            }
+        };
+        processing_object.process_record(LogFileRecord::new(index, current_datetime, record))?;
+    }
+    Ok(processing_object)
+}
+
+#[cfg(test)]
+mod syslog_handler_tests {
+    use super::*;
+
+    fn dt(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn parses_rfc3164_line() {
+        let message =
+            parse_syslog_line("<34>Oct 11 22:14:15 mymachine su[123]: 'su root' failed").unwrap();
+        assert_eq!(message.format, SyslogFormat::Rfc3164);
+        assert_eq!(message.priority, Some("34".to_string()));
+        assert_eq!(message.timestamp, "Oct 11 22:14:15");
+        assert_eq!(message.host, "mymachine");
+        assert_eq!(message.app, "su");
+        assert_eq!(message.process_id, "123");
+        assert_eq!(message.message, "'su root' failed");
+    }
+
+    #[test]
+    fn parses_rfc3164_line_without_priority_or_pid() {
+        let message = parse_syslog_line("Oct  1 02:03:04 host kernel: eth0: link up").unwrap();
+        assert_eq!(message.priority, None);
+        assert_eq!(message.timestamp, "Oct  1 02:03:04");
+        assert_eq!(message.app, "kernel");
+        assert_eq!(message.process_id, "");
+        assert_eq!(message.message, "eth0: link up");
+    }
+
+    #[test]
+    fn parses_rfc5424_line_with_structured_data() {
+        let message = parse_syslog_line(
+            r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="App \"X\""][user@1 name="REDACTED"] An application event"#,
+        )
+        .unwrap();
+        assert_eq!(message.format, SyslogFormat::Rfc5424);
+        assert_eq!(message.host, "mymachine.example.com");
+        assert_eq!(message.app, "evntslog");
+        assert_eq!(message.process_id, "");
+        assert_eq!(message.message_id, "ID47");
+        assert_eq!(
+            message.structured_data,
+            vec![
+                ("exampleSDID@32473.iut".to_string(), "3".to_string()),
+                (
+                    "exampleSDID@32473.eventSource".to_string(),
+                    "App \"X\"".to_string()
+                ),
+                ("user@1.name".to_string(), "REDACTED".to_string()),
+            ]
+        );
+        assert_eq!(message.message, "An application event");
+    }
+
+    #[test]
+    fn malformed_structured_data_is_left_in_message() {
+        for (line, expected_problem) in [
+            (
+                r#"<165>1 2003-10-11T22:14:15.003Z host app - ID47 [a@1 x="1"][b@1 y:"2"] event"#,
+                "y:\"2\" in b@1 is not followed by =\"",
+            ),
+            (
+                r#"<165>1 2003-10-11T22:14:15.003Z host app - ID47 [a@1 x="1"][b@1 y="2] event"#,
+                "the value of y in b@1 is missing its closing quote",
+            ),
+        ] {
+            let message = parse_syslog_line(line).unwrap();
+            assert_eq!(
+                message.structured_data,
+                vec![("a@1.x".to_string(), "1".to_string())]
+            );
+            assert_eq!(
+                message.structured_data_problem.as_deref(),
+                Some(expected_problem)
+            );
+            assert!(message.message.starts_with("[b@1 y"));
+        }
+    }
+
+    #[test]
+    fn parses_rfc5424_line_without_structured_data() {
+        let message =
+            parse_syslog_line("<34>1 2003-10-11T22:14:15-04:00 host app 99 - - hello").unwrap();
+        assert!(message.structured_data.is_empty());
+        assert_eq!(message.message, "hello");
+        assert_eq!(
+            get_timestamp_of_syslog_message(
+                &message,
+                &mut SyslogYearInferrer::new(None, None),
+                None
+            ),
+            Some(dt("2003-10-12 02:14:15"))
+        );
+    }
+
+    #[test]
+    fn uses_provided_year() {
+        let mut inferrer = SyslogYearInferrer::new(Some(2019), Some(dt("2025-03-01 00:00:00")));
+        assert_eq!(
+            inferrer.infer("Oct 18 13:02:11"),
+            Some(dt("2019-10-18 13:02:11"))
+        );
+    }
+
+    #[test]
+    fn infers_previous_year_when_first_record_is_after_modified_time() {
+        let mut inferrer = SyslogYearInferrer::new(None, Some(dt("2025-01-05 00:00:00")));
+        assert_eq!(
+            inferrer.infer("Dec 30 23:59:00"),
+            Some(dt("2024-12-30 23:59:00"))
+        );
+        assert_eq!(
+            inferrer.infer("Jan  2 00:00:01"),
+            Some(dt("2025-01-02 00:00:01"))
+        );
+    }
+
+    #[test]
+    fn detects_year_rollover_in_ascending_file() {
+        let mut inferrer = SyslogYearInferrer::new(Some(2023), None);
+        assert_eq!(
+            inferrer.infer("Dec 31 23:59:59"),
+            Some(dt("2023-12-31 23:59:59"))
+        );
+        assert_eq!(
+            inferrer.infer("Jan  1 00:00:01"),
+            Some(dt("2024-01-01 00:00:01"))
+        );
+        assert_eq!(
+            inferrer.infer("Jan  1 00:00:00"),
+            Some(dt("2024-01-01 00:00:00"))
+        );
+    }
+
+    #[test]
+    fn feb_29_only_in_leap_years() {
+        assert_eq!(parse_timestamp_with_year("Feb 29 00:00:00", 2023), None);
+        assert_eq!(
+            parse_timestamp_with_year("Feb 29 00:00:00", 2024),
+            Some(dt("2024-02-29 00:00:00"))
+        );
+    }
+
+    #[test]
+    fn archive_members_use_their_own_modified_time() {
+        let archive = tempfile::NamedTempFile::new().unwrap();
+        let mut log_file = LogFile {
+            log_type: LogType::Syslog,
+            file_path: archive.path().to_path_buf(),
+            type_detection: None,
+            archive_member: Some(ArchiveMember {
+                archive_type: ArchiveType::Tar,
+                inner_path: "var/log/messages".to_string(),
+                size: 0,
+                modified_time: Some(dt("2019-12-31 23:59:58")),
+            }),
+        };
+        assert_eq!(
+            get_file_modified_time(&log_file),
+            Some(dt("2019-12-31 23:59:58"))
+        );
+
+        // Falls back to the archive's own modified time
+        log_file.archive_member.as_mut().unwrap().modified_time = None;
+        assert!(get_file_modified_time(&log_file).is_some_and(|t| t.year() >= 2025));
+    }
+}
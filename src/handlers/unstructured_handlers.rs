@@ -1,22 +1,17 @@
 use crate::basic_objects::*;
 use crate::errors::*;
+use crate::file_hashing::StreamHashers;
 use crate::helpers::get_file_stem;
+use crate::log_file_readers::{open_log_file, open_log_file_with_hashing};
 use crate::processing_objects::*;
 use csv::StringRecord;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 
 pub fn try_to_get_timestamp_hit_for_unstructured(
     log_file: &LogFile,
     execution_settings: &ExecutionSettings,
 ) -> Result<Option<IdentifiedTimeInformation>> {
-    let file = File::open(&log_file.file_path).map_err(|e| {
-        LavaError::new(
-            format!("Unable to read log file because of {e}"),
-            LavaErrorLevel::Critical,
-        )
-    })?;
-    let reader = BufReader::new(file);
+    let reader = open_log_file(log_file)?;
 
     for line_result in reader.lines() {
         let line = line_result.map_err(|e| {
@@ -30,7 +25,9 @@ pub fn try_to_get_timestamp_hit_for_unstructured(
                 return Ok(Some(IdentifiedTimeInformation {
                     column_name: None,
                     column_index: None,
+                    composite_column_indices: Vec::new(),
                     direction: None,
+                    assumed_timezone: execution_settings.assumed_timezone,
                     regex_info: date_regex.clone(),
                 }));
             }
@@ -43,13 +40,7 @@ pub fn set_time_direction_by_scanning_unstructured_file(
     log_file: &LogFile,
     timestamp_hit: &mut IdentifiedTimeInformation,
 ) -> Result<()> {
-    let file = File::open(&log_file.file_path).map_err(|e| {
-        LavaError::new(
-            format!("Unable to open the log file because of {e}"),
-            LavaErrorLevel::Critical,
-        )
-    })?;
-    let reader = BufReader::new(file);
+    let reader = open_log_file(log_file)?;
     let mut direction_checker = TimeDirectionChecker::default();
     for (index, line_result) in reader.lines().enumerate() {
         let line = line_result.map_err(|e| {
@@ -58,10 +49,7 @@ pub fn set_time_direction_by_scanning_unstructured_file(
                 LavaErrorLevel::Critical,
             )
         })?;
-        if let Some(current_datetime) = timestamp_hit
-            .regex_info
-            .get_timestamp_object_from_string_contianing_date(line)?
-        {
+        if let Some(current_datetime) = timestamp_hit.get_utc_timestamp_from_string(line)? {
             if let Some(direction) = direction_checker.process_timestamp(current_datetime) {
                 timestamp_hit.direction = Some(direction);
                 return Ok(());
@@ -75,6 +63,7 @@ pub fn stream_unstructured_file(
     log_file: &LogFile,
     timestamp_hit: &Option<IdentifiedTimeInformation>,
     execution_settings: &ExecutionSettings,
+    stream_hashers: &StreamHashers,
 ) -> Result<LogRecordProcessor> {
     let mut processing_object = LogRecordProcessor::new(
         timestamp_hit,
@@ -82,13 +71,7 @@ pub fn stream_unstructured_file(
         get_file_stem(log_file)?,
         None,
     );
-    let file = File::open(&log_file.file_path).map_err(|e| {
-        LavaError::new(
-            format!("Unable to open log file because of {e}"),
-            LavaErrorLevel::Critical,
-        )
-    })?;
-    let reader = BufReader::new(file);
+    let reader = open_log_file_with_hashing(log_file, stream_hashers)?;
     for (index, line_result) in reader.lines().enumerate() {
         let line = line_result.map_err(|e| {
             LavaError::new(
@@ -98,9 +81,7 @@ pub fn stream_unstructured_file(
         })?;
         let current_datetime = match timestamp_hit {
             None => None,
-            Some(timestamp_hit) => timestamp_hit
-                .regex_info
-                .get_timestamp_object_from_string_contianing_date(line.clone())?,
+            Some(timestamp_hit) => timestamp_hit.get_utc_timestamp_from_string(line.clone())?,
         };
         processing_object.process_record(LogFileRecord::new(
             index,
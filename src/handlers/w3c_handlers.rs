@@ -0,0 +1,357 @@
+use crate::basic_objects::*;
+use crate::errors::*;
+use crate::file_hashing::StreamHashers;
+use crate::helpers::get_file_stem;
+use crate::log_file_readers::{open_log_file, open_log_file_with_hashing};
+use crate::processing_objects::*;
+use csv::StringRecord;
+use std::io::BufRead;
+
+static FIELDS_DIRECTIVE: &str = "#Fields:";
+static DATE_DIRECTIVE: &str = "#Date:";
+static DATE_FROM_DIRECTIVE: &str = "#Date"; // Used in the timestamp column name when the date comes from the #Date directive
+
+pub fn line_is_w3c_fields_directive(line: &str) -> bool {
+    line.starts_with(FIELDS_DIRECTIVE)
+}
+
+fn parse_fields_directive(line: &str) -> Vec<String> {
+    line[FIELDS_DIRECTIVE.len()..]
+        .split_whitespace()
+        .map(|field| field.to_string())
+        .collect()
+}
+
+/// Tracks the directives seen so far, since a server restart starts a new block that can change the fields
+struct W3cDirectiveState {
+    all_fields: Vec<String>,
+    current_fields_to_all_fields: Vec<usize>,
+    current_date: Option<String>,
+}
+
+impl W3cDirectiveState {
+    fn new(all_fields: &StringRecord) -> Self {
+        Self {
+            all_fields: all_fields.iter().map(|field| field.to_string()).collect(),
+            current_fields_to_all_fields: Vec::new(),
+            current_date: None,
+        }
+    }
+
+    /// Returns true if the line was a directive
+    fn process_directive(&mut self, line: &str) -> bool {
+        if !line.starts_with('#') {
+            return false;
+        }
+        if line_is_w3c_fields_directive(line) {
+            self.current_fields_to_all_fields = parse_fields_directive(line)
+                .iter()
+                .filter_map(|field| self.all_fields.iter().position(|f| f == field))
+                .collect();
+        } else if let Some(date) = line.strip_prefix(DATE_DIRECTIVE) {
+            // #Date: 2024-01-01 00:00:00 is when the block started, only the date part is useful for records
+            self.current_date = date.split_whitespace().next().map(|d| d.to_string());
+        }
+        true
+    }
+
+    /// Lines up the values under the current #Fields with the combined list of fields across all blocks
+    fn get_record(&self, line: &str) -> StringRecord {
+        let mut values: Vec<&str> = vec![""; self.all_fields.len()];
+        for (value, all_fields_index) in line
+            .split_whitespace()
+            .zip(self.current_fields_to_all_fields.iter())
+        {
+            values[*all_fields_index] = value;
+        }
+        StringRecord::from(values)
+    }
+
+    fn get_timestamp_string(
+        &self,
+        record: &StringRecord,
+        timestamp_fields: &[String],
+    ) -> Option<String> {
+        let mut parts: Vec<String> = Vec::new();
+        for timestamp_field in timestamp_fields {
+            if timestamp_field == DATE_FROM_DIRECTIVE {
+                parts.push(self.current_date.clone()?);
+            } else {
+                let index = self.all_fields.iter().position(|f| f == timestamp_field)?;
+                let value = record.get(index)?;
+                if value.is_empty() || value == "-" {
+                    return None;
+                }
+                parts.push(value.to_string());
+            }
+        }
+        Some(parts.join(" "))
+    }
+}
+
+/// The combined fields of every #Fields directive in the file, in order of first appearance. Directives can be anywhere,
+/// so this reads the whole file before it's streamed
+pub fn get_header_info_for_w3c(log_file: &LogFile) -> Result<HeaderInfo> {
+    let reader = open_log_file(log_file)?;
+    let mut all_fields: Vec<String> = Vec::new();
+    let mut first_data_row: Option<usize> = None;
+    for (index, line_result) in reader.lines().enumerate() {
+        let line = line_result.map_err(|e| {
+            LavaError::new(
+                format!("Error reading line because of {} at index {}", e, index),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        if line_is_w3c_fields_directive(&line) {
+            for field in parse_fields_directive(&line) {
+                if !all_fields.contains(&field) {
+                    all_fields.push(field);
+                }
+            }
+        } else if !line.starts_with('#') && !line.trim().is_empty() && first_data_row.is_none() {
+            first_data_row = Some(index);
+        }
+    }
+    if all_fields.is_empty() {
+        return Err(LavaError::new(
+            "No #Fields directive found in W3C log",
+            LavaErrorLevel::Critical,
+        ));
+    }
+    Ok(HeaderInfo {
+        first_data_row: first_data_row.unwrap_or(0),
+        headers: StringRecord::from(all_fields),
+    })
+}
+
+fn get_timestamp_fields(
+    headers: &StringRecord,
+    execution_settings: &ExecutionSettings,
+) -> Option<Vec<String>> {
+    if let Some(field_to_use) = &execution_settings.timestamp_field {
+        return Some(
+            field_to_use
+                .split('+')
+                .map(|field| field.trim().to_string())
+                .collect(),
+        );
+    }
+    let has_field = |name: &str| headers.iter().any(|field| field == name);
+    if !has_field("time") {
+        return None;
+    }
+    match has_field("date") {
+        true => Some(vec!["date".to_string(), "time".to_string()]),
+        false => Some(vec![DATE_FROM_DIRECTIVE.to_string(), "time".to_string()]), // Some servers only log the time and rely on #Date
+    }
+}
+
+fn get_timestamp_fields_from_hit(timestamp_hit: &IdentifiedTimeInformation) -> Vec<String> {
+    timestamp_hit
+        .column_name
+        .as_deref()
+        .unwrap_or_default()
+        .split('+')
+        .map(|field| field.to_string())
+        .collect()
+}
+
+/// Runs the callback on every data line with its record and combined timestamp string, stopping early if the callback returns false
+fn for_each_w3c_record<F>(
+    reader: Box<dyn BufRead>,
+    header_info: &HeaderInfo,
+    timestamp_fields: &[String],
+    mut callback: F,
+) -> Result<()>
+where
+    F: FnMut(usize, StringRecord, Option<String>) -> Result<bool>,
+{
+    let mut directive_state = W3cDirectiveState::new(&header_info.headers);
+    for (index, line_result) in reader.lines().enumerate() {
+        let line = line_result.map_err(|e| {
+            LavaError::new(
+                format!("Error reading line because of {} at index {}", e, index),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        if directive_state.process_directive(&line) || line.trim().is_empty() {
+            continue;
+        }
+        let record = directive_state.get_record(&line);
+        let timestamp_string = directive_state.get_timestamp_string(&record, timestamp_fields);
+        if !callback(index, record, timestamp_string)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+pub fn try_to_get_timestamp_hit_for_w3c(
+    log_file: &LogFile,
+    execution_settings: &ExecutionSettings,
+    header_info: HeaderInfo,
+) -> Result<Option<IdentifiedTimeInformation>> {
+    let timestamp_fields = match get_timestamp_fields(&header_info.headers, execution_settings) {
+        Some(timestamp_fields) => timestamp_fields,
+        None => return Ok(None),
+    };
+    let mut timestamp_hit: Option<IdentifiedTimeInformation> = None;
+    for_each_w3c_record(
+        open_log_file(log_file)?,
+        &header_info,
+        &timestamp_fields,
+        |_, _, timestamp_string| {
+            if let Some(timestamp_string) = timestamp_string {
+                for date_regex in execution_settings.regexes.iter() {
+                    if date_regex.string_contains_date(&timestamp_string) {
+                        timestamp_hit = Some(IdentifiedTimeInformation {
+                            column_name: Some(timestamp_fields.join("+")),
+                            column_index: None,
+                            composite_column_indices: Vec::new(),
+                            direction: None,
+                            assumed_timezone: execution_settings.assumed_timezone,
+                            regex_info: date_regex.clone(),
+                        });
+                        break;
+                    }
+                }
+            }
+            Ok(false)
+        },
+    )?;
+    Ok(timestamp_hit)
+}
+
+pub fn set_time_direction_by_scanning_w3c_file(
+    log_file: &LogFile,
+    timestamp_hit: &mut IdentifiedTimeInformation,
+    header_info: HeaderInfo,
+) -> Result<()> {
+    let timestamp_fields = get_timestamp_fields_from_hit(timestamp_hit);
+    let mut direction_checker = TimeDirectionChecker::default();
+    let mut direction: Option<TimeDirection> = None;
+    for_each_w3c_record(
+        open_log_file(log_file)?,
+        &header_info,
+        &timestamp_fields,
+        |_, _, timestamp_string| {
+            if let Some(timestamp_string) = timestamp_string
+                && let Some(current_datetime) =
+                    timestamp_hit.get_utc_timestamp_from_string(timestamp_string)?
+            {
+                direction = direction_checker.process_timestamp(current_datetime);
+            }
+            Ok(direction.is_none())
+        },
+    )?;
+    if direction.is_some() {
+        timestamp_hit.direction = direction;
+    }
+    Ok(())
+}
+
+pub fn stream_w3c_file(
+    log_file: &LogFile,
+    timestamp_hit: &Option<IdentifiedTimeInformation>,
+    execution_settings: &ExecutionSettings,
+    header_info: HeaderInfo,
+    stream_hashers: &StreamHashers,
+) -> Result<LogRecordProcessor> {
+    let mut processing_object = LogRecordProcessor::new(
+        timestamp_hit,
+        execution_settings,
+        get_file_stem(log_file)?,
+        Some(header_info.headers.clone()),
+    );
+    let timestamp_fields = match timestamp_hit {
+        Some(timestamp_hit) => get_timestamp_fields_from_hit(timestamp_hit),
+        None => Vec::new(),
+    };
+    for_each_w3c_record(
+        open_log_file_with_hashing(log_file, stream_hashers)?,
+        &header_info,
+        &timestamp_fields,
+        |index, record, timestamp_string| {
+            let current_datetime = match (timestamp_hit, timestamp_string) {
+                (Some(timestamp_hit), Some(timestamp_string)) => {
+                    timestamp_hit.get_utc_timestamp_from_string(timestamp_string)?
+                }
+                _ => None,
+            };
+            processing_object.process_record(LogFileRecord::new(
+                index,
+                current_datetime,
+                record,
+            ))?;
+            Ok(true)
+        },
+    )?;
+    Ok(processing_object)
+}
+
+#[cfg(test)]
+mod w3c_handler_tests {
+    use super::*;
+
+    fn headers(fields: &[&str]) -> StringRecord {
+        StringRecord::from(fields.to_vec())
+    }
+
+    #[test]
+    fn combines_date_and_time_columns() {
+        let all_fields = headers(&["date", "time", "cs-method", "cs-uri-stem"]);
+        let mut state = W3cDirectiveState::new(&all_fields);
+        assert!(state.process_directive("#Fields: date time cs-method cs-uri-stem"));
+        let record = state.get_record("2024-01-01 00:00:01 GET /index.html");
+        assert_eq!(
+            state.get_timestamp_string(&record, &["date".to_string(), "time".to_string()]),
+            Some("2024-01-01 00:00:01".to_string())
+        );
+    }
+
+    #[test]
+    fn uses_date_directive_when_there_is_no_date_field() {
+        let all_fields = headers(&["time", "cs-method"]);
+        let mut state = W3cDirectiveState::new(&all_fields);
+        state.process_directive("#Date: 2024-01-01 00:00:00");
+        state.process_directive("#Fields: time cs-method");
+        let record = state.get_record("12:00:00 GET");
+        assert_eq!(
+            state.get_timestamp_string(
+                &record,
+                &[DATE_FROM_DIRECTIVE.to_string(), "time".to_string()]
+            ),
+            Some("2024-01-01 12:00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn realigns_records_after_mid_file_fields_change() {
+        let all_fields = headers(&["date", "time", "cs-method", "sc-status"]);
+        let mut state = W3cDirectiveState::new(&all_fields);
+        state.process_directive("#Fields: date time cs-method");
+        assert_eq!(
+            state.get_record("2024-01-01 00:00:01 GET"),
+            headers(&["2024-01-01", "00:00:01", "GET", ""])
+        );
+        state.process_directive("#Fields: date time sc-status cs-method");
+        assert_eq!(
+            state.get_record("2024-01-01 00:00:02 200 POST"),
+            headers(&["2024-01-01", "00:00:02", "POST", "200"])
+        );
+    }
+
+    #[test]
+    fn default_timestamp_fields() {
+        let settings = ExecutionSettings::default();
+        assert_eq!(
+            get_timestamp_fields(&headers(&["date", "time", "s-ip"]), &settings),
+            Some(vec!["date".to_string(), "time".to_string()])
+        );
+        assert_eq!(
+            get_timestamp_fields(&headers(&["time", "s-ip"]), &settings),
+            Some(vec!["#Date".to_string(), "time".to_string()])
+        );
+        assert_eq!(get_timestamp_fields(&headers(&["s-ip"]), &settings), None);
+    }
+}
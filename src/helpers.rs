@@ -1,19 +1,38 @@
 use crate::alerts::*;
 use crate::basic_objects::*;
 use crate::errors::*;
-use chrono::Utc;
+use chrono::{TimeDelta, Utc};
 use comfy_table::modifiers::UTF8_ROUND_CORNERS;
 use comfy_table::presets::UTF8_FULL;
 use comfy_table::*;
 use csv::StringRecord;
 use csv::Writer;
-use std::collections::HashMap;
-use std::collections::hash_map::DefaultHasher;
+use csv::WriterBuilder;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::Display;
 use std::fs::OpenOptions;
-use std::hash::{Hash, Hasher};
 use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use xxhash_rust::xxh3::Xxh3;
+
+pub static RECORD_HASH_ALGORITHM: &str = "xxh3-128";
+pub static RECORD_HASH_SEED: u64 = 0;
+static OUTPUT_FILES_WRITTEN: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new()); // Every file written this run, for the manifest
+
+/// Called by everything that writes an output file, so the manifest hashes exactly the files LAVA wrote
+pub fn record_output_file_written(output_file: &Path) {
+    if let Ok(mut output_files_written) = OUTPUT_FILES_WRITTEN.lock() {
+        output_files_written.insert(output_file.to_path_buf());
+    }
+}
+
+pub fn get_output_files_written() -> Vec<PathBuf> {
+    match OUTPUT_FILES_WRITTEN.lock() {
+        Ok(output_files_written) => output_files_written.iter().cloned().collect(),
+        Err(_) => Vec::new(),
+    }
+}
 
 pub fn print_if_verbose_mode_on<T: Display>(thing_to_print: T) {
     if let Some(verbose_mode) = crate::VERBOSE.get() {
@@ -29,20 +48,76 @@ pub fn generate_log_filename() -> String {
     formatted.to_string()
 }
 
-pub fn hash_csv_record(record: &StringRecord) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    record.iter().for_each(|field| field.hash(&mut hasher));
-    hasher.finish()
+pub fn generate_json_report_filename() -> String {
+    let now = Utc::now();
+    let formatted = now.format("%Y-%m-%d_%H-%M-%S_LAVA_Report.json");
+    formatted.to_string()
+}
+
+pub fn hash_csv_record(record: &StringRecord) -> u128 {
+    hash_fields(record.iter())
+}
+
+/// XXH3-128 with the default secret and a seed of 0, over each field as its length in bytes (u64 little endian) followed by its
+/// UTF-8 bytes. The length prefix keeps ["ab","c"] and ["a","bc"] apart, and the values are the same on every platform and build
+/// so the hashes in the output can be reproduced by anyone
+pub fn hash_fields<'a>(fields: impl Iterator<Item = &'a str>) -> u128 {
+    let mut hasher = Xxh3::with_seed(RECORD_HASH_SEED);
+    for field in fields {
+        hasher.update(&(field.len() as u64).to_le_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.digest128()
+}
+
+/// Zero padded so every hash in the output is the same width
+pub fn format_record_hash(hash: u128) -> String {
+    format!("{:032x}", hash)
+}
+
+/// Seconds with millisecond precision, since records out of order by jitter are often less than a second apart
+pub fn get_seconds_string_from_time_delta(time_delta: TimeDelta) -> String {
+    format!("{:.3}", time_delta.num_milliseconds() as f64 / 1000.0)
 }
 
 pub fn get_file_stem(log_file: &LogFile) -> Result<String> {
-    let file_name = &log_file
-        .file_path
+    let inner_path = log_file.get_inner_path();
+    let file_name = inner_path
         .file_stem()
         .ok_or_else(|| LavaError::new("Could not get file stem.", LavaErrorLevel::Critical))?;
     Ok(file_name.to_string_lossy().to_string())
 }
 
+/// Writes a CSV with a header row, for the output files that are written all at once
+pub fn write_rows_to_file<R: IntoIterator<Item = impl AsRef<[u8]>>>(
+    output_file: &Path,
+    headers: &[&str],
+    rows: impl IntoIterator<Item = R>,
+) -> Result<()> {
+    record_output_file_written(output_file);
+    let mut writer = WriterBuilder::new().from_path(output_file).map_err(|e| {
+        LavaError::new(
+            format!("Unable to create output file because of {e}"),
+            LavaErrorLevel::Medium,
+        )
+    })?;
+    writer.write_record(headers).map_err(|e| {
+        LavaError::new(
+            format!("Unable to write headers to file because of {e}"),
+            LavaErrorLevel::Medium,
+        )
+    })?;
+    for row in rows {
+        writer.write_record(row).map_err(|e| {
+            LavaError::new(
+                format!("Unable to write record because of {e}"),
+                LavaErrorLevel::Medium,
+            )
+        })?;
+    }
+    Ok(())
+}
+
 pub fn write_output_to_csv(
     processed_log_files: &Vec<ProcessedLogFile>,
     execution_settings: &ExecutionSettings,
@@ -50,6 +125,7 @@ pub fn write_output_to_csv(
     // in the final version, maybe have a full version that has tons of fields, and then a simplified version. Could have command line arg to trigger verbose one
     //Add something here to create the
     let output_filepath = execution_settings.output_dir.join(generate_log_filename());
+    record_output_file_written(&output_filepath);
     let mut wtr = Writer::from_path(&output_filepath).map_err(|e| {
         LavaError::new(
             format!("Unable to open ouptut file because of {e}"),
@@ -60,7 +136,14 @@ pub fn write_output_to_csv(
         "Filename",
         "File Path",
         "SHA256 Hash",
+        "SHA256 Hash of Decompressed Content",
+        "MD5 Hash",
+        "SHA1 Hash",
         "Size",
+        "Compression",
+        "Log Type",
+        "Log Type Confidence",
+        "Log Type Reason",
         "First Data Row Used",
         "Header Used",
         "Timestamp Format",
@@ -78,9 +161,28 @@ pub fn write_output_to_csv(
             "Standard Deviation of Time Gaps in {}",
             WELFORD_TIME_SIGNIFIGANCE
         ),
+        &format!("Median Time Gap in {}", WELFORD_TIME_SIGNIFIGANCE),
+        &format!("90th Percentile Time Gap in {}", WELFORD_TIME_SIGNIFIGANCE),
+        &format!("99th Percentile Time Gap in {}", WELFORD_TIME_SIGNIFIGANCE),
+        &format!("Max Time Gap in {}", WELFORD_TIME_SIGNIFIGANCE),
         "LTG Number of Standard Deviations Above the Mean",
+        "Approximate Time Gaps Above Alert Threshold",
+        "Expected Cadence (Seconds)",
+        "Missed Cadence Intervals",
+        "Quiet Activity Periods",
+        "Volume Drop Windows",
+        "Volume Spike Windows",
         "Duplicate Record Count",
+        "Duplicate Detection Mode",
+        "Near Duplicate Clusters",
+        "Near Duplicate Ignored Fields",
+        "Records Also in Other Files",
         "Possible Redactions Count",
+        "EVTX Record ID Gaps",
+        "EVTX Log Cleared Events",
+        "Out of Order Records",
+        "Largest Out of Order Displacement (Seconds)",
+        "DST Transitions",
         "Error",
     ])
     .map_err(|e| {
@@ -113,6 +215,7 @@ pub fn write_errors_to_error_log(
     settings: &ExecutionSettings,
 ) -> Result<()> {
     let error_log_path = settings.output_dir.join("LAVA_Errors.log");
+    record_output_file_written(&error_log_path);
 
     // Open the file in append mode, create it if it doesn't exist
     let file = OpenOptions::new()
@@ -166,6 +269,7 @@ pub fn print_pretty_alerts_and_write_to_alerts_output_file(
         false => None,
         true => {
             let output_file_path: PathBuf = execution_settings.output_dir.join("LAVA_Alerts.log");
+            record_output_file_written(&output_file_path);
             let alert_output_file = OpenOptions::new()
                 .create(true)
                 .append(true)
@@ -176,7 +280,8 @@ pub fn print_pretty_alerts_and_write_to_alerts_output_file(
         }
     };
 
-    let mut alert_table_structure: HashMap<AlertLevel, HashMap<AlertKind, Vec<&String>>> =
+    // Files can have different thresholds from the alert config, so they are grouped by threshold as well as kind
+    let mut alert_table_structure: HashMap<AlertLevel, HashMap<(AlertKind, usize), Vec<&String>>> =
         HashMap::new();
     for processed in results.iter() {
         for alert in processed.alerts.iter() {
@@ -190,14 +295,14 @@ pub fn print_pretty_alerts_and_write_to_alerts_output_file(
                     },
                     alert.alert_level,
                     alert.alert_type.kind().clone(),
-                    get_message_for_alert_output_file(alert.alert_level, alert.alert_type.clone())
+                    get_message_for_alert_output_file(alert)
                 )
                 .expect("Failed to write to alert output file");
             }
             alert_table_structure
                 .entry(alert.alert_level)
                 .or_insert_with(HashMap::new)
-                .entry(alert.alert_type.kind().clone())
+                .entry((alert.alert_type.kind(), alert.threshold))
                 .or_insert_with(Vec::new)
                 .push(processed.file_path.as_ref().unwrap());
         }
@@ -229,8 +334,8 @@ pub fn print_pretty_alerts_and_write_to_alerts_output_file(
                     alerts_cell_string.push_str(&format!(
                         "{}\n",
                         get_message_for_alert_comfy_table(
-                            level.clone(),
-                            alert.clone(),
+                            alert.0.clone(),
+                            alert.1,
                             num_files_in_this_category
                         )
                     ));
@@ -320,6 +425,7 @@ fn alert_level_color(alert_level: &AlertLevel) -> comfy_table::Color {
 
 pub fn convert_vector_of_processed_log_files_into_one_for_multipart(
     all_processed_logs: &Vec<ProcessedLogFile>,
+    execution_settings: &ExecutionSettings,
 ) -> ProcessedLogFile {
     let mut combined_processed_log_file = ProcessedLogFile::default();
     let mut list_of_clean_data_for_individual_processed_log_files: Vec<
@@ -339,10 +445,93 @@ pub fn convert_vector_of_processed_log_files_into_one_for_multipart(
         if let Some(current_num_dupes) = processed_log_file.num_dupes {
             *combined_processed_log_file.num_dupes.get_or_insert(0) += current_num_dupes;
         }
+        if let Some(current_num_clusters) = processed_log_file.num_near_dupe_clusters {
+            *combined_processed_log_file
+                .num_near_dupe_clusters
+                .get_or_insert(0) += current_num_clusters;
+        }
+        combined_processed_log_file.dupe_detection_mode = combined_processed_log_file
+            .dupe_detection_mode
+            .take()
+            .or(processed_log_file.dupe_detection_mode.clone());
+        if let Some(current_num_cross_file_dupes) = processed_log_file.num_cross_file_dupes {
+            *combined_processed_log_file
+                .num_cross_file_dupes
+                .get_or_insert(0) += current_num_cross_file_dupes;
+        }
+        // Shared records between the parts are reported once, on the later part
+        combined_processed_log_file.alerts.extend(
+            processed_log_file
+                .alerts
+                .iter()
+                .filter(|alert| matches!(alert.alert_type, AlertType::CrossFileDupes(_, _, _)))
+                .cloned(),
+        );
         //update redactions
         if let Some(current_num_redactions) = processed_log_file.num_redactions {
             *combined_processed_log_file.num_redactions.get_or_insert(0) += current_num_redactions;
         }
+        //update EVTX checks
+        if let Some(current_num_gaps) = processed_log_file.num_evtx_record_id_gaps {
+            *combined_processed_log_file
+                .num_evtx_record_id_gaps
+                .get_or_insert(0) += current_num_gaps;
+        }
+        if let Some(current_num_cleared) = processed_log_file.num_evtx_log_cleared_events {
+            *combined_processed_log_file
+                .num_evtx_log_cleared_events
+                .get_or_insert(0) += current_num_cleared;
+        }
+        //update out of order records
+        if let Some(current_num_out_of_order) = processed_log_file.num_out_of_order_records {
+            *combined_processed_log_file
+                .num_out_of_order_records
+                .get_or_insert(0) += current_num_out_of_order;
+        }
+        combined_processed_log_file.largest_out_of_order_displacement = combined_processed_log_file
+            .largest_out_of_order_displacement
+            .max(processed_log_file.largest_out_of_order_displacement);
+        //update missed intervals in periodic logs
+        if let Some(current_num_missed) = processed_log_file.num_missed_cadence_intervals {
+            *combined_processed_log_file
+                .num_missed_cadence_intervals
+                .get_or_insert(0) += current_num_missed;
+        }
+        if let Some(current_num_quiet) = processed_log_file.num_quiet_activity_periods {
+            *combined_processed_log_file
+                .num_quiet_activity_periods
+                .get_or_insert(0) += current_num_quiet;
+        }
+        if let Some(current_num_drops) = processed_log_file.num_volume_drops {
+            *combined_processed_log_file
+                .num_volume_drops
+                .get_or_insert(0) += current_num_drops;
+        }
+        if let Some(current_num_spikes) = processed_log_file.num_volume_spikes {
+            *combined_processed_log_file
+                .num_volume_spikes
+                .get_or_insert(0) += current_num_spikes;
+        }
+        if let Some(current_time_gap_sketch) = &processed_log_file.time_gap_sketch {
+            let combined_time_gap_sketch = combined_processed_log_file
+                .time_gap_sketch
+                .get_or_insert_with(TimeGapSketch::default);
+            if let Err(e) = combined_time_gap_sketch.merge(current_time_gap_sketch) {
+                combined_processed_log_file.errors.push(e);
+            }
+        }
+        combined_processed_log_file.expected_cadence = combined_processed_log_file
+            .expected_cadence
+            .or(processed_log_file.expected_cadence);
+        combined_processed_log_file.dst_transitions.extend(
+            processed_log_file.dst_transitions.iter().map(|transition| {
+                format!(
+                    "{} in {}",
+                    transition,
+                    processed_log_file.filename.as_deref().unwrap_or_default()
+                )
+            }),
+        );
 
         // If it can get a full log essentials object, then add that to the list of them
         if let Some(log_combo_essentials) =
@@ -420,6 +609,11 @@ pub fn convert_vector_of_processed_log_files_into_one_for_multipart(
                 previous_stats_essentials.num_time_gaps = count;
                 previous_stats_essentials.time_gap_mean = mean;
                 previous_stats_essentials.time_gap_var = var;
+                if let Some(combined_time_gap_sketch) =
+                    combined_processed_log_file.time_gap_sketch.as_mut()
+                {
+                    combined_time_gap_sketch.add(&gap_between_files);
+                }
 
                 match previous_stats_essentials.largest_gap {
                     Some(prev_largest_gap) => {
@@ -456,16 +650,59 @@ pub fn convert_vector_of_processed_log_files_into_one_for_multipart(
         combined_processed_log_file.variance_time_gap =
             Some(final_combined_essentials.time_gap_var);
     }
-    add_alerts_for_processed_log_file(&mut combined_processed_log_file);
+    // Each part counted against its own threshold, so count again against the combined one
+    if let Some(combined_time_gap_sketch) = &combined_processed_log_file.time_gap_sketch {
+        let time_gap_alert_baseline = TimeGapAlertBaseline {
+            method: execution_settings.time_gap_alert_method,
+            mean: combined_processed_log_file.mean_time_gap.unwrap_or(0.0),
+            std: combined_processed_log_file
+                .variance_time_gap
+                .unwrap_or(0.0)
+                .sqrt(),
+            percentile_99: combined_time_gap_sketch.get_percentile(99.0),
+        };
+        combined_processed_log_file.approx_num_time_gaps_above_threshold = Some(
+            time_gap_alert_baseline
+                .get_minimum_time_gap_above_threshold(
+                    &execution_settings.alert_config.get_default_rules(),
+                )
+                .map(|minimum| combined_time_gap_sketch.count_at_least(minimum))
+                .unwrap_or(0),
+        );
+    }
+    add_alerts_for_processed_log_file(
+        &mut combined_processed_log_file,
+        execution_settings.time_gap_alert_method,
+        execution_settings.alert_config.get_default_rules(),
+    );
 
     combined_processed_log_file
 }
 
-fn add_alerts_for_processed_log_file(processed_log_file: &mut ProcessedLogFile) {
+fn add_alerts_for_processed_log_file(
+    processed_log_file: &mut ProcessedLogFile,
+    time_gap_alert_method: TimeGapAlertMethod,
+    alert_rules: AlertRules,
+) {
     let temp_possible_alert_values = PossibleAlertValues {
         num_records: processed_log_file.timestamp_num_records,
         num_dupes: processed_log_file.num_dupes.unwrap_or(0),
         num_redactions: processed_log_file.num_redactions.unwrap_or(0),
+        num_evtx_record_id_gaps: processed_log_file.num_evtx_record_id_gaps.unwrap_or(0),
+        num_evtx_log_cleared_events: processed_log_file.num_evtx_log_cleared_events.unwrap_or(0),
+        num_out_of_order_records: processed_log_file.num_out_of_order_records.unwrap_or(0),
+        largest_out_of_order_displacement: processed_log_file.largest_out_of_order_displacement,
+        num_missed_cadence_intervals: processed_log_file.num_missed_cadence_intervals.unwrap_or(0),
+        expected_cadence: processed_log_file.expected_cadence,
+        num_quiet_activity_periods: processed_log_file.num_quiet_activity_periods.unwrap_or(0),
+        num_volume_drops: processed_log_file.num_volume_drops.unwrap_or(0),
+        num_volume_spikes: processed_log_file.num_volume_spikes.unwrap_or(0),
+        percentile_99_time_gap: processed_log_file
+            .time_gap_sketch
+            .as_ref()
+            .and_then(|sketch| sketch.get_percentile(99.0)),
+        time_gap_alert_method,
+        alert_rules: alert_rules.clone(),
         largest_time_gap: processed_log_file.largest_gap,
         errors: processed_log_file.errors.clone(),
         mean: processed_log_file.mean_time_gap.unwrap_or(0.0),
@@ -475,6 +712,10 @@ fn add_alerts_for_processed_log_file(processed_log_file: &mut ProcessedLogFile)
     processed_log_file
         .alerts
         .extend(generate_alerts(temp_possible_alert_values));
+    // Overlap alerts are added while combining, before the rules are checked
+    processed_log_file
+        .alerts
+        .retain(|alert| !alert_rules.is_disabled(&alert.alert_type.kind()));
 }
 
 fn combine_mean_values(count1: usize, mean1: f64, count2: usize, mean2: f64) -> Option<f64> {
@@ -540,6 +781,36 @@ mod tests {
     use crate::alerts::tests::dummy_timegap;
     use chrono::NaiveDateTime;
 
+    #[test]
+    fn record_hash_is_length_prefixed_xxh3_128() {
+        let record = StringRecord::from(vec!["ab", "c"]);
+        let mut bytes: Vec<u8> = Vec::new();
+        for field in ["ab", "c"] {
+            bytes.extend((field.len() as u64).to_le_bytes());
+            bytes.extend(field.as_bytes());
+        }
+        assert_eq!(
+            hash_csv_record(&record),
+            xxhash_rust::xxh3::xxh3_128(&bytes)
+        );
+        assert_ne!(
+            hash_csv_record(&record),
+            hash_csv_record(&StringRecord::from(vec!["a", "bc"]))
+        );
+        assert_ne!(
+            hash_csv_record(&StringRecord::from(vec!["a"])),
+            hash_csv_record(&StringRecord::from(vec!["a", ""]))
+        );
+    }
+
+    #[test]
+    fn record_hash_is_stable() {
+        assert_eq!(
+            format_record_hash(hash_csv_record(&StringRecord::new())),
+            "99aa06d3014798d86001c324468d497f"
+        );
+    }
+
     #[test]
     fn test_combine_mean_basic_combination() {
         let mean = combine_mean_values(2, 10.0, 2, 20.0);
@@ -590,9 +861,16 @@ mod tests {
             sha256hash: Some(
                 "d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2d2".to_string(),
             ),
+            decompressed_sha256hash: None,
+            md5hash: None,
+            sha1hash: None,
             filename: Some(name.to_string()),
             file_path: Some(format!("C:/logs/{}", name)),
             size: Some("1.2 MB".to_string()),
+            compression: None,
+            log_type: Some("CSV".to_string()),
+            log_type_confidence: Some(1.0),
+            log_type_reason: Some("Extension was .csv".to_string()),
             first_data_row_used: Some("2".to_string()),
             time_header: Some("timestamp".to_string()),
             time_format: Some("%Y-%m-%d %H:%M:%S".to_string()),
@@ -603,10 +881,30 @@ mod tests {
             largest_gap: largest_gap.map(|et| dummy_timegap(et)), // Example: 1 hour gap
             mean_time_gap: mean_time_gap,
             variance_time_gap: variance,
+            time_gap_sketch: None,
             total_num_records: count,
             timestamp_num_records: count,
             num_dupes: Some(2),
+            num_near_dupe_clusters: None,
+            near_dupe_dropped_fields: Vec::new(),
+            dupe_detection_mode: None,
+            num_cross_file_dupes: None,
+            record_hashes: Vec::new(),
             num_redactions: Some(1),
+            num_evtx_record_id_gaps: None,
+            num_evtx_log_cleared_events: None,
+            num_out_of_order_records: None,
+            largest_out_of_order_displacement: None,
+            approx_num_time_gaps_above_threshold: None,
+            expected_cadence: None,
+            num_missed_cadence_intervals: None,
+            num_quiet_activity_periods: None,
+            num_volume_drops: None,
+            num_volume_spikes: None,
+            dst_transitions: Vec::new(),
+            largest_time_gaps: Vec::new(),
+            hour_of_day_counts: None,
+            day_of_week_counts: None,
             errors: errors,
             alerts: alerts,
         }
@@ -637,7 +935,10 @@ mod tests {
                 vec![Alert::new(AlertLevel::High, AlertType::DupeEvents)],
             ),
         ];
-        let result = convert_vector_of_processed_log_files_into_one_for_multipart(&log_files);
+        let result = convert_vector_of_processed_log_files_into_one_for_multipart(
+            &log_files,
+            &ExecutionSettings::default(),
+        );
         assert_eq!(
             result.min_timestamp,
             Some(
@@ -691,7 +992,10 @@ mod tests {
                 vec![],
             ),
         ];
-        let result = convert_vector_of_processed_log_files_into_one_for_multipart(&log_files);
+        let result = convert_vector_of_processed_log_files_into_one_for_multipart(
+            &log_files,
+            &ExecutionSettings::default(),
+        );
         println!("ALERTS:  {:?}", result.alerts);
         assert_eq!(
             result
@@ -749,7 +1053,10 @@ mod tests {
                 vec![],
             ),
         ];
-        let result = convert_vector_of_processed_log_files_into_one_for_multipart(&log_files);
+        let result = convert_vector_of_processed_log_files_into_one_for_multipart(
+            &log_files,
+            &ExecutionSettings::default(),
+        );
         println!("ALERTS:  {:?}", result.alerts);
         assert_eq!(
             result
@@ -808,7 +1115,10 @@ mod tests {
                 vec![],
             ),
         ];
-        let result = convert_vector_of_processed_log_files_into_one_for_multipart(&log_files);
+        let result = convert_vector_of_processed_log_files_into_one_for_multipart(
+            &log_files,
+            &ExecutionSettings::default(),
+        );
         println!("ALERTS:  {:?}", result.alerts);
         assert_eq!(
             result
@@ -856,7 +1166,10 @@ mod tests {
                 vec![],
             ),
         ];
-        let result = convert_vector_of_processed_log_files_into_one_for_multipart(&log_files);
+        let result = convert_vector_of_processed_log_files_into_one_for_multipart(
+            &log_files,
+            &ExecutionSettings::default(),
+        );
         assert_eq!(
             result.largest_gap.unwrap().get_time_duration_number(),
             3660000
@@ -873,6 +1186,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_combine_processed_log_files_merges_time_gap_percentiles() {
+        let mut log_files: Vec<ProcessedLogFile> = vec![
+            sample_processed_log_file(
+                "test1",
+                Some("2025-08-13 05:00:00"),
+                Some("2025-08-13 05:16:40"),
+                Some(1),
+                Some(1000.0),
+                Some(0.0),
+                1001,
+                vec![],
+                vec![],
+            ),
+            sample_processed_log_file(
+                "test2",
+                Some("2025-08-13 08:00:00"),
+                Some("2025-08-13 08:16:40"),
+                Some(1),
+                Some(1000.0),
+                Some(0.0),
+                1001,
+                vec![],
+                vec![],
+            ),
+        ];
+        for log_file in log_files.iter_mut() {
+            let mut time_gap_sketch = TimeGapSketch::default();
+            for _ in 0..1000 {
+                time_gap_sketch.add(&dummy_timegap(1));
+            }
+            log_file.time_gap_sketch = Some(time_gap_sketch);
+        }
+        let result = convert_vector_of_processed_log_files_into_one_for_multipart(
+            &log_files,
+            &ExecutionSettings {
+                time_gap_alert_method: TimeGapAlertMethod::Percentile,
+                ..Default::default()
+            },
+        );
+        let time_gap_sketch = result.time_gap_sketch.unwrap();
+        assert_eq!(time_gap_sketch.count(), 2001); // Both files plus the gap between them
+        assert!((time_gap_sketch.get_percentile(50.0).unwrap() - 1000.0).abs() < 10.0);
+        assert!((time_gap_sketch.get_max().unwrap() - 9_800_000.0).abs() < 98_000.0);
+        assert_eq!(
+            result
+                .alerts
+                .iter()
+                .filter(|a| a.alert_type == AlertType::SusTimeGapPercentile)
+                .count(),
+            1
+        );
+    }
+
     #[test]
     fn test_combine_processed_log_files_sus_event_count_alert() {
         let log_files: Vec<ProcessedLogFile> = vec![
@@ -899,7 +1266,10 @@ mod tests {
                 vec![],
             ),
         ];
-        let result = convert_vector_of_processed_log_files_into_one_for_multipart(&log_files);
+        let result = convert_vector_of_processed_log_files_into_one_for_multipart(
+            &log_files,
+            &ExecutionSettings::default(),
+        );
         println!("{:?}", result.alerts);
         assert_eq!(
             result
@@ -0,0 +1,561 @@
+use crate::alerts::{AlertLevel, get_message_for_alert_output_file};
+use crate::basic_objects::*;
+use crate::errors::*;
+use crate::helpers::record_output_file_written;
+use chrono::{NaiveDateTime, Utc};
+use num_format::{Locale, ToFormattedString};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+static TIMELINE_WIDTH: f64 = 1000.0;
+static HISTOGRAM_HEIGHT: f64 = 120.0;
+static MIN_GAP_WIDTH: f64 = 2.0; // Keeps short gaps on a long timeline visible
+static DAYS_OF_WEEK: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+// Everything is inline so the report can be handed over as a folder and opened without network access
+static STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2em auto; max-width: 1100px; color: #222; }
+h1, h2 { border-bottom: 1px solid #ddd; padding-bottom: 0.2em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1.5em; }
+th, td { border: 1px solid #ddd; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #f4f4f4; }
+.cards { display: flex; gap: 1em; margin-bottom: 1.5em; }
+.card { flex: 1; padding: 1em; border-radius: 6px; color: #fff; }
+.card .count { font-size: 2em; font-weight: bold; }
+.High { background: #c0392b; }
+.Medium { background: #d68910; }
+.Low { background: #27ae60; }
+.level { color: #fff; padding: 1px 6px; border-radius: 3px; }
+.muted { color: #777; }
+svg text { font-size: 11px; fill: #444; }
+svg .span { fill: #5dade2; }
+svg .gap { fill: #c0392b; }
+svg .bar { fill: #5d6d7e; }
+";
+
+/// Writes HtmlReport/index.html with the alert dashboard and a page for each file next to it
+pub fn write_html_report(
+    processed_log_files: &[ProcessedLogFile],
+    execution_settings: &ExecutionSettings,
+) -> Result<()> {
+    let report_dir = execution_settings.output_dir.join("HtmlReport");
+    fs::create_dir_all(&report_dir).map_err(|e| {
+        LavaError::new(
+            format!("Unable to create HTML report directory because of {e}"),
+            LavaErrorLevel::Medium,
+        )
+    })?;
+    for (index, processed_log_file) in processed_log_files.iter().enumerate() {
+        write_page(
+            &report_dir.join(get_file_page_name(index)),
+            &build_file_page(processed_log_file, &execution_settings.output_dir),
+        )?;
+    }
+    write_page(
+        &report_dir.join("index.html"),
+        &build_index_page(processed_log_files, execution_settings),
+    )
+}
+
+fn write_page(output_file: &Path, content: &str) -> Result<()> {
+    record_output_file_written(output_file);
+    fs::write(output_file, content).map_err(|e| {
+        LavaError::new(
+            format!("Unable to write HTML report because of {e}"),
+            LavaErrorLevel::Medium,
+        )
+    })
+}
+
+fn get_file_page_name(index: usize) -> String {
+    format!("file_{}.html", index + 1)
+}
+
+fn build_index_page(
+    processed_log_files: &[ProcessedLogFile],
+    execution_settings: &ExecutionSettings,
+) -> String {
+    let mut body = String::new();
+    let _ = write!(
+        body,
+        "<h1>LAVA Report</h1><p class=\"muted\">Generated {} from {}</p>",
+        Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        escape_html(&execution_settings.input.to_string_lossy())
+    );
+
+    body.push_str("<h2>Alerts</h2><div class=\"cards\">");
+    for alert_level in [AlertLevel::High, AlertLevel::Medium, AlertLevel::Low] {
+        let num_alerts: usize = processed_log_files
+            .iter()
+            .map(|processed_log_file| {
+                processed_log_file
+                    .alerts
+                    .iter()
+                    .filter(|alert| alert.alert_level == alert_level)
+                    .count()
+            })
+            .sum();
+        let _ = write!(
+            body,
+            "<div class=\"card {:?}\"><div class=\"count\">{}</div>{:?} alerts</div>",
+            alert_level, num_alerts, alert_level
+        );
+    }
+    body.push_str("</div>");
+
+    body.push_str("<table><tr><th>Level</th><th>File</th><th>Alert</th></tr>");
+    for alert_level in [AlertLevel::High, AlertLevel::Medium, AlertLevel::Low] {
+        for (index, processed_log_file) in processed_log_files.iter().enumerate() {
+            for alert in processed_log_file
+                .alerts
+                .iter()
+                .filter(|alert| alert.alert_level == alert_level)
+            {
+                let _ = write!(
+                    body,
+                    "<tr><td><span class=\"level {:?}\">{:?}</span></td><td><a href=\"{}\">{}</a></td><td>{}</td></tr>",
+                    alert_level,
+                    alert_level,
+                    get_file_page_name(index),
+                    escape_html(
+                        alert
+                            .original_filepath
+                            .as_deref()
+                            .or(processed_log_file.file_path.as_deref())
+                            .unwrap_or("")
+                    ),
+                    escape_html(&get_message_for_alert_output_file(alert))
+                );
+            }
+        }
+    }
+    body.push_str("</table>");
+
+    body.push_str(
+        "<h2>Files</h2><table><tr><th>File</th><th>Log Type</th><th>Records</th><th>Min Timestamp</th><th>Max Timestamp</th><th>Largest Time Gap</th><th>Alerts</th><th>Errors</th></tr>",
+    );
+    for (index, processed_log_file) in processed_log_files.iter().enumerate() {
+        let _ = write!(
+            body,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            get_file_page_name(index),
+            escape_html(processed_log_file.filename.as_deref().unwrap_or("")),
+            escape_html(processed_log_file.log_type.as_deref().unwrap_or("")),
+            processed_log_file
+                .total_num_records
+                .to_formatted_string(&Locale::en),
+            format_optional_timestamp(processed_log_file.min_timestamp),
+            format_optional_timestamp(processed_log_file.max_timestamp),
+            processed_log_file
+                .largest_gap
+                .map(|largest_gap| escape_html(&largest_gap.to_string()))
+                .unwrap_or_default(),
+            processed_log_file.alerts.len(),
+            processed_log_file.errors.len()
+        );
+    }
+    body.push_str("</table>");
+
+    body.push_str("<h2>Errors</h2>");
+    let num_errors: usize = processed_log_files
+        .iter()
+        .map(|processed_log_file| processed_log_file.errors.len())
+        .sum();
+    if num_errors == 0 {
+        body.push_str("<p class=\"muted\">No errors</p>");
+    } else {
+        body.push_str("<p><a href=\"../LAVA_Errors.log\">LAVA_Errors.log</a></p>");
+        body.push_str("<table><tr><th>Level</th><th>File</th><th>Error</th></tr>");
+        for processed_log_file in processed_log_files.iter() {
+            for error in processed_log_file.errors.iter() {
+                let _ = write!(
+                    body,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    error.level,
+                    escape_html(processed_log_file.filename.as_deref().unwrap_or("")),
+                    escape_html(&error.reason)
+                );
+            }
+        }
+        body.push_str("</table>");
+    }
+
+    wrap_page("LAVA Report", &body)
+}
+
+fn build_file_page(processed_log_file: &ProcessedLogFile, output_dir: &Path) -> String {
+    let filename = processed_log_file.filename.as_deref().unwrap_or("");
+    let mut body = String::new();
+    let _ = write!(
+        body,
+        "<p><a href=\"index.html\">Back to summary</a></p><h1>{}</h1>",
+        escape_html(filename)
+    );
+
+    body.push_str("<table>");
+    let optional_count = |count: Option<usize>| {
+        count
+            .map(|v| v.to_formatted_string(&Locale::en))
+            .unwrap_or_default()
+    };
+    let summary_rows = [
+        (
+            "File Path",
+            escape_html(processed_log_file.file_path.as_deref().unwrap_or("")),
+        ),
+        (
+            "SHA256 Hash",
+            escape_html(processed_log_file.sha256hash.as_deref().unwrap_or("")),
+        ),
+        (
+            "MD5 Hash",
+            escape_html(processed_log_file.md5hash.as_deref().unwrap_or("")),
+        ),
+        (
+            "SHA1 Hash",
+            escape_html(processed_log_file.sha1hash.as_deref().unwrap_or("")),
+        ),
+        (
+            "Log Type",
+            escape_html(processed_log_file.log_type.as_deref().unwrap_or("")),
+        ),
+        (
+            "Header Used",
+            escape_html(processed_log_file.time_header.as_deref().unwrap_or("")),
+        ),
+        (
+            "Timestamp Format",
+            escape_html(processed_log_file.time_format.as_deref().unwrap_or("")),
+        ),
+        (
+            "Total Number of Records",
+            processed_log_file
+                .total_num_records
+                .to_formatted_string(&Locale::en),
+        ),
+        (
+            "Records With Timestamps",
+            processed_log_file
+                .timestamp_num_records
+                .to_formatted_string(&Locale::en),
+        ),
+        (
+            "Min Timestamp",
+            format_optional_timestamp(processed_log_file.min_timestamp),
+        ),
+        (
+            "Max Timestamp",
+            format_optional_timestamp(processed_log_file.max_timestamp),
+        ),
+        (
+            "Largest Time Gap",
+            processed_log_file
+                .largest_gap
+                .map(|largest_gap| escape_html(&largest_gap.to_string()))
+                .unwrap_or_default(),
+        ),
+        (
+            "Duplicate Records",
+            optional_count(processed_log_file.num_dupes),
+        ),
+        (
+            "Near Duplicate Clusters",
+            optional_count(processed_log_file.num_near_dupe_clusters),
+        ),
+        (
+            "Records Also in Other Files",
+            optional_count(processed_log_file.num_cross_file_dupes),
+        ),
+        (
+            "Possible Redactions",
+            optional_count(processed_log_file.num_redactions),
+        ),
+    ];
+    for (name, value) in summary_rows {
+        let _ = write!(body, "<tr><th>{}</th><td>{}</td></tr>", name, value);
+    }
+    body.push_str("</table>");
+
+    body.push_str("<h2>Timeline</h2>");
+    match (
+        processed_log_file.min_timestamp,
+        processed_log_file.max_timestamp,
+    ) {
+        (Some(min_timestamp), Some(max_timestamp)) => body.push_str(&build_timeline_svg(
+            min_timestamp,
+            max_timestamp,
+            &processed_log_file.largest_time_gaps,
+        )),
+        _ => body.push_str("<p class=\"muted\">No timestamps were processed for this file</p>"),
+    }
+
+    if let (Some(hour_of_day_counts), Some(day_of_week_counts)) = (
+        processed_log_file.hour_of_day_counts,
+        processed_log_file.day_of_week_counts,
+    ) {
+        body.push_str("<h2>Activity</h2>");
+        let hour_labels: Vec<String> = (0..24).map(|hour| format!("{:02}", hour)).collect();
+        body.push_str(&build_histogram_svg(&hour_labels, &hour_of_day_counts));
+        let day_labels: Vec<String> = DAYS_OF_WEEK.iter().map(|day| day.to_string()).collect();
+        body.push_str(&build_histogram_svg(&day_labels, &day_of_week_counts));
+    }
+
+    body.push_str("<h2>Alerts</h2>");
+    if processed_log_file.alerts.is_empty() {
+        body.push_str("<p class=\"muted\">No alerts</p>");
+    } else {
+        body.push_str("<table><tr><th>Level</th><th>Alert</th></tr>");
+        for alert in processed_log_file.alerts.iter() {
+            let _ = write!(
+                body,
+                "<tr><td><span class=\"level {:?}\">{:?}</span></td><td>{}</td></tr>",
+                alert.alert_level,
+                alert.alert_level,
+                escape_html(&get_message_for_alert_output_file(alert))
+            );
+        }
+        body.push_str("</table>");
+    }
+
+    let file_stem = Path::new(filename)
+        .file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let output_files: Vec<String> = [
+        format!("Duplicates/{}_DUPLICATES.csv", file_stem),
+        format!("Duplicates/{}_DUPLICATE_SUMMARY.csv", file_stem),
+        format!("Duplicates/{}_SPILLED_REPEATS.csv", file_stem),
+        format!("NearDuplicates/{}_NEAR_DUPLICATES.csv", file_stem),
+        format!("Redactions/{}_POSSIBLE_REDACTIONS.csv", file_stem),
+        format!("Gaps/{}_GAPS.csv", file_stem),
+    ]
+    .into_iter()
+    .filter(|output_file| output_dir.join(output_file).exists())
+    .collect();
+    if !output_files.is_empty() {
+        body.push_str("<h2>Output Files</h2><ul>");
+        for output_file in output_files {
+            let _ = write!(
+                body,
+                "<li><a href=\"../{}\">{}</a></li>",
+                encode_href(&output_file),
+                escape_html(&output_file)
+            );
+        }
+        body.push_str("</ul>");
+    }
+
+    if !processed_log_file.errors.is_empty() {
+        body.push_str("<h2>Errors</h2><ul>");
+        for error in processed_log_file.errors.iter() {
+            let _ = write!(
+                body,
+                "<li>[{}] {}</li>",
+                error.level,
+                escape_html(&error.reason)
+            );
+        }
+        body.push_str("</ul>");
+    }
+
+    wrap_page(filename, &body)
+}
+
+/// A bar from the first to the last timestamp with the top time gaps drawn over it in red
+fn build_timeline_svg(
+    min_timestamp: NaiveDateTime,
+    max_timestamp: NaiveDateTime,
+    time_gaps: &[IndexedTimeGap],
+) -> String {
+    let total_seconds = (max_timestamp - min_timestamp).num_milliseconds() as f64 / 1000.0;
+    let mut svg = format!(
+        "<svg width=\"100%\" viewBox=\"0 0 {} 60\" xmlns=\"http://www.w3.org/2000/svg\"><rect class=\"span\" x=\"0\" y=\"10\" width=\"{}\" height=\"24\"/>",
+        TIMELINE_WIDTH, TIMELINE_WIDTH
+    );
+    if total_seconds > 0.0 {
+        for indexed_time_gap in time_gaps {
+            let time_gap = indexed_time_gap.time_gap;
+            let x = get_timeline_position(min_timestamp, time_gap.beginning_time, total_seconds);
+            let width = (get_timeline_position(min_timestamp, time_gap.end_time, total_seconds)
+                - x)
+                .max(MIN_GAP_WIDTH);
+            let _ = write!(
+                svg,
+                "<rect class=\"gap\" x=\"{:.1}\" y=\"10\" width=\"{:.1}\" height=\"24\"><title>{}</title></rect>",
+                x,
+                width,
+                escape_html(&format!(
+                    "{} ({})",
+                    time_gap,
+                    ProcessedLogFile::convert_time_delta_to_number_of_hours(time_gap.gap)
+                ))
+            );
+        }
+    }
+    let _ = write!(
+        svg,
+        "<text x=\"0\" y=\"52\">{}</text><text x=\"{}\" y=\"52\" text-anchor=\"end\">{}</text></svg>",
+        min_timestamp.format("%Y-%m-%d %H:%M:%S"),
+        TIMELINE_WIDTH,
+        max_timestamp.format("%Y-%m-%d %H:%M:%S")
+    );
+    svg
+}
+
+fn get_timeline_position(
+    min_timestamp: NaiveDateTime,
+    timestamp: NaiveDateTime,
+    total_seconds: f64,
+) -> f64 {
+    (timestamp - min_timestamp).num_milliseconds() as f64 / 1000.0 / total_seconds * TIMELINE_WIDTH
+}
+
+fn build_histogram_svg(labels: &[String], counts: &[usize]) -> String {
+    let max_count = counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let bar_width = TIMELINE_WIDTH / counts.len() as f64;
+    let mut svg = format!(
+        "<svg width=\"100%\" viewBox=\"0 0 {} {}\" xmlns=\"http://www.w3.org/2000/svg\">",
+        TIMELINE_WIDTH,
+        HISTOGRAM_HEIGHT + 20.0
+    );
+    for (i, (label, count)) in labels.iter().zip(counts).enumerate() {
+        let height = *count as f64 / max_count * HISTOGRAM_HEIGHT;
+        let x = i as f64 * bar_width;
+        let _ = write!(
+            svg,
+            "<rect class=\"bar\" x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"><title>{}: {}</title></rect><text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            x + 1.0,
+            HISTOGRAM_HEIGHT - height,
+            bar_width - 2.0,
+            height,
+            escape_html(label),
+            count,
+            x + bar_width / 2.0,
+            HISTOGRAM_HEIGHT + 14.0,
+            escape_html(label)
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn wrap_page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>{}</body></html>\n",
+        escape_html(title),
+        STYLE,
+        body
+    )
+}
+
+fn format_optional_timestamp(timestamp: Option<NaiveDateTime>) -> String {
+    timestamp
+        .map(|timestamp| timestamp.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+fn escape_html(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Relative links to output files, which are named after the log file and can have spaces or # in them
+fn encode_href(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod html_report_tests {
+    use super::*;
+    use crate::alerts::{Alert, AlertType};
+    use crate::test_helpers::dt;
+    use tempfile::TempDir;
+
+    #[test]
+    fn escapes_html_and_links() {
+        assert_eq!(
+            escape_html("<script>\"a\" & 'b'</script>"),
+            "&lt;script&gt;&quot;a&quot; &amp; &#39;b&#39;&lt;/script&gt;"
+        );
+        assert_eq!(
+            encode_href("Duplicates/my log #2_DUPLICATES.csv"),
+            "Duplicates/my%20log%20%232_DUPLICATES.csv"
+        );
+    }
+
+    #[test]
+    fn timeline_places_gaps_by_time() {
+        let time_gap = IndexedTimeGap {
+            time_gap: TimeGap::new(dt("2025-05-09 06:00:00"), dt("2025-05-09 12:00:00")),
+            beginning_index: 1,
+            end_index: 2,
+        };
+        let svg = build_timeline_svg(
+            dt("2025-05-09 00:00:00"),
+            dt("2025-05-10 00:00:00"),
+            &[time_gap],
+        );
+        assert!(svg.contains("<rect class=\"gap\" x=\"250.0\" y=\"10\" width=\"250.0\""));
+    }
+
+    #[test]
+    fn writes_index_and_file_pages() {
+        let output_dir = TempDir::new().unwrap();
+        fs::create_dir_all(output_dir.path().join("Duplicates")).unwrap();
+        fs::write(
+            output_dir.path().join("Duplicates/app_DUPLICATES.csv"),
+            "Index,Record\n",
+        )
+        .unwrap();
+        let execution_settings = ExecutionSettings {
+            output_dir: output_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let processed_log_files = vec![ProcessedLogFile {
+            filename: Some("app.csv".to_string()),
+            file_path: Some("/logs/app.csv".to_string()),
+            min_timestamp: Some(dt("2025-05-09 00:00:00")),
+            max_timestamp: Some(dt("2025-05-10 00:00:00")),
+            hour_of_day_counts: Some([1; 24]),
+            day_of_week_counts: Some([24, 0, 0, 0, 0, 0, 0]),
+            num_dupes: Some(1),
+            alerts: vec![Alert::new(AlertLevel::Low, AlertType::DupeEvents)],
+            errors: vec![LavaError::new("<bad> line", LavaErrorLevel::Medium)],
+            ..Default::default()
+        }];
+
+        write_html_report(&processed_log_files, &execution_settings).unwrap();
+
+        let index = fs::read_to_string(output_dir.path().join("HtmlReport/index.html")).unwrap();
+        let file_page =
+            fs::read_to_string(output_dir.path().join("HtmlReport/file_1.html")).unwrap();
+        assert!(index.contains("<div class=\"count\">1</div>Low alerts"));
+        assert!(index.contains("&lt;bad&gt; line"));
+        assert!(file_page.contains("href=\"../Duplicates/app_DUPLICATES.csv\""));
+        assert!(!file_page.contains("POSSIBLE_REDACTIONS"));
+        assert!(file_page.contains("<h2>Activity</h2>"));
+        assert!(!file_page.contains("<link") && !file_page.contains("<script"));
+    }
+}
@@ -0,0 +1,456 @@
+use crate::alert_config::AlertConfig;
+use crate::alerts::{AlertKind, AlertLevel, get_message_for_alert_output_file};
+use crate::basic_objects::*;
+use crate::errors::*;
+use crate::helpers::record_output_file_written;
+use chrono::{NaiveDateTime, SecondsFormat, TimeDelta, Utc};
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Duration;
+
+/// Everything in the output CSV plus the alerts and errors, with typed values instead of display strings.
+/// Timestamps are RFC 3339 in UTC and all durations are in seconds
+#[derive(Debug, Serialize)]
+pub struct JsonReport<'a> {
+    pub run: JsonReportRun<'a>,
+    pub files: Vec<JsonReportFile<'a>>,
+    pub multipart_combined: Option<JsonReportFile<'a>>, // Only in multipart mode, the parts are still in files
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonReportRun<'a> {
+    pub lava_version: &'static str,
+    pub generated_at: String,
+    pub run_duration_seconds: f64,
+    pub input: String,
+    pub output_dir: String,
+    pub num_files: usize,
+    pub total_num_records_with_timestamps: usize,
+    pub settings: JsonReportSettings<'a>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonReportSettings<'a> {
+    pub quick_mode: bool,
+    pub multipart_mode: bool,
+    pub timestamp_field: Option<&'a str>,
+    pub syslog_year: Option<i32>,
+    pub assumed_timezone: Option<String>,
+    pub tolerant_jitter_seconds: Option<f64>, // Only set in tolerant mode
+    pub tolerant_max_out_of_order_records: Option<usize>,
+    pub num_top_time_gaps: usize,
+    pub volume_window: String,
+    pub time_gap_alert_method: &'static str,
+    pub hash_algorithms: Vec<String>,
+    pub near_dupe_ignored_fields: Option<&'a [String]>, // Only set when near duplicate detection is on
+    pub cross_file_dupes: bool,
+    pub dupe_detection_mode: String,
+    pub dupe_memory_budget_mb: Option<usize>, // Only set in spill mode
+    pub dupe_false_positive_rate: Option<f64>, // Only set in approximate mode
+    pub alert_config: AlertConfig,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonReportFile<'a> {
+    pub filename: Option<&'a str>,
+    pub file_path: Option<&'a str>,
+    pub sha256_hash: Option<&'a str>,
+    pub decompressed_sha256_hash: Option<&'a str>,
+    pub md5_hash: Option<&'a str>,
+    pub sha1_hash: Option<&'a str>,
+    pub size: Option<&'a str>,
+    pub compression: Option<&'a str>,
+    pub log_type: Option<&'a str>,
+    pub log_type_confidence: Option<f64>,
+    pub log_type_reason: Option<&'a str>,
+    pub first_data_row_used: Option<&'a str>,
+    pub header_used: Option<&'a str>,
+    pub timestamp_format: Option<&'a str>,
+    pub total_num_records: usize,
+    pub num_records_with_timestamps: usize,
+    pub min_timestamp: Option<String>,
+    pub max_timestamp: Option<String>,
+    pub duration_seconds: Option<f64>,
+    pub largest_time_gap: Option<JsonReportTimeGap>,
+    pub time_gap_statistics: Option<JsonReportTimeGapStatistics>,
+    pub approx_num_time_gaps_above_threshold: Option<usize>,
+    pub expected_cadence_seconds: Option<f64>,
+    pub num_missed_cadence_intervals: Option<usize>,
+    pub num_quiet_activity_periods: Option<usize>,
+    pub num_volume_drops: Option<usize>,
+    pub num_volume_spikes: Option<usize>,
+    pub num_dupes: Option<usize>,
+    pub num_near_dupe_clusters: Option<usize>,
+    pub near_dupe_dropped_fields: &'a [String],
+    pub num_cross_file_dupes: Option<usize>,
+    pub dupe_detection_mode: Option<&'a str>,
+    pub num_redactions: Option<usize>,
+    pub num_evtx_record_id_gaps: Option<usize>,
+    pub num_evtx_log_cleared_events: Option<usize>,
+    pub num_out_of_order_records: Option<usize>,
+    pub largest_out_of_order_displacement_seconds: Option<f64>,
+    pub dst_transitions: &'a [String],
+    pub alerts: Vec<JsonReportAlert<'a>>,
+    pub errors: &'a [LavaError],
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct JsonReportTimeGap {
+    pub beginning_time: String,
+    pub end_time: String,
+    pub duration_seconds: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonReportTimeGapStatistics {
+    pub mean_seconds: f64,
+    pub standard_deviation_seconds: f64,
+    pub median_seconds: Option<f64>,
+    pub percentile_90_seconds: Option<f64>,
+    pub percentile_99_seconds: Option<f64>,
+    pub max_seconds: Option<f64>,
+    pub largest_gap_std_devs_above_mean: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonReportAlert<'a> {
+    pub level: AlertLevel,
+    pub kind: AlertKind,
+    pub threshold: usize,
+    pub message: String,
+    pub file_path: Option<&'a str>, // The original file for alerts carried into a multipart result
+}
+
+impl<'a> JsonReport<'a> {
+    pub fn new(
+        processed_log_files: &'a [ProcessedLogFile],
+        multipart_combined_log_file: Option<&'a ProcessedLogFile>,
+        execution_settings: &'a ExecutionSettings,
+        run_duration: Duration,
+    ) -> Self {
+        Self {
+            run: JsonReportRun {
+                lava_version: env!("CARGO_PKG_VERSION"),
+                generated_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                run_duration_seconds: run_duration.as_secs_f64(),
+                input: execution_settings.input.to_string_lossy().to_string(),
+                output_dir: execution_settings.output_dir.to_string_lossy().to_string(),
+                num_files: processed_log_files.len(),
+                total_num_records_with_timestamps: processed_log_files
+                    .iter()
+                    .map(|processed_log_file| processed_log_file.timestamp_num_records)
+                    .sum(),
+                settings: JsonReportSettings::new(execution_settings),
+            },
+            files: processed_log_files
+                .iter()
+                .map(JsonReportFile::new)
+                .collect(),
+            multipart_combined: multipart_combined_log_file.map(JsonReportFile::new),
+        }
+    }
+
+    pub fn write_to_file(&self, output_file: &Path) -> Result<()> {
+        record_output_file_written(output_file);
+        let file = File::create(output_file).map_err(|e| {
+            LavaError::new(
+                format!("Unable to create JSON report because of {e}"),
+                LavaErrorLevel::Medium,
+            )
+        })?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self).map_err(|e| {
+            LavaError::new(
+                format!("Unable to write JSON report because of {e}"),
+                LavaErrorLevel::Medium,
+            )
+        })
+    }
+}
+
+impl<'a> JsonReportSettings<'a> {
+    pub(crate) fn new(execution_settings: &'a ExecutionSettings) -> Self {
+        Self {
+            quick_mode: execution_settings.quick_mode,
+            multipart_mode: execution_settings.multipart_mode,
+            timestamp_field: execution_settings.timestamp_field.as_deref(),
+            syslog_year: execution_settings.syslog_year,
+            assumed_timezone: execution_settings.assumed_timezone.map(|assumed_timezone| {
+                match assumed_timezone {
+                    AssumedTimezone::Offset(offset) => offset.to_string(),
+                    AssumedTimezone::Named(tz) => tz.name().to_string(),
+                }
+            }),
+            tolerant_jitter_seconds: execution_settings
+                .out_of_order_tolerance
+                .as_ref()
+                .map(|tolerance| get_seconds(tolerance.jitter_window)),
+            tolerant_max_out_of_order_records: execution_settings
+                .out_of_order_tolerance
+                .as_ref()
+                .map(|tolerance| tolerance.max_out_of_order_records),
+            num_top_time_gaps: execution_settings.num_top_time_gaps,
+            volume_window: execution_settings.volume_window.to_string(),
+            time_gap_alert_method: match execution_settings.time_gap_alert_method {
+                TimeGapAlertMethod::StandardDeviations => "std",
+                TimeGapAlertMethod::Percentile => "percentile",
+            },
+            hash_algorithms: execution_settings
+                .hash_algorithms
+                .iter()
+                .map(|hash_algorithm| hash_algorithm.to_string())
+                .collect(),
+            near_dupe_ignored_fields: execution_settings
+                .near_dupe_settings
+                .as_ref()
+                .map(|near_dupe_settings| near_dupe_settings.ignored_fields.as_slice()),
+            cross_file_dupes: execution_settings.cross_file_dupes,
+            dupe_detection_mode: execution_settings.dupe_detection_mode.to_string(),
+            dupe_memory_budget_mb: match execution_settings.dupe_detection_mode {
+                DupeDetectionMode::Spill {
+                    memory_budget_bytes,
+                } => Some(memory_budget_bytes / (1024 * 1024)),
+                _ => None,
+            },
+            dupe_false_positive_rate: match execution_settings.dupe_detection_mode {
+                DupeDetectionMode::Approximate {
+                    false_positive_rate,
+                } => Some(false_positive_rate),
+                _ => None,
+            },
+            alert_config: execution_settings.alert_config.get_effective_config(),
+        }
+    }
+}
+
+impl<'a> JsonReportFile<'a> {
+    fn new(processed_log_file: &'a ProcessedLogFile) -> Self {
+        Self {
+            filename: processed_log_file.filename.as_deref(),
+            file_path: processed_log_file.file_path.as_deref(),
+            sha256_hash: processed_log_file.sha256hash.as_deref(),
+            decompressed_sha256_hash: processed_log_file.decompressed_sha256hash.as_deref(),
+            md5_hash: processed_log_file.md5hash.as_deref(),
+            sha1_hash: processed_log_file.sha1hash.as_deref(),
+            size: processed_log_file.size.as_deref(),
+            compression: processed_log_file.compression.as_deref(),
+            log_type: processed_log_file.log_type.as_deref(),
+            log_type_confidence: processed_log_file.log_type_confidence,
+            log_type_reason: processed_log_file.log_type_reason.as_deref(),
+            first_data_row_used: processed_log_file.first_data_row_used.as_deref(),
+            header_used: processed_log_file.time_header.as_deref(),
+            timestamp_format: processed_log_file.time_format.as_deref(),
+            total_num_records: processed_log_file.total_num_records,
+            num_records_with_timestamps: processed_log_file.timestamp_num_records,
+            min_timestamp: processed_log_file.min_timestamp.map(format_timestamp),
+            max_timestamp: processed_log_file.max_timestamp.map(format_timestamp),
+            duration_seconds: processed_log_file
+                .max_timestamp
+                .zip(processed_log_file.min_timestamp)
+                .map(|(max_timestamp, min_timestamp)| get_seconds(max_timestamp - min_timestamp)),
+            largest_time_gap: processed_log_file
+                .largest_gap
+                .map(|largest_gap| JsonReportTimeGap {
+                    beginning_time: format_timestamp(largest_gap.beginning_time),
+                    end_time: format_timestamp(largest_gap.end_time),
+                    duration_seconds: get_seconds(largest_gap.gap),
+                }),
+            time_gap_statistics: JsonReportTimeGapStatistics::new(processed_log_file),
+            approx_num_time_gaps_above_threshold: processed_log_file
+                .approx_num_time_gaps_above_threshold,
+            expected_cadence_seconds: processed_log_file.expected_cadence.map(get_seconds),
+            num_missed_cadence_intervals: processed_log_file.num_missed_cadence_intervals,
+            num_quiet_activity_periods: processed_log_file.num_quiet_activity_periods,
+            num_volume_drops: processed_log_file.num_volume_drops,
+            num_volume_spikes: processed_log_file.num_volume_spikes,
+            num_dupes: processed_log_file.num_dupes,
+            num_near_dupe_clusters: processed_log_file.num_near_dupe_clusters,
+            near_dupe_dropped_fields: &processed_log_file.near_dupe_dropped_fields,
+            num_cross_file_dupes: processed_log_file.num_cross_file_dupes,
+            dupe_detection_mode: processed_log_file.dupe_detection_mode.as_deref(),
+            num_redactions: processed_log_file.num_redactions,
+            num_evtx_record_id_gaps: processed_log_file.num_evtx_record_id_gaps,
+            num_evtx_log_cleared_events: processed_log_file.num_evtx_log_cleared_events,
+            num_out_of_order_records: processed_log_file.num_out_of_order_records,
+            largest_out_of_order_displacement_seconds: processed_log_file
+                .largest_out_of_order_displacement
+                .map(get_seconds),
+            dst_transitions: &processed_log_file.dst_transitions,
+            alerts: processed_log_file
+                .alerts
+                .iter()
+                .map(|alert| JsonReportAlert {
+                    level: alert.alert_level,
+                    kind: alert.alert_type.kind(),
+                    threshold: alert.threshold,
+                    message: get_message_for_alert_output_file(alert),
+                    file_path: alert
+                        .original_filepath
+                        .as_deref()
+                        .or(processed_log_file.file_path.as_deref()),
+                })
+                .collect(),
+            errors: &processed_log_file.errors,
+        }
+    }
+}
+
+impl JsonReportTimeGapStatistics {
+    /// The Welford stats and the sketch are in WELFORD_TIME_SIGNIFIGANCE units, which are converted to seconds here
+    fn new(processed_log_file: &ProcessedLogFile) -> Option<Self> {
+        let units_per_second = WELFORD_TIME_SIGNIFIGANCE.get_units_per_second();
+        let mean = processed_log_file.mean_time_gap?;
+        let standard_deviation = processed_log_file.variance_time_gap?.sqrt();
+        let time_gap_sketch = processed_log_file.time_gap_sketch.as_ref();
+        let get_percentile = |percentile: f64| {
+            time_gap_sketch
+                .and_then(|sketch| sketch.get_percentile(percentile))
+                .map(|v| v / units_per_second)
+        };
+        Some(Self {
+            mean_seconds: mean / units_per_second,
+            standard_deviation_seconds: standard_deviation / units_per_second,
+            median_seconds: get_percentile(50.0),
+            percentile_90_seconds: get_percentile(90.0),
+            percentile_99_seconds: get_percentile(99.0),
+            max_seconds: time_gap_sketch
+                .and_then(|sketch| sketch.get_max())
+                .map(|v| v / units_per_second),
+            largest_gap_std_devs_above_mean: processed_log_file
+                .largest_gap
+                .filter(|_| standard_deviation > 0.0)
+                .map(|largest_gap| {
+                    (largest_gap.get_time_duration_number() as f64 - mean) / standard_deviation
+                }),
+        })
+    }
+}
+
+fn format_timestamp(timestamp: NaiveDateTime) -> String {
+    timestamp
+        .and_utc()
+        .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn get_seconds(time_delta: TimeDelta) -> f64 {
+    time_delta.num_milliseconds() as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod json_report_tests {
+    use super::*;
+    use crate::alerts::{Alert, AlertType};
+    use crate::test_helpers::dt;
+    use serde_json::Value;
+
+    fn sample_processed_log_file() -> ProcessedLogFile {
+        ProcessedLogFile {
+            filename: Some("app.csv".to_string()),
+            file_path: Some("/logs/app.csv".to_string()),
+            log_type: Some("CSV".to_string()),
+            time_header: Some("date".to_string()),
+            time_format: Some("YYYY-MM-DD HH:MM:SS".to_string()),
+            min_timestamp: Some(dt("2025-05-09 10:00:00")),
+            max_timestamp: Some(dt("2025-05-09 10:12:33")),
+            largest_gap: Some(TimeGap::new(
+                dt("2025-05-09 10:00:00"),
+                dt("2025-05-09 10:12:30"),
+            )),
+            mean_time_gap: Some(251_000.0),
+            variance_time_gap: Some(0.0),
+            total_num_records: 4,
+            timestamp_num_records: 4,
+            num_dupes: Some(1),
+            errors: vec![LavaError::new("Some error", LavaErrorLevel::Medium)],
+            alerts: vec![Alert::new(AlertLevel::Low, AlertType::DupeEvents)],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn report_has_typed_values_instead_of_display_strings() {
+        let processed_log_files = vec![sample_processed_log_file()];
+        let execution_settings = ExecutionSettings::default();
+        let report = JsonReport::new(
+            &processed_log_files,
+            None,
+            &execution_settings,
+            Duration::from_secs(2),
+        );
+        let json: Value = serde_json::to_value(&report).unwrap();
+        let file = &json["files"][0];
+
+        assert_eq!(json["run"]["num_files"], 1);
+        assert_eq!(json["run"]["settings"]["time_gap_alert_method"], "std");
+        assert_eq!(file["header_used"], "date");
+        assert_eq!(file["min_timestamp"], "2025-05-09T10:00:00Z");
+        assert_eq!(file["duration_seconds"], 753.0);
+        assert_eq!(
+            file["largest_time_gap"]["beginning_time"],
+            "2025-05-09T10:00:00Z"
+        );
+        assert_eq!(file["largest_time_gap"]["duration_seconds"], 750.0);
+        assert_eq!(file["time_gap_statistics"]["mean_seconds"], 251.0);
+        assert_eq!(
+            file["time_gap_statistics"]["largest_gap_std_devs_above_mean"],
+            Value::Null
+        );
+        assert_eq!(file["num_redactions"], Value::Null);
+        assert_eq!(file["alerts"][0]["level"], "Low");
+        assert_eq!(file["alerts"][0]["kind"], "DupeEvents");
+        assert_eq!(file["alerts"][0]["file_path"], "/logs/app.csv");
+        assert_eq!(file["errors"][0]["level"], "Medium");
+        assert_eq!(file["errors"][0]["reason"], "Some error");
+    }
+
+    #[test]
+    fn file_without_timestamps_has_no_time_statistics() {
+        let processed_log_files = vec![ProcessedLogFile {
+            filename: Some("empty.log".to_string()),
+            ..Default::default()
+        }];
+        let execution_settings = ExecutionSettings::default();
+        let report = JsonReport::new(
+            &processed_log_files,
+            None,
+            &execution_settings,
+            Duration::ZERO,
+        );
+        let json: Value = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["files"][0]["largest_time_gap"], Value::Null);
+        assert_eq!(json["files"][0]["time_gap_statistics"], Value::Null);
+        assert_eq!(json["files"][0]["duration_seconds"], Value::Null);
+    }
+
+    #[test]
+    fn multipart_report_keeps_each_part_and_the_combined_file() {
+        let processed_log_files = vec![
+            sample_processed_log_file(),
+            ProcessedLogFile {
+                filename: Some("app.1.csv".to_string()),
+                ..sample_processed_log_file()
+            },
+        ];
+        let combined_log_file = ProcessedLogFile {
+            timestamp_num_records: 8,
+            ..Default::default()
+        };
+        let execution_settings = ExecutionSettings {
+            multipart_mode: true,
+            ..Default::default()
+        };
+        let report = JsonReport::new(
+            &processed_log_files,
+            Some(&combined_log_file),
+            &execution_settings,
+            Duration::ZERO,
+        );
+        let json: Value = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["run"]["num_files"], 2);
+        assert_eq!(json["files"][1]["filename"], "app.1.csv");
+        assert_eq!(json["multipart_combined"]["num_records_with_timestamps"], 8);
+    }
+}
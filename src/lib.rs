@@ -1,6 +1,6 @@
+use chrono::Utc;
 use glob::glob;
 use rayon::prelude::*;
-use sha2::{Digest, Sha256};
 use std::fs::{File, metadata};
 use std::io::Read;
 use std::path::PathBuf;
@@ -8,16 +8,42 @@ mod errors;
 use errors::*;
 mod handlers {
     pub mod csv_handlers;
+    pub mod evtx_handlers;
     pub mod json_handlers;
+    pub mod syslog_handlers;
     pub mod unstructured_handlers;
+    pub mod w3c_handlers;
 }
 use handlers::csv_handlers::*;
+use handlers::evtx_handlers::*;
 use handlers::json_handlers::*;
+use handlers::syslog_handlers::*;
 use handlers::unstructured_handlers::*;
+use handlers::w3c_handlers::*;
 use num_format::{Locale, ToFormattedString};
 mod date_regex;
 mod date_string_mutations;
+mod file_type_sniffer;
+use file_type_sniffer::{detect_log_type, detect_log_type_from_start_of_file};
+mod log_file_readers;
+use log_file_readers::*;
+mod archives;
+use archives::*;
+mod file_hashing;
+use file_hashing::{StreamHashers, get_file_hashes_of_reader};
+mod activity_profile;
+mod bounded_dupes;
+mod cross_file_dupes;
+use cross_file_dupes::*;
+pub mod alert_config;
 pub mod helpers;
+mod html_report;
+mod json_report;
+mod near_dupes;
+use json_report::JsonReport;
+pub mod manifest;
+use manifest::RunManifest;
+mod volume_series;
 use helpers::*;
 pub mod basic_objects;
 use basic_objects::*;
@@ -44,6 +70,7 @@ include!(concat!(env!("OUT_DIR"), "/generated_redactions_tests.rs"));
 
 pub fn process_all_files(execution_settings: ExecutionSettings) {
     let start = Instant::now();
+    let start_time = Utc::now();
     let _ = VERBOSE.set(execution_settings.verbose_mode);
     match metadata(&execution_settings.input) {
         Err(e) => println!(
@@ -94,11 +121,26 @@ pub fn process_all_files(execution_settings: ExecutionSettings) {
                 }
             };
 
-            let results: Vec<ProcessedLogFile> = supported_files
+            let mut results: Vec<ProcessedLogFile> = supported_files
                 .par_iter()
                 .map(|path| process_file(path, &execution_settings).expect("Error processing file"))
                 .collect();
 
+            if execution_settings.cross_file_dupes {
+                let shared_records_between_files = find_records_shared_between_files(
+                    &mut results,
+                    &supported_files,
+                    &execution_settings,
+                );
+                if let Err(e) = write_shared_records_between_files_to_file(
+                    &execution_settings.output_dir,
+                    &results,
+                    &shared_records_between_files,
+                ) {
+                    eprintln!("Failed to write the cross file duplicates: {}", e);
+                }
+            }
+
             if let Err(e) = write_errors_to_error_log(&results, &execution_settings) {
                 eprintln!("Failed to write errors to error log {}", e);
             }
@@ -106,7 +148,10 @@ pub fn process_all_files(execution_settings: ExecutionSettings) {
             let results_to_actually_do_stats_on: &Vec<ProcessedLogFile> =
                 match execution_settings.multipart_mode {
                     true => &vec![
-                        convert_vector_of_processed_log_files_into_one_for_multipart(&results),
+                        convert_vector_of_processed_log_files_into_one_for_multipart(
+                            &results,
+                            &execution_settings,
+                        ),
                     ],
                     false => &results,
                 };
@@ -125,6 +170,39 @@ pub fn process_all_files(execution_settings: ExecutionSettings) {
             ) {
                 eprintln!("Failed to output alerts: {}", e);
             }
+            if let Err(e) = execution_settings
+                .alert_config
+                .write_effective_config_to_file(&execution_settings.output_dir)
+            {
+                eprintln!("Failed to write the alert config: {}", e);
+            }
+            if let Some(json_report_path) = &execution_settings.json_report_path {
+                let json_report = JsonReport::new(
+                    &results,
+                    execution_settings
+                        .multipart_mode
+                        .then(|| &results_to_actually_do_stats_on[0]),
+                    &execution_settings,
+                    start.elapsed(),
+                );
+                match json_report.write_to_file(json_report_path) {
+                    Ok(()) => println!("Wrote JSON report to {}", json_report_path.display()),
+                    Err(e) => eprintln!("Failed to write the JSON report: {}", e),
+                }
+            }
+            if execution_settings.write_html_report
+                && let Err(e) = html_report::write_html_report(
+                    results_to_actually_do_stats_on,
+                    &execution_settings,
+                )
+            {
+                eprintln!("Failed to write the HTML report: {}", e);
+            }
+            let mut manifest =
+                RunManifest::new(&execution_settings, &supported_files, &results, start_time);
+            if let Err(e) = manifest.write_to_file(&execution_settings.output_dir) {
+                eprintln!("Failed to write the manifest: {}", e);
+            }
 
             let formatted_total_of_records_with_timestamps = results_to_actually_do_stats_on
                 .iter()
@@ -166,29 +244,52 @@ fn categorize_files(file_paths: &Vec<PathBuf>) -> Vec<LogFile> {
     let mut supported_files: Vec<LogFile> = Vec::new();
 
     for file_path in file_paths {
-        if let Some(extension) = file_path.extension() {
-            if extension == "csv" {
-                supported_files.push(LogFile {
-                    log_type: LogType::Csv,
-                    file_path: file_path.to_path_buf(),
-                })
-            } else if extension == "json" || extension == "jsonl" {
-                supported_files.push(LogFile {
-                    log_type: LogType::Json,
-                    file_path: file_path.to_path_buf(),
-                })
-            } else {
-                supported_files.push(LogFile {
-                    log_type: LogType::Unstructured,
-                    file_path: file_path.to_path_buf(),
-                })
-            }
-        } else {
-            // Some unstructured logs might not have file extensions, so might have to work with this
-            println!(
-                "Error getting file extension for {}",
-                file_path.to_string_lossy().to_string()
-            )
+        let archive_members: Vec<Option<(ArchiveMember, Result<Vec<u8>>)>> =
+            match get_archive_type(file_path) {
+                Ok(Some(archive_type)) => match list_archive_members(file_path, archive_type) {
+                    Ok(members) => {
+                        println!(
+                            "Found {} files inside of archive {}",
+                            members.len(),
+                            file_path.to_string_lossy()
+                        );
+                        members.into_iter().map(Some).collect()
+                    }
+                    Err(e) => {
+                        println!(
+                            "Could not list the contents of archive {} because of {}",
+                            file_path.to_string_lossy(),
+                            e
+                        );
+                        continue;
+                    }
+                },
+                _ => vec![None], // Not an archive, or it can't be read and the error will come up during processing
+            };
+
+        for archive_member in archive_members {
+            let (archive_member, start_of_member) = archive_member.unzip();
+            let mut log_file = LogFile {
+                log_type: LogType::Unstructured,
+                file_path: file_path.to_path_buf(),
+                type_detection: None,
+                archive_member,
+            };
+            let (log_type, type_detection) = match start_of_member {
+                Some(start_of_member) => {
+                    detect_log_type_from_start_of_file(&log_file, start_of_member)
+                }
+                None => detect_log_type(&log_file),
+            };
+            print_if_verbose_mode_on(format!(
+                "Categorized {} as {} ({})",
+                log_file.get_display_path(),
+                log_type,
+                type_detection.reason
+            ));
+            log_file.log_type = log_type;
+            log_file.type_detection = Some(type_detection);
+            supported_files.push(log_file)
         }
     }
     supported_files
@@ -201,7 +302,7 @@ pub fn process_file(
     let mut base_processed_file = ProcessedLogFile::default();
 
     //get metadata. Does not matter what kind of file it is for this function
-    let (size, file_name, file_path) = match get_metadata(&log_file.file_path) {
+    let (size, file_name, file_path) = match get_metadata(log_file) {
         Ok(result) => result,
         Err(e) => {
             base_processed_file.errors.push(e);
@@ -211,24 +312,40 @@ pub fn process_file(
     base_processed_file.size = Some(size.to_string());
     base_processed_file.filename = Some(file_name.clone());
     base_processed_file.file_path = Some(file_path);
-
-    //Get hash if not quick mode
-    if !execution_settings.quick_mode {
-        let hash = match get_hash(&log_file.file_path) {
-            Ok(result) => result,
-            Err(e) => {
-                base_processed_file.errors.push(e);
-                return Ok(base_processed_file);
-            }
-        };
-        base_processed_file.sha256hash = Some(hash);
+    base_processed_file.log_type = Some(log_file.log_type.to_string());
+    if let Some(type_detection) = &log_file.type_detection {
+        base_processed_file.log_type_confidence = Some(type_detection.confidence);
+        base_processed_file.log_type_reason = Some(type_detection.reason.clone());
     }
 
+    let compression_type = match get_log_file_compression_type(log_file) {
+        Ok(result) => result,
+        Err(e) => {
+            base_processed_file.errors.push(e);
+            return Ok(base_processed_file);
+        }
+    };
+    base_processed_file.compression = compression_type.map(|c| c.to_string());
+
+    // Hashes are computed by the same read that streams the records. The main hash is always over the bytes on disk.
+    // The header, timestamp and direction passes below still open the file again, they just don't hash it
+    let stream_hashers = StreamHashers::new(match execution_settings.quick_mode {
+        true => &[],
+        false => &execution_settings.hash_algorithms,
+    });
+
     // Get Header Row
     let header_info = match get_header_info(log_file) {
         Ok(result) => result,
         Err(e) => {
             base_processed_file.errors.push(e);
+            set_file_hashes(
+                &mut base_processed_file,
+                log_file,
+                &stream_hashers,
+                compression_type,
+                execution_settings,
+            );
             return Ok(base_processed_file);
         }
     };
@@ -259,6 +376,7 @@ pub fn process_file(
                     log_file,
                     &mut timestamp_hit,
                     header_info.clone(),
+                    execution_settings,
                 ) {
                     base_processed_file.errors.push(e);
                 }
@@ -288,18 +406,32 @@ pub fn process_file(
         };
 
     // Stream the file to find statistics on time and other stuff
-    let completed_statistics_object = match stream_file(
+    let stream_result = stream_file(
         log_file,
         &potential_timestamp_hit,
         execution_settings,
         header_info.clone(),
-    ) {
+        &stream_hashers,
+    );
+    set_file_hashes(
+        &mut base_processed_file,
+        log_file,
+        &stream_hashers,
+        compression_type,
+        execution_settings,
+    );
+    let mut completed_statistics_object = match stream_result {
         Ok(result) => result,
         Err(e) => {
             base_processed_file.errors.push(e);
             return Ok(base_processed_file);
         }
     };
+    completed_statistics_object.alert_rules =
+        execution_settings.alert_config.get_rules_for_file(log_file);
+    completed_statistics_object.finish_processing_timestamps();
+    completed_statistics_object.finish_processing_dupes();
+    completed_statistics_object.finish_processing_near_dupes();
     base_processed_file.first_data_row_used = header_info.map(|n| n.first_data_row.to_string());
     let values_to_alert_on = completed_statistics_object.get_possible_alert_values();
     base_processed_file
@@ -313,6 +445,8 @@ pub fn process_file(
         let (mean_time_gap, variance) = completed_statistics_object.get_mean_and_variance();
         base_processed_file.mean_time_gap = Some(mean_time_gap);
         base_processed_file.variance_time_gap = Some(variance);
+        base_processed_file.time_gap_sketch =
+            Some(completed_statistics_object.time_gap_sketch.clone());
     }
 
     base_processed_file.total_num_records = completed_statistics_object.total_num_records;
@@ -320,8 +454,59 @@ pub fn process_file(
 
     if !execution_settings.quick_mode {
         base_processed_file.num_dupes = Some(completed_statistics_object.num_dupes);
+        base_processed_file.dupe_detection_mode =
+            Some(completed_statistics_object.get_dupe_detection_description());
         base_processed_file.num_redactions = Some(completed_statistics_object.num_redactions);
+        base_processed_file.num_near_dupe_clusters =
+            completed_statistics_object.num_near_dupe_clusters;
+        base_processed_file.near_dupe_dropped_fields =
+            completed_statistics_object.near_dupe_dropped_fields.clone();
+        if execution_settings.cross_file_dupes {
+            base_processed_file.record_hashes = completed_statistics_object.take_record_hashes();
+        }
+    }
+    if log_file.log_type == LogType::Evtx {
+        base_processed_file.num_evtx_record_id_gaps =
+            Some(completed_statistics_object.num_evtx_record_id_gaps);
+        base_processed_file.num_evtx_log_cleared_events =
+            Some(completed_statistics_object.num_evtx_log_cleared_events);
+    }
+
+    if execution_settings.out_of_order_tolerance.is_some() {
+        base_processed_file.num_out_of_order_records =
+            Some(completed_statistics_object.num_out_of_order_records);
+        base_processed_file.largest_out_of_order_displacement =
+            completed_statistics_object.largest_out_of_order_displacement;
+    }
+    if execution_settings.num_top_time_gaps > 0
+        && completed_statistics_object.largest_time_gap.is_some()
+    {
+        base_processed_file.approx_num_time_gaps_above_threshold =
+            Some(completed_statistics_object.approx_num_time_gaps_above_threshold);
+    }
+    if let Some(cadence) = completed_statistics_object.cadence_detector.cadence {
+        base_processed_file.expected_cadence = Some(cadence);
+        base_processed_file.num_missed_cadence_intervals =
+            Some(completed_statistics_object.num_missed_cadence_intervals);
+    }
+    if completed_statistics_object.min_timestamp.is_some() {
+        base_processed_file.num_quiet_activity_periods =
+            Some(completed_statistics_object.quiet_activity_periods.len());
+        base_processed_file.num_volume_drops = Some(completed_statistics_object.num_volume_drops);
+        base_processed_file.num_volume_spikes = Some(completed_statistics_object.num_volume_spikes);
+        base_processed_file.hour_of_day_counts = Some(
+            completed_statistics_object
+                .activity_profile
+                .get_hour_of_day_counts(),
+        );
+        base_processed_file.day_of_week_counts = Some(
+            completed_statistics_object
+                .activity_profile
+                .get_day_of_week_counts(),
+        );
     }
+    base_processed_file.largest_time_gaps = completed_statistics_object.get_largest_time_gaps();
+    base_processed_file.dst_transitions = completed_statistics_object.dst_transitions;
 
     base_processed_file
         .errors
@@ -330,23 +515,27 @@ pub fn process_file(
     Ok(base_processed_file)
 }
 
-fn get_metadata(file_path: &PathBuf) -> Result<(u64, String, String)> {
-    let file = File::open(file_path).map_err(|e| {
+fn get_metadata(log_file: &LogFile) -> Result<(u64, String, String)> {
+    let file = File::open(&log_file.file_path).map_err(|e| {
         LavaError::new(
             format!("Unable to open file because of {e}"),
             LavaErrorLevel::Critical,
         )
     })?;
-    let size = file
-        .metadata()
-        .map_err(|e| {
-            LavaError::new(
-                format!("Unable to get file metadata because of {e}"),
-                LavaErrorLevel::Critical,
-            )
-        })?
-        .len();
-    let file_name = file_path
+    let size = match &log_file.archive_member {
+        Some(member) => member.size,
+        None => file
+            .metadata()
+            .map_err(|e| {
+                LavaError::new(
+                    format!("Unable to get file metadata because of {e}"),
+                    LavaErrorLevel::Critical,
+                )
+            })?
+            .len(),
+    };
+    let file_name = log_file
+        .get_inner_path()
         .file_name()
         .ok_or("Error getting filename")
         .map_err(|e| {
@@ -357,43 +546,67 @@ fn get_metadata(file_path: &PathBuf) -> Result<(u64, String, String)> {
         })?
         .to_string_lossy()
         .to_string();
-    Ok((size, file_name, file_path.to_string_lossy().to_string()))
+    Ok((size, file_name, log_file.get_display_path()))
 }
 
-fn get_hash(file_path: &PathBuf) -> Result<String> {
-    let mut file = File::open(file_path).map_err(|e| {
-        LavaError::new(
-            format!("Unable to open file because of {e}"),
-            LavaErrorLevel::Critical,
-        )
-    })?;
-
-    let mut hasher = Sha256::new();
+fn get_hash_of_reader<R: Read>(reader: R) -> Result<String> {
+    Ok(get_file_hashes_of_reader(reader, &[HashAlgorithm::Sha256])?
+        .sha256
+        .unwrap_or_default())
+}
 
-    let mut buffer = [0u8; 4096]; //bump up a little but, might have to change back to 4096 if this breakes with the threading
-    loop {
-        let bytes_read = file.read(&mut buffer).map_err(|e| {
-            LavaError::new(
-                format!("Unable to read bytes during hashing because of {e}"),
-                LavaErrorLevel::Critical,
-            )
-        })?;
-        if bytes_read == 0 {
-            break;
+/// Uses the hashes from the stream when it read the whole file, and otherwise reads the file again to hash it.
+/// EVTX files are parsed straight from disk and files whose stream stopped on an error end up being read again
+fn set_file_hashes(
+    base_processed_file: &mut ProcessedLogFile,
+    log_file: &LogFile,
+    stream_hashers: &StreamHashers,
+    compression_type: Option<CompressionType>,
+    execution_settings: &ExecutionSettings,
+) {
+    if execution_settings.quick_mode || execution_settings.hash_algorithms.is_empty() {
+        return;
+    }
+    let (streamed_hashes, streamed_decompressed_hashes) = stream_hashers.finish();
+    let file_hashes = match streamed_hashes {
+        Some(file_hashes) => Ok(file_hashes),
+        None => open_log_file_without_decompression(log_file).and_then(|reader| {
+            get_file_hashes_of_reader(reader, &execution_settings.hash_algorithms)
+        }),
+    };
+    match file_hashes {
+        Ok(file_hashes) => {
+            base_processed_file.md5hash = file_hashes.md5;
+            base_processed_file.sha1hash = file_hashes.sha1;
+            base_processed_file.sha256hash = file_hashes.sha256;
         }
-        hasher.update(&buffer[..bytes_read]);
+        Err(e) => base_processed_file.errors.push(e),
     }
 
-    let result = hasher.finalize();
-    let hash_hex = format!("{:x}", result);
-
-    Ok(hash_hex)
+    if compression_type.is_some()
+        && execution_settings
+            .hash_algorithms
+            .contains(&HashAlgorithm::Sha256)
+    {
+        let decompressed_hash = match streamed_decompressed_hashes {
+            Some(file_hashes) => Ok(file_hashes.sha256.unwrap_or_default()),
+            None => open_log_file(log_file).and_then(get_hash_of_reader),
+        };
+        match decompressed_hash {
+            Ok(decompressed_hash) => {
+                base_processed_file.decompressed_sha256hash = Some(decompressed_hash)
+            }
+            Err(e) => base_processed_file.errors.push(e),
+        }
+    }
 }
 
 fn get_header_info(log_file: &LogFile) -> Result<Option<HeaderInfo>> {
     if log_file.log_type == LogType::Csv {
         let header_info = crate::handlers::csv_handlers::get_header_info(log_file)?;
         return Ok(Some(header_info));
+    } else if log_file.log_type == LogType::W3c {
+        Ok(Some(get_header_info_for_w3c(log_file)?))
     } else {
         return Ok(None);
     }
@@ -420,6 +633,22 @@ fn try_to_get_timestamp_hit(
         return try_to_get_timestamp_hit_for_unstructured(log_file, execution_settings);
     } else if log_file.log_type == LogType::Json {
         return try_to_get_timestamp_hit_for_json(log_file, execution_settings);
+    } else if log_file.log_type == LogType::Evtx {
+        return try_to_get_timestamp_hit_for_evtx(log_file, execution_settings);
+    } else if log_file.log_type == LogType::Syslog {
+        return try_to_get_timestamp_hit_for_syslog(log_file, execution_settings);
+    } else if log_file.log_type == LogType::W3c {
+        let header_info_unwrapped = header_info.ok_or_else(|| {
+            LavaError::new(
+                "Did not receive header info for a W3C log",
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        return try_to_get_timestamp_hit_for_w3c(
+            log_file,
+            execution_settings,
+            header_info_unwrapped,
+        );
     }
     Err(LavaError::new(
         "Have not implemented scanning for timestamp for this file type yet",
@@ -431,6 +660,7 @@ fn set_time_direction_by_scanning_file(
     log_file: &LogFile,
     timestamp_hit: &mut IdentifiedTimeInformation,
     header_info: Option<HeaderInfo>,
+    execution_settings: &ExecutionSettings,
 ) -> Result<()> {
     if log_file.log_type == LogType::Csv {
         let header_info_unwrapped = header_info.ok_or_else(|| {
@@ -448,6 +678,26 @@ fn set_time_direction_by_scanning_file(
         return set_time_direction_by_scanning_unstructured_file(log_file, timestamp_hit);
     } else if log_file.log_type == LogType::Json {
         return set_time_direction_by_scanning_json_file(log_file, timestamp_hit);
+    } else if log_file.log_type == LogType::Evtx {
+        return set_time_direction_by_scanning_evtx_file(log_file, timestamp_hit);
+    } else if log_file.log_type == LogType::Syslog {
+        return set_time_direction_by_scanning_syslog_file(
+            log_file,
+            timestamp_hit,
+            execution_settings,
+        );
+    } else if log_file.log_type == LogType::W3c {
+        let header_info_unwrapped = header_info.ok_or_else(|| {
+            LavaError::new(
+                "Did not receive header info for a W3C log",
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        return set_time_direction_by_scanning_w3c_file(
+            log_file,
+            timestamp_hit,
+            header_info_unwrapped,
+        );
     }
     Err(LavaError::new(
         "Have not implemented scanning for directions for this file type yet.",
@@ -460,6 +710,7 @@ fn stream_file(
     timestamp_hit: &Option<IdentifiedTimeInformation>,
     execution_settings: &ExecutionSettings,
     header_info: Option<HeaderInfo>,
+    stream_hashers: &StreamHashers,
 ) -> Result<LogRecordProcessor> {
     if log_file.log_type == LogType::Csv {
         if let Some(header_info_unwrapped) = header_info {
@@ -468,6 +719,7 @@ fn stream_file(
                 timestamp_hit,
                 execution_settings,
                 header_info_unwrapped,
+                stream_hashers,
             );
         } else {
             return Err(LavaError::new(
@@ -476,9 +728,33 @@ fn stream_file(
             ));
         }
     } else if log_file.log_type == LogType::Unstructured {
-        return stream_unstructured_file(log_file, timestamp_hit, execution_settings);
+        return stream_unstructured_file(
+            log_file,
+            timestamp_hit,
+            execution_settings,
+            stream_hashers,
+        );
     } else if log_file.log_type == LogType::Json {
-        return stream_json_file(log_file, timestamp_hit, execution_settings);
+        return stream_json_file(log_file, timestamp_hit, execution_settings, stream_hashers);
+    } else if log_file.log_type == LogType::Evtx {
+        return stream_evtx_file(log_file, timestamp_hit, execution_settings);
+    } else if log_file.log_type == LogType::Syslog {
+        return stream_syslog_file(log_file, timestamp_hit, execution_settings, stream_hashers);
+    } else if log_file.log_type == LogType::W3c {
+        if let Some(header_info_unwrapped) = header_info {
+            return stream_w3c_file(
+                log_file,
+                timestamp_hit,
+                execution_settings,
+                header_info_unwrapped,
+                stream_hashers,
+            );
+        } else {
+            return Err(LavaError::new(
+                "Did Not reveice header info for a W3C log",
+                LavaErrorLevel::Critical,
+            ));
+        }
     }
     Err(LavaError::new(
         "Have not implemented streaming for this file type yet",
@@ -497,21 +773,55 @@ mod tests {
         paths.push(PathBuf::from("/path/to/file2.csv"));
 
         let result = categorize_files(&paths);
-        let expected: Vec<LogFile> = vec![
-            LogFile {
-                log_type: LogType::Json,
-                file_path: PathBuf::from("/path/to/file1.json"),
-            },
-            LogFile {
-                log_type: LogType::Unstructured,
-                file_path: PathBuf::from("/path/to/file1.txt"),
-            },
-            LogFile {
-                log_type: LogType::Csv,
-                file_path: PathBuf::from("/path/to/file2.csv"),
-            },
+        let log_types: Vec<(LogType, PathBuf)> = result
+            .into_iter()
+            .map(|log_file| (log_file.log_type, log_file.file_path))
+            .collect();
+        let expected: Vec<(LogType, PathBuf)> = vec![
+            (LogType::Json, PathBuf::from("/path/to/file1.json")),
+            (LogType::Unstructured, PathBuf::from("/path/to/file1.txt")),
+            (LogType::Csv, PathBuf::from("/path/to/file2.csv")),
         ];
 
-        assert_eq!(result, expected);
+        assert_eq!(log_types, expected);
+    }
+
+    #[test]
+    fn categorizes_by_content_when_no_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv_path = dir.path().join("export");
+        let json_path = dir.path().join("events.log");
+        std::fs::write(&csv_path, "id,name,date\n1,John,2025-05-09 10:00:00\n").unwrap();
+        std::fs::write(&json_path, "{\"time\":\"2025-05-09 10:00:00\"}\n").unwrap();
+
+        let result = categorize_files(&vec![csv_path, json_path]);
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].log_type, LogType::Csv);
+        assert_eq!(result[1].log_type, LogType::Json);
+        assert!(result[1].type_detection.as_ref().unwrap().confidence > 0.9);
+    }
+
+    #[test]
+    fn categorizes_zip_members() {
+        use std::io::Write;
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("collection.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("inner/users.csv", options).unwrap();
+        writer
+            .write_all(b"id,name,date\n1,John,2025-05-09 10:00:00\n")
+            .unwrap();
+        writer.finish().unwrap();
+
+        let result = categorize_files(&vec![zip_path.clone()]);
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].log_type, LogType::Csv);
+        assert_eq!(
+            result[0].get_display_path(),
+            format!("{}!/inner/users.csv", zip_path.to_string_lossy())
+        );
     }
 }
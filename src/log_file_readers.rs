@@ -0,0 +1,289 @@
+use crate::archives::open_archive_member;
+use crate::basic_objects::*;
+use crate::errors::*;
+use crate::file_hashing::StreamHashers;
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use xz2::read::XzDecoder;
+
+pub fn get_compression_type(file_path: &Path) -> Result<Option<CompressionType>> {
+    let file = File::open(file_path).map_err(|e| {
+        LavaError::new(
+            format!("Unable to open file because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    let mut reader = BufReader::new(file);
+    let magic_bytes = reader.fill_buf().map_err(|e| {
+        LavaError::new(
+            format!("Unable to read file because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    Ok(get_compression_type_from_magic_bytes(magic_bytes))
+}
+
+pub fn get_compression_type_from_magic_bytes(magic_bytes: &[u8]) -> Option<CompressionType> {
+    if magic_bytes.starts_with(&[0x1f, 0x8b]) {
+        Some(CompressionType::Gzip)
+    } else if magic_bytes.starts_with(b"BZh") {
+        Some(CompressionType::Bzip2)
+    } else if magic_bytes.starts_with(&[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00]) {
+        Some(CompressionType::Xz)
+    } else if magic_bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Some(CompressionType::Zstd)
+    } else {
+        None
+    }
+}
+
+pub fn open_log_file(log_file: &LogFile) -> Result<Box<dyn BufRead>> {
+    match &log_file.archive_member {
+        None => open_file_with_decompression(&log_file.file_path),
+        Some(member) => {
+            let mut content = BufReader::new(open_archive_member(&log_file.file_path, member)?);
+            let compression_type =
+                get_compression_type_from_magic_bytes(content.fill_buf().map_err(|e| {
+                    LavaError::new(
+                        format!("Unable to read file because of {e}"),
+                        LavaErrorLevel::Critical,
+                    )
+                })?);
+            wrap_reader_with_decompression(content, compression_type)
+        }
+    }
+}
+
+/// Same as open_log_file, but every byte is also hashed as it is read. The decompressed content is hashed separately
+pub fn open_log_file_with_hashing(
+    log_file: &LogFile,
+    stream_hashers: &StreamHashers,
+) -> Result<Box<dyn BufRead>> {
+    let (raw_reader, compression_type) = match &log_file.archive_member {
+        None => (
+            stream_hashers.hash_raw_bytes(File::open(&log_file.file_path).map_err(|e| {
+                LavaError::new(
+                    format!("Unable to open file because of {e}"),
+                    LavaErrorLevel::Critical,
+                )
+            })?),
+            get_compression_type(&log_file.file_path)?,
+        ),
+        Some(member) => {
+            let mut content = BufReader::new(open_archive_member(&log_file.file_path, member)?);
+            let compression_type =
+                get_compression_type_from_magic_bytes(content.fill_buf().map_err(|e| {
+                    LavaError::new(
+                        format!("Unable to read file because of {e}"),
+                        LavaErrorLevel::Critical,
+                    )
+                })?);
+            (stream_hashers.hash_raw_bytes(content), compression_type)
+        }
+    };
+    let reader = wrap_reader_with_decompression(raw_reader, compression_type)?;
+    match compression_type {
+        None => Ok(reader),
+        Some(_) => Ok(Box::new(BufReader::new(
+            stream_hashers.hash_decompressed_bytes(reader),
+        ))),
+    }
+}
+
+/// The bytes of the log before any decompression, which is what gets hashed
+pub fn open_log_file_without_decompression(log_file: &LogFile) -> Result<Box<dyn BufRead>> {
+    match &log_file.archive_member {
+        None => Ok(Box::new(BufReader::new(
+            File::open(&log_file.file_path).map_err(|e| {
+                LavaError::new(
+                    format!("Unable to open file because of {e}"),
+                    LavaErrorLevel::Critical,
+                )
+            })?,
+        ))),
+        Some(member) => Ok(Box::new(BufReader::new(open_archive_member(
+            &log_file.file_path,
+            member,
+        )?))),
+    }
+}
+
+pub fn get_log_file_compression_type(log_file: &LogFile) -> Result<Option<CompressionType>> {
+    let mut reader = open_log_file_without_decompression(log_file)?;
+    let magic_bytes = reader.fill_buf().map_err(|e| {
+        LavaError::new(
+            format!("Unable to read file because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    Ok(get_compression_type_from_magic_bytes(magic_bytes))
+}
+
+/// Opens the file and transparently decompresses it if it starts with gzip, bzip2, xz or zstd magic bytes
+pub fn open_file_with_decompression(file_path: &Path) -> Result<Box<dyn BufRead>> {
+    let compression_type = get_compression_type(file_path)?;
+    let file = File::open(file_path).map_err(|e| {
+        LavaError::new(
+            format!("Unable to open file because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    wrap_reader_with_decompression(file, compression_type)
+}
+
+pub fn wrap_reader_with_decompression<'a, R: Read + 'a>(
+    reader: R,
+    compression_type: Option<CompressionType>,
+) -> Result<Box<dyn BufRead + 'a>> {
+    let buffered = BufReader::new(reader);
+    let decompressed: Box<dyn BufRead + 'a> = match compression_type {
+        None => Box::new(buffered),
+        Some(CompressionType::Gzip) => Box::new(BufReader::new(MultiGzDecoder::new(buffered))), // Multi so concatenated members from log rotation are all read
+        Some(CompressionType::Bzip2) => Box::new(BufReader::new(MultiBzDecoder::new(buffered))),
+        Some(CompressionType::Xz) => {
+            Box::new(BufReader::new(XzDecoder::new_multi_decoder(buffered)))
+        }
+        Some(CompressionType::Zstd) => Box::new(BufReader::new(
+            zstd::stream::read::Decoder::with_buffer(buffered).map_err(|e| {
+                LavaError::new(
+                    format!("Unable to start zstd decompression because of {e}"),
+                    LavaErrorLevel::Critical,
+                )
+            })?,
+        )),
+    };
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    static CONTENT: &str = "id,name,date\n1,John,2025-05-09 10:00:00\n";
+
+    fn read_all(log_file_path: &Path) -> String {
+        let mut output = String::new();
+        open_file_with_decompression(log_file_path)
+            .unwrap()
+            .read_to_string(&mut output)
+            .unwrap();
+        output
+    }
+
+    fn write_temp_file(bytes: &[u8]) -> NamedTempFile {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(bytes).unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn reads_uncompressed_file() {
+        let temp_file = write_temp_file(CONTENT.as_bytes());
+        assert_eq!(get_compression_type(temp_file.path()).unwrap(), None);
+        assert_eq!(read_all(temp_file.path()), CONTENT);
+    }
+
+    #[test]
+    fn reads_gzip_file() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(CONTENT.as_bytes()).unwrap();
+        let temp_file = write_temp_file(&encoder.finish().unwrap());
+        assert_eq!(
+            get_compression_type(temp_file.path()).unwrap(),
+            Some(CompressionType::Gzip)
+        );
+        assert_eq!(read_all(temp_file.path()), CONTENT);
+    }
+
+    #[test]
+    fn reads_bzip2_file() {
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        encoder.write_all(CONTENT.as_bytes()).unwrap();
+        let temp_file = write_temp_file(&encoder.finish().unwrap());
+        assert_eq!(
+            get_compression_type(temp_file.path()).unwrap(),
+            Some(CompressionType::Bzip2)
+        );
+        assert_eq!(read_all(temp_file.path()), CONTENT);
+    }
+
+    #[test]
+    fn reads_xz_file() {
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(CONTENT.as_bytes()).unwrap();
+        let temp_file = write_temp_file(&encoder.finish().unwrap());
+        assert_eq!(
+            get_compression_type(temp_file.path()).unwrap(),
+            Some(CompressionType::Xz)
+        );
+        assert_eq!(read_all(temp_file.path()), CONTENT);
+    }
+
+    #[test]
+    fn reads_zstd_file() {
+        let compressed = zstd::stream::encode_all(CONTENT.as_bytes(), 3).unwrap();
+        let temp_file = write_temp_file(&compressed);
+        assert_eq!(
+            get_compression_type(temp_file.path()).unwrap(),
+            Some(CompressionType::Zstd)
+        );
+        assert_eq!(read_all(temp_file.path()), CONTENT);
+    }
+
+    #[test]
+    fn reads_concatenated_gzip_members() {
+        let mut bytes = Vec::new();
+        for chunk in ["first line\n", "second line\n"] {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(chunk.as_bytes()).unwrap();
+            bytes.extend(encoder.finish().unwrap());
+        }
+        let temp_file = write_temp_file(&bytes);
+        assert_eq!(read_all(temp_file.path()), "first line\nsecond line\n");
+    }
+
+    #[test]
+    fn hashes_raw_and_decompressed_bytes_in_the_same_read() {
+        use crate::file_hashing::get_file_hashes_of_reader;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(CONTENT.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let temp_file = write_temp_file(&compressed);
+        let log_file = LogFile {
+            log_type: LogType::Csv,
+            file_path: temp_file.path().to_path_buf(),
+            type_detection: None,
+            archive_member: None,
+        };
+        let stream_hashers = StreamHashers::new(&[HashAlgorithm::Md5, HashAlgorithm::Sha256]);
+
+        let mut output = String::new();
+        open_log_file_with_hashing(&log_file, &stream_hashers)
+            .unwrap()
+            .read_to_string(&mut output)
+            .unwrap();
+        let (raw, decompressed) = stream_hashers.finish();
+
+        assert_eq!(output, CONTENT);
+        assert_eq!(
+            raw.unwrap(),
+            get_file_hashes_of_reader(
+                compressed.as_slice(),
+                &[HashAlgorithm::Md5, HashAlgorithm::Sha256]
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            decompressed.unwrap().sha256,
+            get_file_hashes_of_reader(CONTENT.as_bytes(), &[HashAlgorithm::Sha256])
+                .unwrap()
+                .sha256
+        );
+    }
+}
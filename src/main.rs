@@ -1,5 +1,7 @@
 use clap::{ArgGroup, Command, arg};
-use lava::main_helpers::{get_full_execution_settings, print_compiled_regexes};
+use lava::main_helpers::{
+    get_full_execution_settings, print_compiled_regexes, print_manifest_verification,
+};
 use lava::process_all_files;
 
 fn main() {
@@ -12,15 +14,45 @@ fn main() {
         .arg(arg!(-o --output <PATH> "Output directory.").default_value("LAVA_Output"))
         .arg(arg!(-p --printregexes "Print the built in timestamp formats."))
         .arg(arg!(-r --regexes <PATH> "YML file with custom timestamp formats to use. For formatting example run --printregexes."))
-        .arg(arg!(-t --tf <PATH> "Timestamp field to use for time analysis. Supports -> for nested keys in JSONL."))
+        .arg(arg!(-t --tf <PATH> "Timestamp field to use for time analysis. Supports -> for nested keys in JSONL and + to combine CSV columns, ex \"Date+Time\"."))
         .arg(arg!(-q --quick "Quick mode. Skips resource-intensive processing steps such as file hashing and duplicate detection."))
+        .arg(arg!(--year <YEAR> "Year of the first record in syslog files whose timestamps have no year. Defaults to inferring it from the file modified time.").value_parser(clap::value_parser!(i32)))
+        .arg(arg!(--"assume-tz" <TZ> "Timezone of timestamps that do not include an offset, ex -05:00 or America/New_York. Defaults to treating them as UTC.").allow_hyphen_values(true))
+        .arg(arg!(--tolerant "Tolerant mode. Records slightly out of order are counted and listed instead of stopping time analysis for the file."))
+        .arg(arg!(--jitter <SECONDS> "How far behind the latest timestamp a record can be in tolerant mode.").value_parser(clap::value_parser!(u64)).default_value("60").requires("tolerant"))
+        .arg(arg!(--"max-out-of-order" <COUNT> "How many records can be out of order in tolerant mode before the file is treated as not sorted.").value_parser(clap::value_parser!(usize)).default_value("1000").requires("tolerant"))
+        .arg(arg!(--"top-gaps" <N> "How many of the largest time gaps to list for each file.").value_parser(clap::value_parser!(usize)).default_value("10"))
+        .arg(arg!(--"volume-window" <WINDOW> "Size of the windows records are counted in for volume anomalies: minute, hour or day.").default_value("hour"))
+        .arg(arg!(--"gap-alert-method" <METHOD> "How the largest time gap is judged for alerts: std (standard deviations above the mean) or percentile (multiples of the 99th percentile gap).").default_value("std"))
+        .arg(arg!(--"alert-config" <PATH> "YML file that overrides alert thresholds, disables alerts, or scopes either to files by glob or log type."))
+        .arg(arg!(--hash <ALGORITHMS> "Comma separated hashes to compute for each file: md5, sha1 and/or sha256.").default_value("sha256"))
+        .arg(arg!(--"dupe-mode" <MODE> "How record hashes are kept for duplicate detection: memory, spill (exact, spills to temporary files past --dupe-memory) or approximate (Bloom filter with --dupe-fp-rate false positives).").default_value("memory"))
+        .arg(arg!(--"dupe-memory" <MB> "Memory budget in MB for the record hashes of each file being processed in spill mode.").value_parser(clap::value_parser!(usize)).default_value("1024"))
+        .arg(arg!(--"dupe-fp-rate" <RATE> "False positive rate of the Bloom filter in approximate mode.").value_parser(clap::value_parser!(f64)).default_value("0.001"))
+        .arg(arg!(--"near-dupes" "Also look for records that are duplicates apart from volatile fields like sequence IDs, GUIDs and ingest timestamps, which are found in the first records of each file."))
+        .arg(arg!(--"ignore-fields" <FIELDS> "Comma separated column names or JSON pointers, ex /meta/ingest_time, to leave out when looking for near duplicates. Implies --near-dupes."))
+        .arg(arg!(--"cross-file-dupes" "Also look for the same records in more than one input file, like a log exported twice or rotated chunks that overlap, and list which files share records and how many."))
+        .arg(arg!(--format <FORMAT> "Report format: csv, or json to also write a JSON report with every result and alert alongside the CSV.").default_value("csv"))
+        .arg(arg!(--report <PATH> "Path to write the JSON report to. Implies --format json."))
+        .arg(arg!(--html "Also write a self-contained HTML report with an alert dashboard and a timeline for each file to HtmlReport in the output directory."))
         .arg(arg!(-m --multipart "Multipart mode. Treats all input files as chunks of the same log."))
         .arg(arg!(-v --verbose "Verbose mode."))// Not implemented yet
         // .disable_version_flag(true)
+        .subcommand(
+            Command::new("verify-manifest")
+                .about("Re-hash the inputs and outputs listed in a LAVA_Manifest.json and report any that changed.")
+                .arg(arg!(<MANIFEST> "Path to the LAVA_Manifest.json to verify.")),
+        )
+        .subcommand_negates_reqs(true)
         .group(ArgGroup::new("required").args(&["input", "printregexes", "help"]).required(true).multiple(false))
         .get_matches();
 
-    if matches.get_flag("printregexes") {
+    if let Some(("verify-manifest", verify_matches)) = matches.subcommand() {
+        let manifest_path = verify_matches.get_one::<String>("MANIFEST").unwrap(); // Required by clap
+        if !print_manifest_verification(manifest_path) {
+            std::process::exit(1);
+        }
+    } else if matches.get_flag("printregexes") {
         print_compiled_regexes();
     } else {
         let execution_settings = get_full_execution_settings(&matches).unwrap(); // I think unwrap is fine here because I want to crash the program if I get an error here
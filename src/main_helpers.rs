@@ -1,8 +1,12 @@
 use crate::PREBUILT_DATE_REGEXES;
+use crate::alert_config::AlertConfig;
 use crate::basic_objects::*;
 use crate::date_regex::DateRegex;
 use crate::date_regex::RawDateRegex;
 use crate::errors::*;
+use crate::helpers::generate_json_report_filename;
+use crate::manifest::verify_manifest;
+use chrono::TimeDelta;
 use clap::ArgMatches;
 use std::fs;
 use std::path::Path;
@@ -17,6 +21,27 @@ pub fn print_compiled_regexes() -> () {
     }
 }
 
+/// Returns whether every file in the manifest still has the hash it was recorded with
+pub fn print_manifest_verification(manifest_path: &str) -> bool {
+    match verify_manifest(Path::new(manifest_path)) {
+        Err(e) => {
+            eprintln!("Failed to verify manifest: {}", e);
+            false
+        }
+        Ok(verification) => {
+            for problem in verification.problems.iter() {
+                println!("\x1b[91m{}\x1b[0m", problem);
+            }
+            println!(
+                "Checked {} files, {} did not match the manifest",
+                verification.num_files_checked,
+                verification.problems.len()
+            );
+            verification.problems.is_empty()
+        }
+    }
+}
+
 pub fn get_full_execution_settings(matches: &ArgMatches) -> Result<ExecutionSettings> {
     // might want to perfrom lots of sanitation here
     let input_dir = PathBuf::from(
@@ -48,6 +73,151 @@ pub fn get_full_execution_settings(matches: &ArgMatches) -> Result<ExecutionSett
 
     let timestamp_field = matches.get_one::<String>("tf").cloned();
 
+    let assumed_timezone = match matches.get_one::<String>("assume-tz") {
+        None => None,
+        Some(timezone) => Some(AssumedTimezone::parse(timezone).ok_or_else(|| {
+            LavaError::new(
+                format!("Unable to parse --assume-tz value {timezone}, expected an offset like -05:00 or UTC, or a timezone name like America/New_York"),
+                LavaErrorLevel::Critical,
+            )
+        })?),
+    };
+
+    let out_of_order_tolerance = match matches.get_flag("tolerant") {
+        false => None,
+        true => Some(OutOfOrderTolerance {
+            jitter_window: TimeDelta::seconds(
+                matches.get_one::<u64>("jitter").copied().unwrap_or(60) as i64,
+            ),
+            max_out_of_order_records: matches
+                .get_one::<usize>("max-out-of-order")
+                .copied()
+                .unwrap_or(1000),
+        }),
+    };
+
+    let volume_window = match matches.get_one::<String>("volume-window") {
+        None => VolumeWindow::default(),
+        Some(window) => VolumeWindow::parse(window).ok_or_else(|| {
+            LavaError::new(
+                format!(
+                    "Unable to parse --volume-window value {window}, expected minute, hour or day"
+                ),
+                LavaErrorLevel::Critical,
+            )
+        })?,
+    };
+
+    let time_gap_alert_method = match matches.get_one::<String>("gap-alert-method") {
+        None => TimeGapAlertMethod::default(),
+        Some(method) => TimeGapAlertMethod::parse(method).ok_or_else(|| {
+            LavaError::new(
+                format!(
+                    "Unable to parse --gap-alert-method value {method}, expected std or percentile"
+                ),
+                LavaErrorLevel::Critical,
+            )
+        })?,
+    };
+
+    let alert_config = match matches.get_one::<String>("alert-config") {
+        None => AlertConfig::default(),
+        Some(alert_config_path) => AlertConfig::load_from_file(Path::new(alert_config_path))?,
+    };
+
+    let mut hash_algorithms: Vec<HashAlgorithm> = Vec::new();
+    for hash_name in matches
+        .get_one::<String>("hash")
+        .map(|hash_names| hash_names.as_str())
+        .unwrap_or("sha256")
+        .split(',')
+    {
+        let hash_algorithm = HashAlgorithm::parse(hash_name).ok_or_else(|| {
+            LavaError::new(
+                format!("Unable to parse --hash value {hash_name}, expected md5, sha1 or sha256"),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+        if !hash_algorithms.contains(&hash_algorithm) {
+            hash_algorithms.push(hash_algorithm);
+        }
+    }
+
+    let dupe_mode = matches
+        .get_one::<String>("dupe-mode")
+        .map(|dupe_mode| dupe_mode.as_str())
+        .unwrap_or("memory");
+    let false_positive_rate = matches
+        .get_one::<f64>("dupe-fp-rate")
+        .copied()
+        .unwrap_or(0.001);
+    if !(false_positive_rate > 0.0 && false_positive_rate < 1.0) {
+        return Err(LavaError::new(
+            format!(
+                "Unable to use --dupe-fp-rate value {false_positive_rate}, expected a rate between 0 and 1"
+            ),
+            LavaErrorLevel::Critical,
+        ));
+    }
+    let dupe_detection_mode = DupeDetectionMode::parse(
+        dupe_mode,
+        matches.get_one::<usize>("dupe-memory").copied().unwrap_or(1024),
+        false_positive_rate,
+    )
+    .ok_or_else(|| {
+        LavaError::new(
+            format!("Unable to parse --dupe-mode value {dupe_mode}, expected memory, spill or approximate"),
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    if matches.get_flag("cross-file-dupes") && dupe_detection_mode != DupeDetectionMode::InMemory {
+        return Err(LavaError::new(
+            format!(
+                "--cross-file-dupes keeps every record hash in memory, so it can't be used with --dupe-mode {dupe_mode}"
+            ),
+            LavaErrorLevel::Critical,
+        ));
+    }
+
+    let near_dupe_settings = match matches.get_one::<String>("ignore-fields") {
+        Some(ignored_fields) => Some(NearDupeSettings {
+            ignored_fields: ignored_fields
+                .split(',')
+                .map(|field| field.trim().to_string())
+                .filter(|field| !field.is_empty())
+                .collect(),
+        }),
+        None => matches
+            .get_flag("near-dupes")
+            .then(NearDupeSettings::default),
+    };
+    if near_dupe_settings.is_some() && dupe_detection_mode != DupeDetectionMode::InMemory {
+        println!(
+            "\x1b[91mNear duplicate detection keeps every distinct record in memory, so it isn't bounded by --dupe-mode {}\x1b[0m",
+            dupe_mode
+        );
+    }
+
+    let report_format = matches
+        .get_one::<String>("format")
+        .map(|format| format.to_lowercase());
+    if let Some(format) = &report_format
+        && format != "csv"
+        && format != "json"
+    {
+        return Err(LavaError::new(
+            format!("Unable to parse --format value {format}, expected csv or json"),
+            LavaErrorLevel::Critical,
+        ));
+    }
+    let json_report_path = match (matches.get_one::<String>("report"), report_format) {
+        (Some(report_path), _) => Some(PathBuf::from(report_path)),
+        (None, Some(format)) if format == "json" => {
+            Some(output_dir.join(generate_json_report_filename()))
+        }
+        (None, _) => None,
+    };
+
     Ok(ExecutionSettings {
         input: input_dir,
         output_dir: output_dir,
@@ -57,6 +227,19 @@ pub fn get_full_execution_settings(matches: &ArgMatches) -> Result<ExecutionSett
         multipart_mode: matches.get_flag("multipart"),
         verbose_mode: matches.get_flag("verbose"),
         actually_write_to_files: true,
+        syslog_year: matches.get_one::<i32>("year").copied(),
+        assumed_timezone,
+        out_of_order_tolerance,
+        num_top_time_gaps: matches.get_one::<usize>("top-gaps").copied().unwrap_or(10),
+        volume_window,
+        time_gap_alert_method,
+        alert_config,
+        json_report_path,
+        write_html_report: matches.get_flag("html"),
+        hash_algorithms,
+        near_dupe_settings,
+        cross_file_dupes: matches.get_flag("cross-file-dupes"),
+        dupe_detection_mode,
     })
 }
 
@@ -70,22 +253,24 @@ fn setup_output_dir(output_dir: &Path) -> Result<()> {
         })?;
     }
 
-    // Create "Duplicates" and "Redactions" subdirectories
-    let duplicates_dir = output_dir.join("Duplicates");
-    let redactions_dir = output_dir.join("Redactions");
-
-    fs::create_dir_all(&duplicates_dir).map_err(|e| {
-        LavaError::new(
-            format!("Unable to create output directory because of {e}"),
-            LavaErrorLevel::Critical,
-        )
-    })?;
-    fs::create_dir_all(&redactions_dir).map_err(|e| {
-        LavaError::new(
-            format!("Unable to create output directory because of {e}"),
-            LavaErrorLevel::Critical,
-        )
-    })?;
+    for subdirectory in [
+        "Duplicates",
+        "Redactions",
+        "EvtxFindings",
+        "OutOfOrder",
+        "Gaps",
+        "MissedIntervals",
+        "ActivityProfile",
+        "Volume",
+        "NearDuplicates",
+    ] {
+        fs::create_dir_all(output_dir.join(subdirectory)).map_err(|e| {
+            LavaError::new(
+                format!("Unable to create output directory because of {e}"),
+                LavaErrorLevel::Critical,
+            )
+        })?;
+    }
 
     Ok(())
 }
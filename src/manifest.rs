@@ -0,0 +1,430 @@
+use crate::basic_objects::*;
+use crate::errors::*;
+use crate::helpers::{self, RECORD_HASH_ALGORITHM, RECORD_HASH_SEED};
+use crate::json_report::JsonReportSettings;
+use crate::{PREBUILT_REDACTION_REGEXES, get_hash_of_reader};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+pub static MANIFEST_FILENAME: &str = "LAVA_Manifest.json";
+
+/// Record of a run for chain of custody, with hashes of every input LAVA read and every output it wrote
+#[derive(Debug, Serialize)]
+pub struct RunManifest<'a> {
+    pub lava_version: &'static str,
+    pub hostname: Option<String>,
+    pub start_time: String,
+    pub end_time: String,
+    pub settings: ManifestSettings<'a>,
+    pub record_hash: ManifestRecordHash,
+    pub inputs: Vec<ManifestFile>,
+    pub outputs: Vec<ManifestFile>, // Paths inside the output directory are relative to it
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestSettings<'a> {
+    pub input: String,
+    pub output_dir: String,
+    pub json_report_path: Option<String>,
+    pub write_html_report: bool,
+    #[serde(flatten)]
+    pub report_settings: JsonReportSettings<'a>,
+    pub date_regexes: Vec<ManifestDateRegex<'a>>,
+    pub redaction_rules: Vec<ManifestRedactionRule<'a>>,
+}
+
+/// How the record hashes in the duplicate and near duplicate outputs are made, so they can be reproduced without LAVA
+#[derive(Debug, Serialize)]
+pub struct ManifestRecordHash {
+    pub algorithm: &'static str,
+    pub seed: u64,
+    pub input: &'static str,
+    pub output: &'static str,
+}
+
+impl Default for ManifestRecordHash {
+    fn default() -> Self {
+        Self {
+            algorithm: RECORD_HASH_ALGORITHM,
+            seed: RECORD_HASH_SEED,
+            input: "Each field of the record in order, as its length in bytes (u64 little endian) followed by its UTF-8 bytes. Near duplicates hash each kept field name and value the same way",
+            output: "32 lowercase hex digits of the 128 bit hash",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestDateRegex<'a> {
+    pub pretty_format: &'a str,
+    pub regex: &'a str,
+    pub strftime_format: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestRedactionRule<'a> {
+    pub name: &'a str,
+    pub pattern: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub path: String,
+    pub sha256: String,
+}
+
+/// Only the parts of the manifest that verify-manifest needs
+#[derive(Debug, Deserialize)]
+struct ManifestHashes {
+    inputs: Vec<ManifestFile>,
+    outputs: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Default)]
+pub struct ManifestVerification {
+    pub num_files_checked: usize,
+    pub problems: Vec<String>,
+}
+
+impl<'a> RunManifest<'a> {
+    /// Input hashes already computed while processing are reused, so files are only read again in quick mode and for archives.
+    /// They are matched up by path, so the processed files don't have to be in the same order as the inputs
+    pub fn new(
+        execution_settings: &'a ExecutionSettings,
+        supported_files: &[LogFile],
+        processed_log_files: &[ProcessedLogFile],
+        start_time: DateTime<Utc>,
+    ) -> Self {
+        let processed_hashes: HashMap<&str, &str> = processed_log_files
+            .iter()
+            .filter_map(|processed_log_file| {
+                Some((
+                    processed_log_file.file_path.as_deref()?,
+                    processed_log_file.sha256hash.as_deref()?,
+                ))
+            })
+            .collect();
+        let mut inputs: Vec<ManifestFile> = Vec::new();
+        let mut seen_paths: BTreeSet<PathBuf> = BTreeSet::new();
+        for log_file in supported_files {
+            if !seen_paths.insert(log_file.file_path.clone()) {
+                continue; // Archives show up once per member
+            }
+            let hash = match &log_file.archive_member {
+                None => match processed_hashes.get(log_file.get_display_path().as_str()) {
+                    Some(hash) => Ok(hash.to_string()),
+                    None => get_hash_of_file(&log_file.file_path),
+                },
+                Some(_) => get_hash_of_file(&log_file.file_path), // The archive itself for archive members
+            };
+            match hash {
+                Ok(sha256) => inputs.push(ManifestFile {
+                    path: get_absolute_path_string(&log_file.file_path),
+                    sha256,
+                }),
+                Err(e) => eprintln!(
+                    "Failed to hash {} for the manifest: {}",
+                    log_file.file_path.display(),
+                    e
+                ),
+            }
+        }
+
+        Self {
+            lava_version: env!("CARGO_PKG_VERSION"),
+            hostname: get_hostname(),
+            start_time: start_time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            end_time: String::new(), // Set when written so it covers everything before the manifest
+            settings: ManifestSettings::new(execution_settings),
+            record_hash: ManifestRecordHash::default(),
+            inputs,
+            outputs: get_output_files_written(execution_settings),
+        }
+    }
+
+    pub fn write_to_file(&mut self, output_dir: &Path) -> Result<()> {
+        self.end_time = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let file = File::create(output_dir.join(MANIFEST_FILENAME)).map_err(|e| {
+            LavaError::new(
+                format!("Unable to create manifest because of {e}"),
+                LavaErrorLevel::Medium,
+            )
+        })?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self).map_err(|e| {
+            LavaError::new(
+                format!("Unable to write manifest because of {e}"),
+                LavaErrorLevel::Medium,
+            )
+        })
+    }
+}
+
+impl<'a> ManifestSettings<'a> {
+    fn new(execution_settings: &'a ExecutionSettings) -> Self {
+        Self {
+            input: get_absolute_path_string(&execution_settings.input),
+            output_dir: get_absolute_path_string(&execution_settings.output_dir),
+            json_report_path: execution_settings
+                .json_report_path
+                .as_deref()
+                .map(get_absolute_path_string),
+            write_html_report: execution_settings.write_html_report,
+            report_settings: JsonReportSettings::new(execution_settings),
+            date_regexes: execution_settings
+                .regexes
+                .iter()
+                .map(|date_regex| ManifestDateRegex {
+                    pretty_format: &date_regex.pretty_format,
+                    regex: date_regex.regex.as_str(),
+                    strftime_format: &date_regex.strftime_format,
+                })
+                .collect(),
+            redaction_rules: PREBUILT_REDACTION_REGEXES
+                .iter()
+                .map(|redaction| ManifestRedactionRule {
+                    name: &redaction.name,
+                    pattern: redaction.pattern.as_str(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Hashes exactly the files LAVA recorded writing. Only files in this run's output directory or at its JSON report path are
+/// included, so anything else in a reused output directory is left out
+fn get_output_files_written(execution_settings: &ExecutionSettings) -> Vec<ManifestFile> {
+    let output_dir = get_absolute_path(&execution_settings.output_dir);
+    let json_report_path = execution_settings
+        .json_report_path
+        .as_deref()
+        .map(get_absolute_path);
+    let output_paths: BTreeSet<PathBuf> = helpers::get_output_files_written()
+        .iter()
+        .map(|output_path| get_absolute_path(output_path))
+        .filter(|output_path| {
+            output_path.starts_with(&output_dir) || json_report_path.as_ref() == Some(output_path)
+        })
+        .collect();
+
+    let mut outputs: Vec<ManifestFile> = Vec::new();
+    for output_path in output_paths {
+        let path = match output_path.strip_prefix(&output_dir) {
+            Ok(relative_path) => relative_path.to_string_lossy().replace('\\', "/"),
+            Err(_) => get_absolute_path_string(&output_path),
+        };
+        if path == MANIFEST_FILENAME {
+            continue;
+        }
+        match get_hash_of_file(&output_path) {
+            Ok(sha256) => outputs.push(ManifestFile { path, sha256 }),
+            Err(e) => eprintln!("Failed to hash {} for the manifest: {}", path, e),
+        }
+    }
+    outputs
+}
+
+/// Re-hashes every input and output in the manifest. Relative output paths are resolved against the manifest's directory
+pub fn verify_manifest(manifest_path: &Path) -> Result<ManifestVerification> {
+    let content = fs::read_to_string(manifest_path).map_err(|e| {
+        LavaError::new(
+            format!("Unable to read manifest because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    let manifest_hashes: ManifestHashes = serde_json::from_str(&content).map_err(|e| {
+        LavaError::new(
+            format!("Failed to parse manifest because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })?;
+    let manifest_dir = manifest_path.parent().unwrap_or(Path::new(""));
+
+    let mut verification = ManifestVerification::default();
+    for (kind, manifest_file, path) in manifest_hashes
+        .inputs
+        .iter()
+        .map(|input| ("Input", input, PathBuf::from(&input.path)))
+        .chain(
+            manifest_hashes
+                .outputs
+                .iter()
+                .map(|output| ("Output", output, manifest_dir.join(&output.path))),
+        )
+    {
+        verification.num_files_checked += 1;
+        match get_hash_of_file(&path) {
+            Ok(sha256) if sha256 == manifest_file.sha256 => (),
+            Ok(sha256) => verification.problems.push(format!(
+                "{} {} has changed, expected SHA256 {} but got {}",
+                kind, manifest_file.path, manifest_file.sha256, sha256
+            )),
+            Err(e) => verification.problems.push(format!(
+                "{} {} could not be hashed: {}",
+                kind, manifest_file.path, e
+            )),
+        }
+    }
+    Ok(verification)
+}
+
+fn get_hash_of_file(path: &Path) -> Result<String> {
+    get_hash_of_reader(File::open(path).map_err(|e| {
+        LavaError::new(
+            format!("Unable to open file because of {e}"),
+            LavaErrorLevel::Critical,
+        )
+    })?)
+}
+
+fn get_absolute_path(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn get_absolute_path_string(path: &Path) -> String {
+    get_absolute_path(path).to_string_lossy().to_string()
+}
+
+fn get_hostname() -> Option<String> {
+    if let Ok(hostname) = std::env::var("COMPUTERNAME") {
+        return Some(hostname); // Windows
+    }
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .map(|hostname| hostname.trim().to_string())
+        .filter(|hostname| !hostname.is_empty())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+}
+
+#[cfg(test)]
+mod manifest_tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_run(temp_dir: &TempDir) -> (ExecutionSettings, PathBuf) {
+        let input = temp_dir.path().join("app.log");
+        fs::write(&input, "2025-05-09 10:00:00 started\n").unwrap();
+        let output_dir = temp_dir.path().join("out");
+        fs::create_dir_all(output_dir.join("Gaps")).unwrap();
+        let start_time = Utc::now();
+        fs::write(output_dir.join("Gaps/app_GAPS.csv"), "Begin,End\n").unwrap();
+        helpers::record_output_file_written(&output_dir.join("Gaps/app_GAPS.csv"));
+        // Written during the run, but not by LAVA, so it is left out
+        fs::write(output_dir.join("notes.txt"), "not from LAVA\n").unwrap();
+        let execution_settings = ExecutionSettings {
+            input: input.clone(),
+            output_dir: output_dir.clone(),
+            ..Default::default()
+        };
+        let log_file = LogFile {
+            log_type: LogType::Unstructured,
+            file_path: input,
+            type_detection: None,
+            archive_member: None,
+        };
+        let mut manifest = RunManifest::new(
+            &execution_settings,
+            &[log_file],
+            &[ProcessedLogFile::default()],
+            start_time,
+        );
+        manifest.write_to_file(&output_dir).unwrap();
+        (execution_settings, output_dir.join(MANIFEST_FILENAME))
+    }
+
+    #[test]
+    fn manifest_hashes_inputs_and_outputs() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_, manifest_path) = write_run(&temp_dir);
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+
+        assert_eq!(manifest["inputs"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            manifest["inputs"][0]["sha256"],
+            get_hash_of_file(&temp_dir.path().join("app.log")).unwrap()
+        );
+        assert_eq!(manifest["outputs"][0]["path"], "Gaps/app_GAPS.csv");
+        assert_eq!(manifest["outputs"].as_array().unwrap().len(), 1);
+        assert!(
+            !manifest["settings"]["redaction_rules"]
+                .as_array()
+                .unwrap()
+                .is_empty()
+        );
+        assert!(manifest["settings"]["alert_config"].is_object());
+        assert_eq!(manifest["record_hash"]["algorithm"], "xxh3-128");
+        assert_eq!(manifest["record_hash"]["seed"], 0);
+    }
+
+    #[test]
+    fn processed_hashes_are_matched_to_inputs_by_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let execution_settings = ExecutionSettings {
+            output_dir: temp_dir.path().join("out"),
+            ..Default::default()
+        };
+        let log_files: Vec<LogFile> = ["first.log", "second.log", "third.log"]
+            .iter()
+            .map(|file_name| {
+                let file_path = temp_dir.path().join(file_name);
+                fs::write(&file_path, *file_name).unwrap();
+                LogFile {
+                    log_type: LogType::Unstructured,
+                    file_path,
+                    type_detection: None,
+                    archive_member: None,
+                }
+            })
+            .collect();
+        // Out of order, and the third file has no processed result, so it is hashed from disk
+        let processed_log_files: Vec<ProcessedLogFile> = [&log_files[1], &log_files[0]]
+            .iter()
+            .map(|log_file| ProcessedLogFile {
+                file_path: Some(log_file.get_display_path()),
+                sha256hash: Some(format!("hash of {}", log_file.get_display_path())),
+                ..Default::default()
+            })
+            .collect();
+
+        let manifest = RunManifest::new(
+            &execution_settings,
+            &log_files,
+            &processed_log_files,
+            Utc::now(),
+        );
+        let hashes: Vec<&str> = manifest
+            .inputs
+            .iter()
+            .map(|input| input.sha256.as_str())
+            .collect();
+        assert_eq!(
+            hashes,
+            vec![
+                format!("hash of {}", log_files[0].get_display_path()).as_str(),
+                format!("hash of {}", log_files[1].get_display_path()).as_str(),
+                get_hash_of_file(&log_files[2].file_path).unwrap().as_str(),
+            ]
+        );
+    }
+
+    #[test]
+    fn verify_reports_changed_and_missing_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let (execution_settings, manifest_path) = write_run(&temp_dir);
+
+        let verification = verify_manifest(&manifest_path).unwrap();
+        assert_eq!(verification.num_files_checked, 2);
+        assert!(verification.problems.is_empty());
+
+        fs::write(&execution_settings.input, "2025-05-09 10:00:01 started\n").unwrap();
+        fs::remove_file(execution_settings.output_dir.join("Gaps/app_GAPS.csv")).unwrap();
+        let verification = verify_manifest(&manifest_path).unwrap();
+        assert_eq!(verification.problems.len(), 2);
+        assert!(verification.problems[0].starts_with("Input"));
+        assert!(verification.problems[1].contains("could not be hashed"));
+    }
+}
@@ -0,0 +1,512 @@
+use crate::basic_objects::{IdentifiedTimeInformation, NearDupeSettings};
+use crate::date_regex::DateRegex;
+use crate::errors::*;
+use crate::helpers::{format_record_hash, hash_fields, write_rows_to_file};
+use csv::StringRecord;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+static VOLATILE_FIELD_SAMPLE_SIZE: usize = 1000; // Records held back at the start of the file to find the volatile fields in
+static MIN_VOLATILE_FIELD_SAMPLE_SIZE: usize = 10; // Fewer values than this can't show a field is unique
+static MAX_VARIANT_INDEXES_TO_OUTPUT: usize = 10;
+
+static GUID_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\{?[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\}?$",
+    )
+    .unwrap()
+});
+static LONG_HEX_ID_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[0-9a-fA-F]{16,}$").unwrap());
+
+/// Records that are the same once the volatile fields are dropped, by the hash of the fields that are left
+#[derive(Debug, Clone, PartialEq)]
+pub struct NearDupeCluster {
+    pub key_hash: u128,
+    pub num_records: usize,
+    first_variant: (u128, usize), // Hash of the entire record and the index it was first seen at
+    other_variant_hashes: HashSet<u128>,
+    other_variant_indexes: Vec<usize>, // Stops growing once there are enough to output
+}
+
+impl NearDupeCluster {
+    fn new(key_hash: u128, hash_of_entire_record: u128, index: usize) -> Self {
+        Self {
+            key_hash,
+            num_records: 1,
+            first_variant: (hash_of_entire_record, index),
+            other_variant_hashes: HashSet::new(),
+            other_variant_indexes: Vec::new(),
+        }
+    }
+
+    pub fn get_num_variants(&self) -> usize {
+        1 + self.other_variant_hashes.len()
+    }
+
+    pub fn get_first_index(&self) -> usize {
+        self.first_variant.1
+    }
+
+    fn add_record(&mut self, hash_of_entire_record: u128, index: usize) {
+        self.num_records += 1;
+        if self.first_variant.0 != hash_of_entire_record
+            && self.other_variant_hashes.insert(hash_of_entire_record)
+            && self.other_variant_indexes.len() + 1 < MAX_VARIANT_INDEXES_TO_OUTPUT
+        {
+            self.other_variant_indexes.push(index);
+        }
+    }
+
+    fn get_output_row(&self) -> Vec<String> {
+        let variant_indexes: Vec<String> = std::iter::once(&self.first_variant.1)
+            .chain(self.other_variant_indexes.iter())
+            .map(|index| index.to_string())
+            .collect();
+        vec![
+            format_record_hash(self.key_hash),
+            self.num_records.to_string(),
+            self.get_num_variants().to_string(),
+            variant_indexes.join(";"),
+        ]
+    }
+}
+
+struct SampledRecord {
+    index: usize,
+    hash_of_entire_record: u128,
+    fields: Vec<(String, String)>,
+}
+
+/// Finds records that differ only in fields that change every time a record is ingested, like a sequence ID, a GUID or
+/// an ingest timestamp. The fields to drop are the ones given plus any found to be volatile in the first records of the file.
+/// Byte identical records are left to the exact duplicate check, so only clusters with more than one variant are reported.
+/// Every distinct record gets a cluster, held in memory whatever the duplicate detection mode is
+#[derive(Default)]
+pub struct NearDupeDetector {
+    ignored_fields: Vec<String>,
+    event_timestamp_fields: Vec<String>, // The timestamp used for time analysis is what tells real repeats apart, so it is never dropped
+    date_regexes: Vec<DateRegex>,
+    sample: Vec<SampledRecord>,
+    volatile_fields: Option<Vec<String>>, // Set once the sample is full
+    clusters: HashMap<u128, NearDupeCluster>,
+}
+
+impl NearDupeDetector {
+    pub fn new(
+        near_dupe_settings: &NearDupeSettings,
+        timestamp_hit: &Option<IdentifiedTimeInformation>,
+        headers: &StringRecord,
+        date_regexes: &[DateRegex],
+    ) -> Self {
+        let mut event_timestamp_fields: Vec<String> = Vec::new();
+        if let Some(timestamp_hit) = timestamp_hit {
+            if let Some(column_name) = &timestamp_hit.column_name {
+                event_timestamp_fields
+                    .extend(column_name.split('+').map(|field| field.to_string()));
+            }
+            for column_index in timestamp_hit
+                .column_index
+                .iter()
+                .chain(timestamp_hit.composite_column_indices.iter())
+            {
+                if let Some(header) = headers.get(*column_index) {
+                    event_timestamp_fields.push(header.to_string());
+                }
+            }
+        }
+        Self {
+            ignored_fields: near_dupe_settings.ignored_fields.clone(),
+            event_timestamp_fields,
+            date_regexes: date_regexes.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    pub fn process_record(
+        &mut self,
+        index: usize,
+        hash_of_entire_record: u128,
+        headers: &StringRecord,
+        record: &StringRecord,
+    ) {
+        let fields = get_record_fields(headers, record);
+        match &self.volatile_fields {
+            Some(_) => self.add_to_cluster(index, hash_of_entire_record, &fields),
+            None => {
+                self.sample.push(SampledRecord {
+                    index,
+                    hash_of_entire_record,
+                    fields,
+                });
+                if self.sample.len() >= VOLATILE_FIELD_SAMPLE_SIZE {
+                    self.finish_sample();
+                }
+            }
+        }
+    }
+
+    /// Clusters with more than one variant, in the order they were first seen
+    pub fn finish(&mut self) -> Vec<NearDupeCluster> {
+        if self.volatile_fields.is_none() {
+            self.finish_sample();
+        }
+        let mut clusters: Vec<NearDupeCluster> = self
+            .clusters
+            .values()
+            .filter(|cluster| cluster.get_num_variants() > 1)
+            .cloned()
+            .collect();
+        clusters.sort_by_key(|cluster| cluster.get_first_index());
+        clusters
+    }
+
+    /// Everything left out of the near duplicate hash, given and found
+    pub fn get_dropped_fields(&self) -> Vec<String> {
+        let mut dropped_fields = self.ignored_fields.clone();
+        for volatile_field in self.volatile_fields.iter().flatten() {
+            if !dropped_fields.contains(volatile_field) {
+                dropped_fields.push(volatile_field.clone());
+            }
+        }
+        dropped_fields
+    }
+
+    fn finish_sample(&mut self) {
+        self.volatile_fields = Some(self.find_volatile_fields());
+        let sample = std::mem::take(&mut self.sample);
+        for sampled_record in sample {
+            self.add_to_cluster(
+                sampled_record.index,
+                sampled_record.hash_of_entire_record,
+                &sampled_record.fields,
+            );
+        }
+    }
+
+    fn find_volatile_fields(&self) -> Vec<String> {
+        let mut field_names: Vec<&str> = Vec::new();
+        for sampled_record in self.sample.iter() {
+            for (field_name, _) in sampled_record.fields.iter() {
+                if !field_names.contains(&field_name.as_str()) {
+                    field_names.push(field_name);
+                }
+            }
+        }
+        field_names
+            .into_iter()
+            .filter(|field_name| {
+                !self.is_ignored(field_name)
+                    && !self
+                        .event_timestamp_fields
+                        .iter()
+                        .any(|event_timestamp_field| event_timestamp_field == field_name)
+                    && !self.is_whole_record(field_name)
+            })
+            .filter(|field_name| {
+                let values: Vec<&str> = self
+                    .sample
+                    .iter()
+                    .filter_map(|sampled_record| {
+                        sampled_record
+                            .fields
+                            .iter()
+                            .find(|(name, _)| name == field_name)
+                            .map(|(_, value)| value.as_str())
+                    })
+                    .collect();
+                values_look_volatile(&values, &self.date_regexes)
+            })
+            .map(|field_name| field_name.to_string())
+            .collect()
+    }
+
+    /// Unstructured and syslog lines are a single field, which is unique and has a date in it on every line
+    fn is_whole_record(&self, field_name: &str) -> bool {
+        self.sample.iter().any(|sampled_record| {
+            sampled_record.fields.len() == 1 && sampled_record.fields[0].0 == field_name
+        })
+    }
+
+    fn is_ignored(&self, field_name: &str) -> bool {
+        self.ignored_fields
+            .iter()
+            .chain(self.volatile_fields.iter().flatten())
+            .any(|ignored_field| field_matches(field_name, ignored_field))
+    }
+
+    fn add_to_cluster(
+        &mut self,
+        index: usize,
+        hash_of_entire_record: u128,
+        fields: &[(String, String)],
+    ) {
+        let kept_fields: Vec<&(String, String)> = fields
+            .iter()
+            .filter(|(field_name, _)| !self.is_ignored(field_name))
+            .collect();
+        // Every record would have the same empty key, which says nothing about them being alike
+        if kept_fields.is_empty() {
+            return;
+        }
+        let key_hash = hash_fields(
+            kept_fields
+                .into_iter()
+                .flat_map(|(field_name, value)| [field_name.as_str(), value.as_str()]),
+        );
+        self.clusters
+            .entry(key_hash)
+            .and_modify(|cluster| cluster.add_record(hash_of_entire_record, index))
+            .or_insert_with(|| NearDupeCluster::new(key_hash, hash_of_entire_record, index));
+    }
+}
+
+/// A field is volatile if every value in the sample is different and either counts up or down like a sequence ID,
+/// or looks like a GUID, a long hex ID or a timestamp
+fn values_look_volatile(values: &[&str], date_regexes: &[DateRegex]) -> bool {
+    if values.len() < MIN_VOLATILE_FIELD_SAMPLE_SIZE {
+        return false;
+    }
+    let unique_values: HashSet<&&str> = values.iter().collect();
+    if unique_values.len() != values.len() {
+        return false;
+    }
+    let numbers: Option<Vec<i128>> = values.iter().map(|value| value.parse().ok()).collect();
+    if let Some(numbers) = numbers {
+        return numbers.windows(2).all(|pair| pair[0] < pair[1])
+            || numbers.windows(2).all(|pair| pair[0] > pair[1]);
+    }
+    values.iter().all(|value| {
+        GUID_REGEX.is_match(value)
+            || LONG_HEX_ID_REGEX.is_match(value)
+            || date_regexes
+                .iter()
+                .any(|date_regex| date_regex.string_contains_date(value))
+    })
+}
+
+/// A JSON pointer also matches everything nested under it
+fn field_matches(field_name: &str, ignored_field: &str) -> bool {
+    field_name == ignored_field
+        || (ignored_field.starts_with('/')
+            && field_name.starts_with(ignored_field)
+            && field_name[ignored_field.len()..].starts_with('/'))
+}
+
+/// Column names and values, or JSON pointers and leaf values for JSON records
+fn get_record_fields(headers: &StringRecord, record: &StringRecord) -> Vec<(String, String)> {
+    if record.len() == 1
+        && let Some(content) = record.get(0)
+        && content.trim_start().starts_with('{')
+        && let Ok(value) = serde_json::from_str::<Value>(content)
+    {
+        let mut fields: Vec<(String, String)> = Vec::new();
+        flatten_json_value(&value, String::new(), &mut fields);
+        return fields;
+    }
+    record
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let field_name = headers
+                .get(i)
+                .map(|header| header.to_string())
+                .unwrap_or_else(|| format!("Column {}", i + 1));
+            (field_name, value.to_string())
+        })
+        .collect()
+}
+
+fn flatten_json_value(value: &Value, pointer: String, fields: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            // Sorted so the same fields in a different order are still a near duplicate
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_unstable_by_key(|(key, _)| *key);
+            for (key, nested_value) in entries {
+                let escaped_key = key.replace('~', "~0").replace('/', "~1");
+                flatten_json_value(nested_value, format!("{}/{}", pointer, escaped_key), fields);
+            }
+        }
+        Value::Array(array) => {
+            for (i, nested_value) in array.iter().enumerate() {
+                flatten_json_value(nested_value, format!("{}/{}", pointer, i), fields);
+            }
+        }
+        Value::String(string) => fields.push((pointer, string.clone())),
+        _ => fields.push((pointer, value.to_string())),
+    }
+}
+
+pub fn write_near_dupe_clusters_to_file(
+    output_dir: &Path,
+    file_name: &str,
+    clusters: &[NearDupeCluster],
+) -> Result<()> {
+    let output_file = output_dir.join(format!("NearDuplicates/{}_NEAR_DUPLICATES.csv", file_name));
+    write_rows_to_file(
+        &output_file,
+        &[
+            "Cluster Hash",
+            "Number of Records",
+            "Distinct Variants",
+            "Index of First Record of Each Variant",
+        ],
+        clusters.iter().map(|cluster| cluster.get_output_row()),
+    )
+}
+
+#[cfg(test)]
+mod near_dupe_tests {
+    use super::*;
+    use crate::helpers::hash_csv_record;
+
+    fn detector(ignored_fields: Vec<&str>, headers: &StringRecord) -> NearDupeDetector {
+        NearDupeDetector::new(
+            &NearDupeSettings {
+                ignored_fields: ignored_fields.iter().map(|f| f.to_string()).collect(),
+            },
+            &None,
+            headers,
+            &[],
+        )
+    }
+
+    fn add(
+        detector: &mut NearDupeDetector,
+        index: usize,
+        headers: &StringRecord,
+        fields: Vec<&str>,
+    ) {
+        let record = StringRecord::from(fields);
+        detector.process_record(index, hash_csv_record(&record), headers, &record);
+    }
+
+    #[test]
+    fn sequence_ids_and_guids_are_found_and_dropped() {
+        let headers = StringRecord::from(vec!["seq", "guid", "user", "action"]);
+        let mut detector = detector(Vec::new(), &headers);
+        for i in 0..20 {
+            let guid = format!("0000{:04}-1111-2222-3333-444455556666", i);
+            let user = format!("user{}", i % 5); // Repeats every 5 records
+            add(
+                &mut detector,
+                i,
+                &headers,
+                vec![&(100 + i).to_string(), &guid, &user, "login"],
+            );
+        }
+        let clusters = detector.finish();
+
+        assert_eq!(detector.get_dropped_fields(), vec!["seq", "guid"]);
+        assert_eq!(clusters.len(), 5);
+        assert_eq!(clusters[0].num_records, 4);
+        assert_eq!(clusters[0].get_num_variants(), 4);
+        assert_eq!(clusters[0].get_output_row()[3], "0;5;10;15");
+    }
+
+    #[test]
+    fn exact_duplicates_are_not_near_duplicates() {
+        let headers = StringRecord::from(vec!["user", "action"]);
+        let mut detector = detector(Vec::new(), &headers);
+        add(&mut detector, 0, &headers, vec!["alice", "login"]);
+        add(&mut detector, 1, &headers, vec!["alice", "login"]);
+        add(&mut detector, 2, &headers, vec!["bob", "login"]);
+        assert!(detector.finish().is_empty());
+    }
+
+    #[test]
+    fn given_json_pointers_drop_nested_fields() {
+        let headers = StringRecord::from(vec!["Record"]);
+        let mut detector = detector(vec!["/meta"], &headers);
+        add(
+            &mut detector,
+            0,
+            &headers,
+            vec![r#"{"msg":"started","meta":{"ingest":"a","id":1}}"#],
+        );
+        add(
+            &mut detector,
+            1,
+            &headers,
+            vec![r#"{"msg":"started","meta":{"ingest":"b","id":2}}"#],
+        );
+        add(
+            &mut detector,
+            2,
+            &headers,
+            vec![r#"{"msg":"stopped","meta":{"ingest":"c","id":3}}"#],
+        );
+        let clusters = detector.finish();
+
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].num_records, 2);
+        assert_eq!(clusters[0].get_num_variants(), 2);
+    }
+
+    #[test]
+    fn unstructured_lines_are_not_dropped_as_volatile() {
+        let headers = StringRecord::from(vec!["Record"]);
+        let mut detector = NearDupeDetector::new(
+            &NearDupeSettings::default(),
+            &None,
+            &headers,
+            &crate::PREBUILT_DATE_REGEXES,
+        );
+        for i in 0..3000 {
+            let line = format!(
+                "2024-05-01 10:{:02}:{:02} worker {} finished",
+                i / 60 % 60,
+                i % 60,
+                i
+            );
+            add(&mut detector, i, &headers, vec![&line]);
+        }
+        let clusters = detector.finish();
+
+        assert!(detector.get_dropped_fields().is_empty());
+        assert!(clusters.is_empty());
+    }
+
+    #[test]
+    fn records_with_every_field_dropped_are_not_clustered() {
+        let headers = StringRecord::from(vec!["Record"]);
+        let mut detector = detector(vec!["Record"], &headers);
+        add(&mut detector, 0, &headers, vec!["first line"]);
+        add(&mut detector, 1, &headers, vec!["second line"]);
+        assert!(detector.finish().is_empty());
+    }
+
+    #[test]
+    fn big_clusters_only_keep_the_first_variant_indexes() {
+        let headers = StringRecord::from(vec!["user", "action"]);
+        let mut detector = detector(vec!["user"], &headers);
+        for i in 0..500 {
+            add(
+                &mut detector,
+                i,
+                &headers,
+                vec![&format!("user{}", i), "login"],
+            );
+        }
+        let clusters = detector.finish();
+
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].get_num_variants(), 500);
+        assert_eq!(clusters[0].get_output_row()[3], "0;1;2;3;4;5;6;7;8;9");
+    }
+
+    #[test]
+    fn fields_that_repeat_are_not_volatile() {
+        let values: Vec<String> = (0..20).map(|i| (i % 10).to_string()).collect();
+        let values: Vec<&str> = values.iter().map(|v| v.as_str()).collect();
+        assert!(!values_look_volatile(&values, &[]));
+        let names: Vec<String> = (0..20).map(|i| format!("user{}", i)).collect();
+        let names: Vec<&str> = names.iter().map(|v| v.as_str()).collect();
+        assert!(!values_look_volatile(&names, &[]));
+        assert!(field_matches("/meta/id", "/meta"));
+        assert!(!field_matches("/metadata", "/meta"));
+    }
+}
@@ -1,20 +1,43 @@
+use crate::activity_profile::*;
+use crate::alerts::{AlertRules, TimeGapAlertBaseline};
 use crate::basic_objects::*;
+use crate::bounded_dupes::*;
 use crate::errors::*;
-use chrono::NaiveDateTime;
+use crate::helpers::{
+    format_record_hash, get_seconds_string_from_time_delta, record_output_file_written,
+    write_rows_to_file,
+};
+use crate::near_dupes::*;
+use crate::volume_series::*;
+use chrono::{NaiveDateTime, TimeDelta};
 use csv::StringRecord;
 use csv::WriterBuilder;
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
 use std::fs::OpenOptions;
 use std::path::PathBuf;
 use welford::Welford;
 include!(concat!(env!("OUT_DIR"), "/generated_redaction_regexes.rs"));
 
+static EVTX_LOG_CLEARED_EVENT_ID: u64 = 1102;
+static CADENCE_SAMPLE_SIZE: usize = 100; // Number of gaps the cadence is estimated from
+static MIN_CADENCE_SAMPLE_SIZE: usize = 10; // Files with fewer gaps than this are not checked for a cadence
+static CADENCE_TOLERANCE: f64 = 0.5; // Fraction of the cadence a gap can be off by and still be on time
+static MIN_FRACTION_OF_GAPS_ON_CADENCE: f64 = 0.9; // Fraction of the sample that has to be on time for the log to be periodic
+static MAX_REPEAT_INDEXES_TO_OUTPUT: usize = 100; // Records repeated more than this only get a count in the duplicate summary
+
 #[cfg(test)]
 mod tests {
     mod build_file_path_tests;
+    mod cadence_processing_tests;
     mod direction_checker_tests;
+    mod dst_processing_tests;
     mod dupe_processing_tests;
+    mod evtx_processing_tests;
+    mod out_of_order_processing_tests;
     mod redaction_processing_tests;
+    mod time_gap_processing_tests;
     mod timestamp_processing_tests;
 }
 
@@ -40,6 +63,266 @@ impl TimeDirectionChecker {
     }
 }
 
+/// Every occurrence of one record, keyed by its hash in the duplicate tracker
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DuplicateOccurrences {
+    pub first_index: usize,
+    pub last_index: usize,
+    pub num_occurrences: usize,
+    pub repeat_indexes: Vec<usize>, // Stops growing at MAX_REPEAT_INDEXES_TO_OUTPUT
+    pub min_timestamp: Option<NaiveDateTime>,
+    pub max_timestamp: Option<NaiveDateTime>,
+    pub num_contiguous_repeats: usize, // Repeats right after the last occurrence, or inside a repeated block of records
+}
+
+impl DuplicateOccurrences {
+    fn new(index: usize, timestamp: Option<NaiveDateTime>) -> Self {
+        Self {
+            first_index: index,
+            last_index: index,
+            num_occurrences: 1,
+            min_timestamp: timestamp,
+            max_timestamp: timestamp,
+            ..Default::default()
+        }
+    }
+
+    fn add_repeat(&mut self, record: &LogFileRecord) {
+        self.num_occurrences += 1;
+        self.last_index = record.index;
+        if self.repeat_indexes.len() < MAX_REPEAT_INDEXES_TO_OUTPUT {
+            self.repeat_indexes.push(record.index);
+        }
+        if let Some(timestamp) = record.timestamp {
+            self.min_timestamp = Some(self.min_timestamp.map_or(timestamp, |t| t.min(timestamp)));
+            self.max_timestamp = Some(self.max_timestamp.map_or(timestamp, |t| t.max(timestamp)));
+        }
+    }
+
+    /// Folds in the occurrences of the same record from a later spilled run of the duplicate tracker
+    pub fn merge_later_occurrences(&mut self, later: DuplicateOccurrences) {
+        if later.first_index == self.last_index + 1 {
+            self.num_contiguous_repeats += 1;
+        }
+        self.num_contiguous_repeats += later.num_contiguous_repeats;
+        self.num_occurrences += later.num_occurrences;
+        self.last_index = later.last_index;
+        for index in std::iter::once(later.first_index).chain(later.repeat_indexes) {
+            if self.repeat_indexes.len() < MAX_REPEAT_INDEXES_TO_OUTPUT {
+                self.repeat_indexes.push(index);
+            }
+        }
+        self.min_timestamp = match (self.min_timestamp, later.min_timestamp) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max_timestamp = match (self.max_timestamp, later.max_timestamp) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// Repeats that are all contiguous point to a copy and paste or an export bug rather than the event really happening again
+    pub fn repeats_are_contiguous(&self) -> bool {
+        self.num_contiguous_repeats == self.num_occurrences - 1
+    }
+
+    pub fn get_summary_output_row(&self, hash_of_record: u128) -> Vec<String> {
+        let num_repeats = self.num_occurrences - 1;
+        vec![
+            format_record_hash(hash_of_record),
+            self.first_index.to_string(),
+            self.num_occurrences.to_string(),
+            if num_repeats > self.repeat_indexes.len() {
+                format!("{} repeats", num_repeats)
+            } else {
+                self.repeat_indexes
+                    .iter()
+                    .map(|index| index.to_string())
+                    .collect::<Vec<String>>()
+                    .join(";")
+            },
+            self.min_timestamp
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+            self.max_timestamp
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+            self.max_timestamp
+                .zip(self.min_timestamp)
+                .map(|(max_timestamp, min_timestamp)| {
+                    get_seconds_string_from_time_delta(max_timestamp - min_timestamp)
+                })
+                .unwrap_or_default(),
+            if self.repeats_are_contiguous() {
+                "Yes".to_string()
+            } else {
+                "No".to_string()
+            },
+        ]
+    }
+}
+
+/// An entry in the duplicate tracker. Most records are only seen once, so the full occurrences are only built on the first repeat
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackedRecord {
+    SeenOnce {
+        index: usize,
+        timestamp: Option<NaiveDateTime>,
+    },
+    Repeated(Box<DuplicateOccurrences>),
+}
+
+impl TrackedRecord {
+    fn new(record: &LogFileRecord) -> Self {
+        TrackedRecord::SeenOnce {
+            index: record.index,
+            timestamp: record.timestamp,
+        }
+    }
+
+    fn get_last_index(&self) -> usize {
+        match self {
+            TrackedRecord::SeenOnce { index, .. } => *index,
+            TrackedRecord::Repeated(occurrences) => occurrences.last_index,
+        }
+    }
+
+    fn add_repeat(&mut self, record: &LogFileRecord) {
+        if let TrackedRecord::SeenOnce { index, timestamp } = *self {
+            *self = TrackedRecord::Repeated(Box::new(DuplicateOccurrences::new(index, timestamp)));
+        }
+        if let TrackedRecord::Repeated(occurrences) = self {
+            occurrences.add_repeat(record);
+        }
+    }
+
+    fn add_contiguous_repeat(&mut self) {
+        if let TrackedRecord::Repeated(occurrences) = self {
+            occurrences.num_contiguous_repeats += 1;
+        }
+    }
+
+    /// Memory used outside of the duplicate tracker itself, for the estimate of how much it's using
+    fn get_heap_bytes(&self) -> usize {
+        match self {
+            TrackedRecord::SeenOnce { .. } => 0,
+            TrackedRecord::Repeated(occurrences) => {
+                size_of::<DuplicateOccurrences>()
+                    + occurrences.repeat_indexes.len() * size_of::<usize>()
+            }
+        }
+    }
+
+    pub fn get_occurrences(&self) -> Cow<'_, DuplicateOccurrences> {
+        match self {
+            TrackedRecord::SeenOnce { index, timestamp } => {
+                Cow::Owned(DuplicateOccurrences::new(*index, *timestamp))
+            }
+            TrackedRecord::Repeated(occurrences) => Cow::Borrowed(occurrences),
+        }
+    }
+}
+
+/// The last record seen by the duplicate tracker, used to tell when repeats line up into a copied block
+#[derive(Debug, Clone, Copy)]
+struct PreviousTrackedRecord {
+    hash_of_record: u128,
+    index: usize,
+    previous_occurrence_index: Option<usize>,
+    counted_as_contiguous: bool,
+}
+
+/// Periodic logs like heartbeats and metrics are written at a fixed interval, so a gap of several intervals means records are missing
+#[derive(Debug, Default)]
+pub struct CadenceDetector {
+    pub cadence: Option<TimeDelta>,
+    sample: Vec<IndexedTimeGap>,
+    sample_is_done: bool,
+}
+
+impl CadenceDetector {
+    /// Returns the gaps now known to have missed intervals. The first gaps are held back until there are enough to estimate the cadence
+    pub fn process_time_gap(
+        &mut self,
+        indexed_time_gap: IndexedTimeGap,
+    ) -> Vec<MissedCadenceInterval> {
+        if self.sample_is_done {
+            return self
+                .get_missed_cadence_interval(indexed_time_gap)
+                .into_iter()
+                .collect();
+        }
+        self.sample.push(indexed_time_gap);
+        if self.sample.len() < CADENCE_SAMPLE_SIZE {
+            return Vec::new();
+        }
+        self.estimate_cadence_from_sample()
+    }
+
+    /// Called at the end of the file, in case there were fewer gaps than a full sample
+    pub fn finish(&mut self) -> Vec<MissedCadenceInterval> {
+        if self.sample_is_done || self.sample.len() < MIN_CADENCE_SAMPLE_SIZE {
+            return Vec::new();
+        }
+        self.estimate_cadence_from_sample()
+    }
+
+    /// The median gap is the cadence, as long as nearly every gap in the sample is close to it
+    fn estimate_cadence_from_sample(&mut self) -> Vec<MissedCadenceInterval> {
+        self.sample_is_done = true;
+        let sample = std::mem::take(&mut self.sample);
+        let mut gaps: Vec<TimeDelta> = sample
+            .iter()
+            .map(|indexed_time_gap| indexed_time_gap.time_gap.gap)
+            .collect();
+        gaps.sort();
+        let median_gap = gaps[gaps.len() / 2];
+        if median_gap <= TimeDelta::zero() {
+            return Vec::new();
+        }
+        let tolerance = get_microseconds(median_gap) * CADENCE_TOLERANCE;
+        let num_gaps_on_cadence = gaps
+            .iter()
+            .filter(|gap| get_microseconds(**gap - median_gap).abs() <= tolerance)
+            .count();
+        if (num_gaps_on_cadence as f64) < gaps.len() as f64 * MIN_FRACTION_OF_GAPS_ON_CADENCE {
+            return Vec::new();
+        }
+        self.cadence = Some(median_gap);
+        sample
+            .into_iter()
+            .filter_map(|indexed_time_gap| self.get_missed_cadence_interval(indexed_time_gap))
+            .collect()
+    }
+
+    fn get_missed_cadence_interval(
+        &self,
+        indexed_time_gap: IndexedTimeGap,
+    ) -> Option<MissedCadenceInterval> {
+        let cadence = self.cadence?;
+        let num_cadences_in_gap =
+            get_microseconds(indexed_time_gap.time_gap.gap) / get_microseconds(cadence);
+        if num_cadences_in_gap <= 1.0 + CADENCE_TOLERANCE {
+            return None;
+        }
+        Some(MissedCadenceInterval {
+            indexed_time_gap,
+            num_missed_intervals: (num_cadences_in_gap.round() as usize)
+                .saturating_sub(1)
+                .max(1),
+        })
+    }
+}
+
+/// Logs can be written more than once a millisecond, so the cadence math can't be done in milliseconds
+fn get_microseconds(time_delta: TimeDelta) -> f64 {
+    match time_delta.num_microseconds() {
+        Some(microseconds) => microseconds as f64,
+        None => time_delta.num_milliseconds() as f64 * 1000.0,
+    }
+}
+
 #[derive(Default)]
 pub struct LogRecordProcessor {
     pub order: Option<TimeDirection>,
@@ -50,11 +333,40 @@ pub struct LogRecordProcessor {
     pub timestamp_num_records: usize,
     pub min_timestamp: Option<NaiveDateTime>,
     pub max_timestamp: Option<NaiveDateTime>,
-    pub previous_timestamp: Option<NaiveDateTime>,
+    pub previous_timestamp: Option<NaiveDateTime>, // Latest timestamp in the direction of the file
+    pub previous_timestamp_in_time_order: Option<(NaiveDateTime, usize)>, // Timestamp and index used for time gaps, lags behind in tolerant mode
+    reorder_buffer: BTreeMap<NaiveDateTime, Vec<usize>>, // Indexes of the records held back at each timestamp
     pub largest_time_gap: Option<TimeGap>,
-    pub duplicate_checker_set: HashSet<u64>,
+    pub largest_time_gaps: BinaryHeap<Reverse<IndexedTimeGap>>, // Min-heap so the smallest of the top gaps is the one pushed out
+    pub approx_num_time_gaps_above_threshold: usize,
+    pub alert_rules: AlertRules, // Rules from the alert config for this file, set before the timestamps are finished
+    pub time_gap_sketch: TimeGapSketch,
+    pub cadence_detector: CadenceDetector,
+    pub num_missed_cadence_intervals: usize,
+    pub activity_profile: ActivityProfile,
+    pub quiet_activity_periods: Vec<QuietActivityPeriod>,
+    pub volume_series: VolumeSeries,
+    pub num_volume_drops: usize,
+    pub num_volume_spikes: usize,
+    pub duplicate_tracker: HashMap<u128, TrackedRecord>,
+    num_tracked_repeat_bytes: usize, // Occurrences of repeated records, for the estimate of how much memory the duplicate tracker is using
+    duplicate_spiller: Option<DuplicateTrackerSpiller>, // Only in spill mode
+    pub num_spilled_duplicate_runs: usize,
+    approximate_duplicate_filter: Option<ScalableBloomFilter>, // Replaces the duplicate tracker in approximate mode
+    previous_tracked_record: Option<PreviousTrackedRecord>,
     pub num_dupes: usize,
+    pub near_dupe_detector: Option<NearDupeDetector>, // Only when near duplicate detection is on
+    pub num_near_dupe_clusters: Option<usize>,
+    pub near_dupe_dropped_fields: Vec<String>,
     pub num_redactions: usize,
+    pub previous_event_record_id: Option<u64>,
+    first_event_record_id: Option<u64>,
+    possible_evtx_wrap: Option<(LogFileRecord, String)>, // First jump back in record IDs, only a gap if the IDs don't join up at the end
+    pub num_evtx_record_id_gaps: usize,
+    pub num_evtx_log_cleared_events: usize,
+    pub num_out_of_order_records: usize,
+    pub largest_out_of_order_displacement: Option<TimeDelta>,
+    pub dst_transitions: Vec<String>, // Explained jumps in local time when the assumed timezone is a named zone
     pub errors: Vec<LavaError>,
     pub welford_calculator: Welford<i128>,
     process_timestamps: bool,
@@ -82,12 +394,36 @@ impl LogRecordProcessor {
             Some(hit) => hit.direction.clone(),
             None => None,
         };
+        let near_dupe_detector =
+            execution_settings
+                .near_dupe_settings
+                .as_ref()
+                .map(|near_dupe_settings| {
+                    NearDupeDetector::new(
+                        near_dupe_settings,
+                        timestamp_hit,
+                        &data_field_headers,
+                        &execution_settings.regexes,
+                    )
+                });
+        let (duplicate_spiller, approximate_duplicate_filter) =
+            match execution_settings.dupe_detection_mode {
+                DupeDetectionMode::InMemory => (None, None),
+                DupeDetectionMode::Spill { .. } => (Some(DuplicateTrackerSpiller::default()), None),
+                DupeDetectionMode::Approximate {
+                    false_positive_rate,
+                } => (None, Some(ScalableBloomFilter::new(false_positive_rate))),
+            };
         Self {
             order,
             execution_settings: execution_settings.clone(),
             file_name: log_file_stem,
             data_field_headers: data_field_headers,
             process_timestamps: process_timestamps,
+            volume_series: VolumeSeries::new(execution_settings.volume_window),
+            near_dupe_detector,
+            duplicate_spiller,
+            approximate_duplicate_filter,
             ..Default::default()
         }
     }
@@ -96,6 +432,7 @@ impl LogRecordProcessor {
 
         if !self.execution_settings.quick_mode {
             self.process_record_for_dupes(&record)?;
+            self.process_record_for_near_dupes(&record);
             self.process_record_for_redactions(&record)?;
         }
 
@@ -107,12 +444,59 @@ impl LogRecordProcessor {
     }
 
     pub fn process_record_for_dupes(&mut self, record: &LogFileRecord) -> Result<()> {
-        let is_duplicate = !self
-            .duplicate_checker_set
-            .insert(record.hash_of_entire_record);
-        if is_duplicate {
+        let hash_of_record = record.hash_of_entire_record;
+        if let Some(approximate_duplicate_filter) = self.approximate_duplicate_filter.as_mut() {
+            if approximate_duplicate_filter.insert(hash_of_record) {
+                self.num_dupes += 1;
+                if self.execution_settings.actually_write_to_files
+                    && let Err(e) = self.write_hit_to_file(record, AlertOutputType::Duplicate, None)
+                {
+                    self.errors.push(e);
+                }
+            }
+            return Ok(());
+        }
+        let previous_occurrence_index = match self.duplicate_tracker.get_mut(&hash_of_record) {
+            None => {
+                self.duplicate_tracker
+                    .insert(hash_of_record, TrackedRecord::new(record));
+                None
+            }
+            Some(tracked_record) => {
+                let previous_occurrence_index = tracked_record.get_last_index();
+                let heap_bytes_before = tracked_record.get_heap_bytes();
+                tracked_record.add_repeat(record);
+                self.num_tracked_repeat_bytes +=
+                    tracked_record.get_heap_bytes() - heap_bytes_before;
+                Some(previous_occurrence_index)
+            }
+        };
+        let mut counted_as_contiguous = false;
+        if let Some(previous_occurrence_index) = previous_occurrence_index {
             // println!("Found duplicate record at index {}", record.index);
             self.num_dupes += 1;
+            let follows_previous_occurrence = previous_occurrence_index + 1 == record.index;
+            // The record before this one is also a repeat of the record before the previous occurrence, so a block was repeated
+            let continues_repeated_block = self.previous_tracked_record.is_some_and(|previous| {
+                previous.index + 1 == record.index
+                    && previous
+                        .previous_occurrence_index
+                        .is_some_and(|index| index + 1 == previous_occurrence_index)
+            });
+            if continues_repeated_block
+                && let Some(previous) = self.previous_tracked_record
+                && !previous.counted_as_contiguous
+                && let Some(tracked_record) =
+                    self.duplicate_tracker.get_mut(&previous.hash_of_record)
+            {
+                tracked_record.add_contiguous_repeat();
+            }
+            if follows_previous_occurrence || continues_repeated_block {
+                if let Some(tracked_record) = self.duplicate_tracker.get_mut(&hash_of_record) {
+                    tracked_record.add_contiguous_repeat();
+                }
+                counted_as_contiguous = true;
+            }
             if self.execution_settings.actually_write_to_files {
                 match self.write_hit_to_file(record, AlertOutputType::Duplicate, None) {
                     Ok(()) => (),
@@ -120,8 +504,223 @@ impl LogRecordProcessor {
                 }
             }
         }
+        self.previous_tracked_record = Some(PreviousTrackedRecord {
+            hash_of_record,
+            index: record.index,
+            previous_occurrence_index,
+            counted_as_contiguous,
+        });
+        if let DupeDetectionMode::Spill {
+            memory_budget_bytes,
+        } = self.execution_settings.dupe_detection_mode
+            && self.get_estimated_duplicate_tracker_bytes() > memory_budget_bytes
+            && let Some(duplicate_spiller) = self.duplicate_spiller.as_mut()
+        {
+            match duplicate_spiller.spill(&mut self.duplicate_tracker) {
+                Ok(()) => self.num_tracked_repeat_bytes = 0,
+                Err(e) => {
+                    // Keep going in memory rather than lose the counts
+                    self.errors.push(e);
+                    self.duplicate_spiller = None;
+                }
+            }
+        }
         Ok(())
     }
+
+    fn get_estimated_duplicate_tracker_bytes(&self) -> usize {
+        self.duplicate_tracker.len() * ESTIMATED_BYTES_PER_TRACKED_RECORD
+            + self.num_tracked_repeat_bytes
+    }
+
+    /// How duplicates were found, for the output
+    pub fn get_dupe_detection_description(&self) -> String {
+        match self.execution_settings.dupe_detection_mode {
+            DupeDetectionMode::InMemory => "Exact".to_string(),
+            DupeDetectionMode::Spill { .. } if self.num_spilled_duplicate_runs == 0 => {
+                "Exact, within the memory budget".to_string()
+            }
+            DupeDetectionMode::Spill { .. } => format!(
+                "Exact, merged from {} sorted runs spilled to disk",
+                self.num_spilled_duplicate_runs
+            ),
+            DupeDetectionMode::Approximate {
+                false_positive_rate,
+            } => format!(
+                "Approximate, {}% false positive rate",
+                false_positive_rate * 100.0
+            ),
+        }
+    }
+
+    /// Every distinct record hash in the file, for comparing against the other input files
+    pub fn take_record_hashes(&mut self) -> Vec<u128> {
+        self.duplicate_tracker.keys().copied().collect()
+    }
+
+    /// Records that were repeated, in the order they first appeared
+    pub fn get_duplicated_records(&self) -> Vec<(u128, &DuplicateOccurrences)> {
+        let mut duplicated_records: Vec<(u128, &DuplicateOccurrences)> = self
+            .duplicate_tracker
+            .iter()
+            .filter_map(|(hash_of_record, tracked_record)| match tracked_record {
+                TrackedRecord::Repeated(occurrences) => Some((*hash_of_record, &**occurrences)),
+                TrackedRecord::SeenOnce { .. } => None,
+            })
+            .collect();
+        duplicated_records.sort_by_key(|(_, occurrences)| occurrences.first_index);
+        duplicated_records
+    }
+
+    /// Called once the whole file has been streamed to merge any spilled runs and write one row per duplicated record,
+    /// linking repeats back to the first occurrence. Approximate mode doesn't know where the first occurrence was
+    pub fn finish_processing_dupes(&mut self) {
+        if self.approximate_duplicate_filter.is_some() {
+            return;
+        }
+        let duplicated_records: Vec<(u128, DuplicateOccurrences)> =
+            match self.duplicate_spiller.take() {
+                Some(duplicate_spiller) if duplicate_spiller.get_num_runs() > 0 => {
+                    match self.merge_spilled_duplicate_runs(duplicate_spiller) {
+                        Ok(duplicated_records) => duplicated_records,
+                        Err(e) => {
+                            self.errors.push(e);
+                            return;
+                        }
+                    }
+                }
+                _ => self
+                    .get_duplicated_records()
+                    .into_iter()
+                    .map(|(hash_of_record, occurrences)| (hash_of_record, occurrences.clone()))
+                    .collect(),
+            };
+        if self.num_dupes == 0 || !self.execution_settings.actually_write_to_files {
+            return;
+        }
+        if let Err(e) = self.write_duplicate_summary_to_file(&duplicated_records) {
+            self.errors.push(e);
+        }
+    }
+
+    /// Repeats whose earlier occurrence was in an earlier run are only found here, once the records are no longer available.
+    /// They are counted now and written to their own file by index, with the hash and first occurrence to tie them back
+    fn merge_spilled_duplicate_runs(
+        &mut self,
+        mut duplicate_spiller: DuplicateTrackerSpiller,
+    ) -> Result<Vec<(u128, DuplicateOccurrences)>> {
+        duplicate_spiller.spill(&mut self.duplicate_tracker)?;
+        self.num_spilled_duplicate_runs = duplicate_spiller.get_num_runs();
+        let mut duplicated_records: Vec<(u128, DuplicateOccurrences)> = Vec::new();
+        let mut cross_run_repeats: Vec<(usize, u128, usize)> = Vec::new();
+        duplicate_spiller.merge(|hash_of_record, occurrences, cross_run_repeat_indexes| {
+            cross_run_repeats.extend(
+                cross_run_repeat_indexes
+                    .iter()
+                    .map(|index| (*index, hash_of_record, occurrences.first_index)),
+            );
+            if occurrences.num_occurrences > 1 {
+                duplicated_records.push((hash_of_record, occurrences));
+            }
+        })?;
+
+        cross_run_repeats.sort_unstable();
+        self.num_dupes += cross_run_repeats.len();
+        if self.execution_settings.actually_write_to_files
+            && !cross_run_repeats.is_empty()
+            && let Err(e) = self.write_spilled_repeats_to_file(&cross_run_repeats)
+        {
+            self.errors.push(e);
+        }
+        duplicated_records.sort_by_key(|(_, occurrences)| occurrences.first_index);
+        Ok(duplicated_records)
+    }
+
+    fn write_spilled_repeats_to_file(
+        &self,
+        cross_run_repeats: &[(usize, u128, usize)],
+    ) -> Result<()> {
+        let output_file = self
+            .execution_settings
+            .output_dir
+            .join(format!("Duplicates/{}_SPILLED_REPEATS.csv", self.file_name));
+        write_rows_to_file(
+            &output_file,
+            &[
+                "Index of Repeat",
+                "Hash of Record",
+                "Index of First Occurrence",
+            ],
+            cross_run_repeats
+                .iter()
+                .map(|(index, hash_of_record, first_index)| {
+                    [
+                        index.to_string(),
+                        format_record_hash(*hash_of_record),
+                        first_index.to_string(),
+                    ]
+                }),
+        )
+    }
+
+    fn write_duplicate_summary_to_file(
+        &self,
+        duplicated_records: &[(u128, DuplicateOccurrences)],
+    ) -> Result<()> {
+        let output_file = self.execution_settings.output_dir.join(format!(
+            "Duplicates/{}_DUPLICATE_SUMMARY.csv",
+            self.file_name
+        ));
+        write_rows_to_file(
+            &output_file,
+            &[
+                "Hash of Record",
+                "Index of First Occurrence",
+                "Number of Occurrences",
+                "Indexes of Repeats",
+                "First Timestamp",
+                "Last Timestamp",
+                "Time Span of Occurrences (Seconds)",
+                "Repeats Are Contiguous",
+            ],
+            duplicated_records
+                .iter()
+                .map(|(hash_of_record, occurrences)| {
+                    occurrences.get_summary_output_row(*hash_of_record)
+                }),
+        )
+    }
+
+    pub fn process_record_for_near_dupes(&mut self, record: &LogFileRecord) {
+        if let Some(near_dupe_detector) = self.near_dupe_detector.as_mut() {
+            near_dupe_detector.process_record(
+                record.index,
+                record.hash_of_entire_record,
+                &self.data_field_headers,
+                &record.raw_record,
+            );
+        }
+    }
+
+    /// Called once the whole file has been streamed, since the volatile fields are only known after the first records
+    pub fn finish_processing_near_dupes(&mut self) {
+        let Some(near_dupe_detector) = self.near_dupe_detector.as_mut() else {
+            return;
+        };
+        let clusters = near_dupe_detector.finish();
+        self.num_near_dupe_clusters = Some(clusters.len());
+        self.near_dupe_dropped_fields = near_dupe_detector.get_dropped_fields();
+        if self.execution_settings.actually_write_to_files
+            && let Err(e) = write_near_dupe_clusters_to_file(
+                &self.execution_settings.output_dir,
+                &self.file_name,
+                &clusters,
+            )
+        {
+            self.errors.push(e);
+        }
+    }
+
     pub fn process_record_for_redactions(&mut self, record: &LogFileRecord) -> Result<()> {
         for redaction in PREBUILT_REDACTION_REGEXES.iter() {
             if redaction.string_record_contains_match(&record.raw_record) {
@@ -142,6 +741,72 @@ impl LogRecordProcessor {
 
         Ok(())
     }
+    /// EVTX only. Record IDs are assigned sequentially by the event log service, so a jump means records are missing
+    pub fn process_evtx_record(
+        &mut self,
+        record: &LogFileRecord,
+        event_record_id: u64,
+        event_id: Option<u64>,
+    ) -> Result<()> {
+        let mut findings: Vec<String> = Vec::new();
+        if let Some(previous_event_record_id) = self.previous_event_record_id
+            && event_record_id != previous_event_record_id + 1
+        {
+            let finding = format!(
+                "Event record ID jumped from {} to {}",
+                previous_event_record_id, event_record_id
+            );
+            if event_record_id < previous_event_record_id && self.possible_evtx_wrap.is_none() {
+                self.possible_evtx_wrap = Some((record.clone(), finding));
+            } else {
+                self.num_evtx_record_id_gaps += 1;
+                findings.push(finding);
+            }
+        }
+        if self.first_event_record_id.is_none() {
+            self.first_event_record_id = Some(event_record_id);
+        }
+        self.previous_event_record_id = Some(event_record_id);
+
+        if event_id == Some(EVTX_LOG_CLEARED_EVENT_ID) {
+            self.num_evtx_log_cleared_events += 1;
+            findings.push(format!(
+                "Log cleared event ({}) at event record ID {}",
+                EVTX_LOG_CLEARED_EVENT_ID, event_record_id
+            ));
+        }
+
+        if self.execution_settings.actually_write_to_files {
+            for finding in findings {
+                match self.write_hit_to_file(record, AlertOutputType::EvtxFinding, Some(finding)) {
+                    Ok(()) => (),
+                    Err(e) => self.errors.push(e),
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Once a circular log fills up, the oldest chunks are overwritten with new records, so reading in file order the IDs jump back
+    /// from the newest record to the oldest one that is left and then count up to just before the first record read. That jump
+    /// is only counted as a gap if the IDs don't join up like that
+    pub fn finish_processing_evtx_record_ids(&mut self) {
+        let Some((record, finding)) = self.possible_evtx_wrap.take() else {
+            return;
+        };
+        let log_wrapped =
+            self.previous_event_record_id.map(|last_id| last_id + 1) == self.first_event_record_id;
+        if log_wrapped {
+            return;
+        }
+        self.num_evtx_record_id_gaps += 1;
+        if self.execution_settings.actually_write_to_files
+            && let Err(e) =
+                self.write_hit_to_file(&record, AlertOutputType::EvtxFinding, Some(finding))
+        {
+            self.errors.push(e);
+        }
+    }
+
     pub fn write_hit_to_file(
         &mut self,
         record: &LogFileRecord,
@@ -150,6 +815,7 @@ impl LogRecordProcessor {
     ) -> Result<()> {
         let output_file = self.build_file_path(&alert_type)?;
         let file_existed_before = output_file.exists();
+        record_output_file_written(&output_file);
         let file = OpenOptions::new()
             .create(true)
             .append(true)
@@ -197,6 +863,10 @@ impl LogRecordProcessor {
             AlertOutputType::Redaction => {
                 StringRecord::from(vec!["Index of Hit", "Rule Name"]) // Maybe in the future add the name of the rule that hit in the second column
             }
+            AlertOutputType::EvtxFinding => StringRecord::from(vec!["Index of Hit", "Finding"]),
+            AlertOutputType::OutOfOrder => {
+                StringRecord::from(vec!["Index of Hit", "Displacement (Seconds)"])
+            }
         };
 
         full_output_headers.extend(self.data_field_headers.iter());
@@ -211,6 +881,12 @@ impl LogRecordProcessor {
             AlertOutputType::Redaction => {
                 format!("Redactions/{}_POSSIBLE_REDACTIONS.csv", self.file_name)
             }
+            AlertOutputType::EvtxFinding => {
+                format!("EvtxFindings/{}_EVTX_FINDINGS.csv", self.file_name)
+            }
+            AlertOutputType::OutOfOrder => {
+                format!("OutOfOrder/{}_OUT_OF_ORDER.csv", self.file_name)
+            }
         };
 
         Ok(execution_settings
@@ -224,6 +900,13 @@ impl LogRecordProcessor {
         self.min_timestamp = None;
         self.max_timestamp = None;
         self.largest_time_gap = None;
+        self.largest_time_gaps.clear();
+        self.time_gap_sketch = TimeGapSketch::default();
+        self.cadence_detector = CadenceDetector::default();
+        self.num_missed_cadence_intervals = 0;
+        self.activity_profile = ActivityProfile::default();
+        self.volume_series = VolumeSeries::new(self.execution_settings.volume_window);
+        self.reorder_buffer.clear();
         println!(
             "\x1b[91m{} was not sorted on the identified timestamp. Out of order record at index {}\x1b[0m",
             self.file_name, record.index
@@ -237,8 +920,71 @@ impl LogRecordProcessor {
         ));
     }
 
+    fn is_out_of_order(
+        &self,
+        previous_datetime: NaiveDateTime,
+        current_timestamp: NaiveDateTime,
+    ) -> bool {
+        match self.order {
+            Some(TimeDirection::Ascending) => previous_datetime > current_timestamp,
+            Some(TimeDirection::Descending) => previous_datetime < current_timestamp,
+            None => false,
+        }
+    }
+
+    /// When the clocks fall back the same local hour happens twice, and the timestamp was read as whichever occurrence
+    /// fits the direction of the file. If the other occurrence puts the record back in order, that is the right one
+    fn get_other_reading_in_order(
+        &self,
+        previous_datetime: NaiveDateTime,
+        current_timestamp: NaiveDateTime,
+    ) -> Option<NaiveDateTime> {
+        let other_reading = self
+            .execution_settings
+            .assumed_timezone?
+            .get_other_reading_of_repeated_local_time(current_timestamp)?;
+        match self.is_out_of_order(previous_datetime, other_reading) {
+            true => None,
+            false => Some(other_reading),
+        }
+    }
+
+    /// Timestamps are already in UTC so a DST change doesn't leave a gap or an overlap, but note where the local clock jumped
+    fn annotate_dst_transition(
+        &mut self,
+        previous_datetime: NaiveDateTime,
+        current_timestamp: NaiveDateTime,
+        index: usize,
+    ) {
+        let Some(assumed_timezone) = self.execution_settings.assumed_timezone else {
+            return;
+        };
+        let (Some(previous_offset), Some(current_offset)) = (
+            assumed_timezone.get_named_offset_at(previous_datetime),
+            assumed_timezone.get_named_offset_at(current_timestamp),
+        ) else {
+            return;
+        };
+        if previous_offset.0 == current_offset.0 {
+            return;
+        }
+        let (earlier_offset, later_offset) = match self.order {
+            Some(TimeDirection::Descending) => (current_offset, previous_offset),
+            _ => (previous_offset, current_offset),
+        };
+        let clock_change =
+            match later_offset.0.local_minus_utc() > earlier_offset.0.local_minus_utc() {
+                true => "sprang forward",
+                false => "fell back",
+            };
+        self.dst_transitions.push(format!(
+            "Clocks {} from {} to {} at index {}",
+            clock_change, earlier_offset.1, later_offset.1, index
+        ));
+    }
+
     pub fn process_timestamp(&mut self, record: &LogFileRecord) -> Result<()> {
-        let current_timestamp = match record.timestamp {
+        let mut current_timestamp = match record.timestamp {
             Some(timestamp) => timestamp,
             None => {
                 self.errors.push(LavaError::new(
@@ -255,40 +1001,341 @@ impl LogRecordProcessor {
 
         if let Some(previous_datetime) = self.previous_timestamp {
             // This is where all logic is done if it isn't the first record
-            if self.order == Some(TimeDirection::Ascending) {
-                if previous_datetime > current_timestamp {
-                    self.handle_first_out_of_order_timestamp(record);
-                    return Ok(());
+            if self.is_out_of_order(previous_datetime, current_timestamp) {
+                match self.get_other_reading_in_order(previous_datetime, current_timestamp) {
+                    Some(other_reading) => current_timestamp = other_reading,
+                    None => {
+                        if !self.try_to_tolerate_out_of_order_timestamp(
+                            record,
+                            previous_datetime,
+                            current_timestamp,
+                        ) {
+                            self.handle_first_out_of_order_timestamp(record);
+                        }
+                        return Ok(());
+                    }
                 }
-                self.max_timestamp = Some(current_timestamp)
-            } else if self.order == Some(TimeDirection::Descending) {
-                if previous_datetime < current_timestamp {
-                    self.handle_first_out_of_order_timestamp(record);
-                    return Ok(());
+            }
+            self.annotate_dst_transition(previous_datetime, current_timestamp, record.index);
+        }
+        self.previous_timestamp = Some(current_timestamp);
+        self.add_timestamp_in_time_order(current_timestamp, record.index);
+        Ok(())
+    }
+
+    /// Tolerant mode only. A record within the jitter window of the latest timestamp is counted and listed, and still used for stats
+    fn try_to_tolerate_out_of_order_timestamp(
+        &mut self,
+        record: &LogFileRecord,
+        latest_timestamp: NaiveDateTime,
+        current_timestamp: NaiveDateTime,
+    ) -> bool {
+        let Some(tolerance) = self.execution_settings.out_of_order_tolerance else {
+            return false;
+        };
+        let displacement = (latest_timestamp - current_timestamp).abs();
+        if displacement > tolerance.jitter_window
+            || self.num_out_of_order_records >= tolerance.max_out_of_order_records
+        {
+            return false;
+        }
+        self.num_out_of_order_records += 1;
+        self.largest_out_of_order_displacement = self
+            .largest_out_of_order_displacement
+            .max(Some(displacement));
+        if self.execution_settings.actually_write_to_files
+            && let Err(e) = self.write_hit_to_file(
+                record,
+                AlertOutputType::OutOfOrder,
+                Some(get_seconds_string_from_time_delta(displacement)),
+            )
+        {
+            self.errors.push(e);
+        }
+        self.add_timestamp_in_time_order(current_timestamp, record.index);
+        true
+    }
+
+    fn add_timestamp_in_time_order(&mut self, current_timestamp: NaiveDateTime, index: usize) {
+        self.volume_series.add_timestamp(current_timestamp);
+        self.activity_profile
+            .add_timestamp(match self.execution_settings.assumed_timezone {
+                Some(assumed_timezone) => assumed_timezone.convert_utc_to_local(current_timestamp),
+                None => current_timestamp,
+            });
+        self.min_timestamp = Some(match self.min_timestamp {
+            Some(min_timestamp) => min_timestamp.min(current_timestamp),
+            None => current_timestamp,
+        });
+        self.max_timestamp = Some(match self.max_timestamp {
+            Some(max_timestamp) => max_timestamp.max(current_timestamp),
+            None => current_timestamp,
+        });
+        match self.execution_settings.out_of_order_tolerance {
+            None => self.add_time_gap(current_timestamp, index),
+            Some(tolerance) => {
+                self.reorder_buffer
+                    .entry(current_timestamp)
+                    .or_default()
+                    .push(index);
+                self.release_timestamps_from_reorder_buffer(Some(tolerance.jitter_window));
+            }
+        }
+    }
+
+    /// Tolerant mode holds timestamps back until nothing within the jitter window could still come before them,
+    /// so time gaps are measured in true time order. Passing no jitter window releases everything
+    fn release_timestamps_from_reorder_buffer(&mut self, jitter_window: Option<TimeDelta>) {
+        loop {
+            let next_in_time_order = match self.order {
+                Some(TimeDirection::Descending) => self.reorder_buffer.last_entry(),
+                _ => self.reorder_buffer.first_entry(),
+            };
+            let Some(mut next_entry) = next_in_time_order else {
+                break;
+            };
+            let timestamp = *next_entry.key();
+            if let (Some(jitter_window), Some(latest_timestamp)) =
+                (jitter_window, self.previous_timestamp)
+            {
+                let could_still_be_preceded = match self.order {
+                    Some(TimeDirection::Descending) => timestamp < latest_timestamp + jitter_window,
+                    _ => timestamp > latest_timestamp - jitter_window,
+                };
+                if could_still_be_preceded {
+                    break;
                 }
-                self.min_timestamp = Some(current_timestamp)
             }
+            let index = next_entry.get_mut().remove(0);
+            if next_entry.get().is_empty() {
+                next_entry.remove();
+            }
+            self.add_time_gap(timestamp, index);
+        }
+    }
+
+    fn add_time_gap(&mut self, current_timestamp: NaiveDateTime, index: usize) {
+        if let Some((previous_datetime, previous_index)) = self.previous_timestamp_in_time_order {
             let current_time_gap = TimeGap::new(previous_datetime, current_timestamp);
             self.welford_calculator
                 .push(current_time_gap.get_time_duration_number() as i128);
+            self.time_gap_sketch.add(&current_time_gap);
             if let Some(largest_time_gap) = self.largest_time_gap {
                 if current_time_gap > largest_time_gap {
-                    self.largest_time_gap =
-                        Some(TimeGap::new(previous_datetime, current_timestamp));
+                    self.largest_time_gap = Some(current_time_gap);
                 }
             } else {
                 // This is the second row, intialize the time gap
-                self.largest_time_gap = Some(TimeGap::new(previous_datetime, current_timestamp));
+                self.largest_time_gap = Some(current_time_gap);
             }
-        } else {
-            // This is the first row, inialize both min and max to this value
-            self.min_timestamp = Some(current_timestamp);
-            self.max_timestamp = Some(current_timestamp);
+            let (beginning_index, end_index) = match previous_datetime <= current_timestamp {
+                true => (previous_index, index),
+                false => (index, previous_index),
+            };
+            let indexed_time_gap = IndexedTimeGap {
+                time_gap: current_time_gap,
+                beginning_index,
+                end_index,
+            };
+            self.add_to_largest_time_gaps(indexed_time_gap);
+            let missed_cadence_intervals = self.cadence_detector.process_time_gap(indexed_time_gap);
+            self.add_missed_cadence_intervals(missed_cadence_intervals);
         }
-        self.previous_timestamp = Some(current_timestamp);
+        self.previous_timestamp_in_time_order = Some((current_timestamp, index));
+    }
+
+    fn add_to_largest_time_gaps(&mut self, indexed_time_gap: IndexedTimeGap) {
+        let num_top_time_gaps = self.execution_settings.num_top_time_gaps;
+        if num_top_time_gaps == 0 {
+            return;
+        }
+        if self.largest_time_gaps.len() < num_top_time_gaps {
+            self.largest_time_gaps.push(Reverse(indexed_time_gap));
+        } else if let Some(Reverse(smallest_kept)) = self.largest_time_gaps.peek()
+            && indexed_time_gap.time_gap > smallest_kept.time_gap
+        {
+            self.largest_time_gaps.pop();
+            self.largest_time_gaps.push(Reverse(indexed_time_gap));
+        }
+    }
+
+    fn add_missed_cadence_intervals(
+        &mut self,
+        missed_cadence_intervals: Vec<MissedCadenceInterval>,
+    ) {
+        for missed_cadence_interval in missed_cadence_intervals {
+            self.num_missed_cadence_intervals += missed_cadence_interval.num_missed_intervals;
+            if self.execution_settings.actually_write_to_files
+                && let Err(e) = self.write_missed_cadence_interval_to_file(&missed_cadence_interval)
+            {
+                self.errors.push(e);
+            }
+        }
+    }
+
+    fn write_missed_cadence_interval_to_file(
+        &self,
+        missed_cadence_interval: &MissedCadenceInterval,
+    ) -> Result<()> {
+        let output_file = self.execution_settings.output_dir.join(format!(
+            "MissedIntervals/{}_MISSED_INTERVALS.csv",
+            self.file_name
+        ));
+        let file_existed_before = output_file.exists();
+        record_output_file_written(&output_file);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(output_file)
+            .map_err(|e| {
+                LavaError::new(
+                    format!("Unable to create output file because of {e}"),
+                    LavaErrorLevel::Medium,
+                )
+            })?;
+        let mut writer = WriterBuilder::new().has_headers(false).from_writer(file);
+        if !file_existed_before {
+            writer
+                .write_record([
+                    "Beginning Time",
+                    "End Time",
+                    "Index of Record at Beginning",
+                    "Index of Record at End",
+                    "Duration of Gap (Seconds)",
+                    "Missed Intervals",
+                ])
+                .map_err(|e| {
+                    LavaError::new(
+                        format!("Unable to write headers to file because of {e}"),
+                        LavaErrorLevel::Medium,
+                    )
+                })?;
+        }
+        writer
+            .write_record(&missed_cadence_interval.get_output_row())
+            .map_err(|e| {
+                LavaError::new(
+                    format!("Unable to write record because of {e}"),
+                    LavaErrorLevel::Medium,
+                )
+            })?;
         Ok(())
     }
 
+    /// Largest first. The mean and standard deviation are only final once every timestamp is in, so this is done at the end
+    pub fn get_largest_time_gaps(&self) -> Vec<IndexedTimeGap> {
+        let mut largest_time_gaps: Vec<IndexedTimeGap> = self
+            .largest_time_gaps
+            .iter()
+            .map(|Reverse(indexed_time_gap)| *indexed_time_gap)
+            .collect();
+        largest_time_gaps.sort_by(|a, b| b.cmp(a));
+        largest_time_gaps
+    }
+
+    fn write_largest_time_gaps_to_file(&self, largest_time_gaps: &[IndexedTimeGap]) -> Result<()> {
+        let (mean, variance) = self.get_mean_and_variance();
+        let output_file = self
+            .execution_settings
+            .output_dir
+            .join(format!("Gaps/{}_GAPS.csv", self.file_name));
+        let minimum_time_gap_above_threshold = self
+            .get_time_gap_alert_baseline()
+            .get_minimum_time_gap_above_threshold(&self.alert_rules);
+        let rows: Vec<StringRecord> = largest_time_gaps
+            .iter()
+            .enumerate()
+            .map(|(rank, indexed_time_gap)| {
+                let above_alert_threshold =
+                    minimum_time_gap_above_threshold.is_some_and(|minimum| {
+                        indexed_time_gap.time_gap.get_time_duration_number() as f64 >= minimum
+                    });
+                indexed_time_gap.get_output_row(
+                    rank + 1,
+                    mean,
+                    variance.sqrt(),
+                    above_alert_threshold,
+                )
+            })
+            .collect();
+        write_rows_to_file(
+            &output_file,
+            &[
+                "Rank",
+                "Beginning Time",
+                "End Time",
+                "Index of Record at Beginning",
+                "Index of Record at End",
+                "Duration of Gap (Hours)",
+                "Pretty Duration of Gap",
+                "Number of Standard Deviations Above the Mean",
+                "Above Alert Threshold",
+            ],
+            &rows,
+        )
+    }
+
+    /// Called once the whole file has been streamed, to use the timestamps still held back in tolerant mode
+    pub fn finish_processing_timestamps(&mut self) {
+        if !self.process_timestamps {
+            return;
+        }
+        self.release_timestamps_from_reorder_buffer(None);
+        let missed_cadence_intervals = self.cadence_detector.finish();
+        self.add_missed_cadence_intervals(missed_cadence_intervals);
+
+        let largest_time_gaps = self.get_largest_time_gaps();
+        // The threshold isn't known until the mean, std, and percentiles are, so the gaps are counted from the sketch, which is approximate
+        self.approx_num_time_gaps_above_threshold = self
+            .get_time_gap_alert_baseline()
+            .get_minimum_time_gap_above_threshold(&self.alert_rules)
+            .map(|minimum| self.time_gap_sketch.count_at_least(minimum))
+            .unwrap_or(0);
+        self.quiet_activity_periods = self.activity_profile.get_quiet_periods();
+        let volume_window_counts = self.volume_series.get_window_counts();
+        for window_count in volume_window_counts.iter() {
+            match window_count.anomaly {
+                Some(VolumeAnomaly::Drop) => self.num_volume_drops += 1,
+                Some(VolumeAnomaly::Spike) => self.num_volume_spikes += 1,
+                None => (),
+            }
+        }
+        if self.execution_settings.actually_write_to_files
+            && let Err(e) = self.volume_series.write_to_file(
+                &self.execution_settings.output_dir,
+                &self.file_name,
+                &volume_window_counts,
+            )
+        {
+            self.errors.push(e);
+        }
+        if self.execution_settings.actually_write_to_files
+            && let Err(e) = self.activity_profile.write_to_files(
+                &self.execution_settings.output_dir,
+                &self.file_name,
+                &self.quiet_activity_periods,
+            )
+        {
+            self.errors.push(e);
+        }
+        if self.execution_settings.actually_write_to_files
+            && !largest_time_gaps.is_empty()
+            && let Err(e) = self.write_largest_time_gaps_to_file(&largest_time_gaps)
+        {
+            self.errors.push(e);
+        }
+    }
+
+    pub fn get_time_gap_alert_baseline(&self) -> TimeGapAlertBaseline {
+        let (mean, variance) = self.get_mean_and_variance();
+        TimeGapAlertBaseline {
+            method: self.execution_settings.time_gap_alert_method,
+            mean,
+            std: variance.sqrt(),
+            percentile_99: self.time_gap_sketch.get_percentile(99.0),
+        }
+    }
+
     pub fn get_mean_and_variance(&self) -> (f64, f64) {
         let mean = match self.welford_calculator.mean() {
             Some(real_mean) => real_mean as f64,
@@ -307,6 +1354,18 @@ impl LogRecordProcessor {
             num_records: self.timestamp_num_records,
             num_dupes: self.num_dupes,
             num_redactions: self.num_redactions,
+            num_evtx_record_id_gaps: self.num_evtx_record_id_gaps,
+            num_evtx_log_cleared_events: self.num_evtx_log_cleared_events,
+            num_out_of_order_records: self.num_out_of_order_records,
+            largest_out_of_order_displacement: self.largest_out_of_order_displacement,
+            num_missed_cadence_intervals: self.num_missed_cadence_intervals,
+            expected_cadence: self.cadence_detector.cadence,
+            num_quiet_activity_periods: self.quiet_activity_periods.len(),
+            num_volume_drops: self.num_volume_drops,
+            num_volume_spikes: self.num_volume_spikes,
+            percentile_99_time_gap: self.time_gap_sketch.get_percentile(99.0),
+            time_gap_alert_method: self.execution_settings.time_gap_alert_method,
+            alert_rules: self.alert_rules.clone(),
             largest_time_gap: self.largest_time_gap,
             errors: self.errors.clone(),
             mean: mean,
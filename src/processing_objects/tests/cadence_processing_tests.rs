@@ -0,0 +1,101 @@
+use super::super::*;
+use crate::basic_objects::{ExecutionSettings, TimeDirection};
+use crate::test_helpers::*;
+use chrono::TimeDelta;
+use csv::StringRecord;
+
+fn process_times(processor: &mut LogRecordProcessor, times: &[NaiveDateTime]) {
+    for (index, time) in times.iter().enumerate() {
+        processor
+            .process_timestamp(&LogFileRecord::new(
+                index,
+                Some(*time),
+                StringRecord::from(vec!["test"]),
+            ))
+            .unwrap();
+    }
+    processor.finish_processing_timestamps();
+}
+
+fn new_ascending_processor() -> LogRecordProcessor {
+    LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &ExecutionSettings::default(),
+        "Test".to_string(),
+        None,
+    )
+}
+
+/// One record every minute, with a little jitter, skipping the minutes given
+fn heartbeat_times(num_minutes: i64, skipped_minutes: &[i64]) -> Vec<NaiveDateTime> {
+    let start = dt("2024-05-01 00:00:00");
+    (0..num_minutes)
+        .filter(|minute| !skipped_minutes.contains(minute))
+        .map(|minute| start + TimeDelta::minutes(minute) + TimeDelta::seconds(minute % 3))
+        .collect()
+}
+
+fn assert_cadence_is_about_a_minute(processor: &LogRecordProcessor) {
+    let cadence = processor.cadence_detector.cadence.unwrap();
+    assert!((cadence - TimeDelta::minutes(1)).abs() <= TimeDelta::seconds(2));
+}
+
+#[test]
+fn finds_missed_intervals_in_a_heartbeat_log() {
+    let mut processor = new_ascending_processor();
+    process_times(&mut processor, &heartbeat_times(200, &[10, 11, 12, 150]));
+
+    assert_cadence_is_about_a_minute(&processor);
+    assert_eq!(processor.num_missed_cadence_intervals, 4);
+}
+
+#[test]
+fn missed_intervals_in_the_sample_are_found_once_the_cadence_is_known() {
+    let mut processor = new_ascending_processor();
+    process_times(&mut processor, &heartbeat_times(30, &[5]));
+
+    assert_cadence_is_about_a_minute(&processor);
+    assert_eq!(processor.num_missed_cadence_intervals, 1);
+}
+
+#[test]
+fn irregular_log_has_no_cadence() {
+    let mut processor = new_ascending_processor();
+    let start = dt("2024-05-01 00:00:00");
+    let times: Vec<NaiveDateTime> = (0..200)
+        .map(|i| start + TimeDelta::seconds(i * i % 97 + i * 60))
+        .collect();
+    process_times(&mut processor, &times);
+
+    assert_eq!(processor.cadence_detector.cadence, None);
+    assert_eq!(processor.num_missed_cadence_intervals, 0);
+}
+
+#[test]
+fn too_few_gaps_to_estimate_a_cadence() {
+    let mut processor = new_ascending_processor();
+    process_times(&mut processor, &heartbeat_times(5, &[2]));
+
+    assert_eq!(processor.cadence_detector.cadence, None);
+    assert_eq!(processor.num_missed_cadence_intervals, 0);
+}
+
+#[test]
+fn finds_missed_intervals_with_a_cadence_under_a_millisecond() {
+    let mut processor = new_ascending_processor();
+    let start = dt("2024-05-01 00:00:00");
+    let mut times: Vec<NaiveDateTime> = (0..200)
+        .map(|i| start + TimeDelta::microseconds(i * 500))
+        .collect();
+    let last = *times.last().unwrap();
+    times.push(last + TimeDelta::microseconds(700));
+    times.push(last + TimeDelta::microseconds(2700));
+    process_times(&mut processor, &times);
+
+    assert_eq!(
+        processor.cadence_detector.cadence,
+        Some(TimeDelta::microseconds(500))
+    );
+    // The 700 microsecond gap is close enough to on time, and the 2 millisecond gap after it misses 3 records
+    assert_eq!(processor.num_missed_cadence_intervals, 3);
+}
@@ -0,0 +1,144 @@
+use super::super::*;
+use crate::basic_objects::{AssumedTimezone, ExecutionSettings, TimeDirection, TimeGap};
+use crate::test_helpers::*;
+use csv::StringRecord;
+
+fn new_york_settings() -> ExecutionSettings {
+    ExecutionSettings {
+        assumed_timezone: AssumedTimezone::parse("America/New_York"),
+        ..Default::default()
+    }
+}
+
+/// Converts local New York times the same way the handlers do before they reach the processor
+fn process_local_times(
+    processor: &mut LogRecordProcessor,
+    local_times: &[&str],
+    prefer_later: bool,
+) {
+    let new_york = AssumedTimezone::parse("America/New_York").unwrap();
+    for (index, local_time) in local_times.iter().enumerate() {
+        let utc_time = new_york.convert_local_to_utc(dt(local_time), prefer_later);
+        processor
+            .process_timestamp(&LogFileRecord::new(
+                index,
+                utc_time,
+                StringRecord::from(vec!["test"]),
+            ))
+            .unwrap();
+    }
+}
+
+#[test]
+fn spring_forward_is_annotated_and_not_a_gap() {
+    let settings = new_york_settings();
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &settings,
+        "Test".to_string(),
+        None,
+    );
+    process_local_times(
+        &mut processor,
+        &[
+            "2024-03-10 01:30:00",
+            "2024-03-10 01:59:00",
+            "2024-03-10 03:00:00",
+            "2024-03-10 03:30:00",
+        ],
+        false,
+    );
+
+    assert!(processor.errors.is_empty());
+    assert_eq!(
+        processor.dst_transitions,
+        vec!["Clocks sprang forward from EST to EDT at index 2".to_string()]
+    );
+    assert_eq!(
+        processor.largest_time_gap.unwrap(),
+        TimeGap::new(dt("2024-03-10 07:00:00"), dt("2024-03-10 07:30:00"))
+    );
+}
+
+#[test]
+fn fall_back_repeated_hour_is_not_out_of_order() {
+    let settings = new_york_settings();
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &settings,
+        "Test".to_string(),
+        None,
+    );
+    process_local_times(
+        &mut processor,
+        &[
+            "2024-11-03 00:50:00",
+            "2024-11-03 01:30:00",
+            "2024-11-03 01:55:00",
+            "2024-11-03 01:10:00",
+            "2024-11-03 01:40:00",
+            "2024-11-03 02:05:00",
+        ],
+        false,
+    );
+
+    assert!(processor.errors.is_empty());
+    assert_eq!(processor.timestamp_num_records, 6);
+    assert_eq!(
+        processor.dst_transitions,
+        vec!["Clocks fell back from EDT to EST at index 3".to_string()]
+    );
+    assert_eq!(processor.min_timestamp, Some(dt("2024-11-03 04:50:00")));
+    assert_eq!(processor.max_timestamp, Some(dt("2024-11-03 07:05:00")));
+}
+
+#[test]
+fn fall_back_repeated_hour_in_descending_file() {
+    let settings = new_york_settings();
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Descending)),
+        &settings,
+        "Test".to_string(),
+        None,
+    );
+    process_local_times(
+        &mut processor,
+        &[
+            "2024-11-03 02:05:00",
+            "2024-11-03 01:40:00",
+            "2024-11-03 01:10:00",
+            "2024-11-03 01:55:00",
+            "2024-11-03 01:30:00",
+            "2024-11-03 00:50:00",
+        ],
+        true,
+    );
+
+    assert!(processor.errors.is_empty());
+    assert_eq!(
+        processor.dst_transitions,
+        vec!["Clocks fell back from EDT to EST at index 3".to_string()]
+    );
+    assert_eq!(processor.min_timestamp, Some(dt("2024-11-03 04:50:00")));
+    assert_eq!(processor.max_timestamp, Some(dt("2024-11-03 07:05:00")));
+}
+
+#[test]
+fn out_of_order_outside_of_dst_transition_still_stops_time_processing() {
+    let settings = new_york_settings();
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &settings,
+        "Test".to_string(),
+        None,
+    );
+    process_local_times(
+        &mut processor,
+        &["2024-11-05 01:30:00", "2024-11-05 01:10:00"],
+        false,
+    );
+
+    assert_eq!(processor.errors.len(), 1);
+    assert!(processor.dst_transitions.is_empty());
+    assert_eq!(processor.min_timestamp, None);
+}
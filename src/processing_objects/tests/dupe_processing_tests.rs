@@ -1,5 +1,6 @@
 use super::super::*;
 use crate::basic_objects::{ExecutionSettings, TimeDirection};
+use crate::helpers::hash_csv_record;
 use crate::test_helpers::*;
 use csv::StringRecord;
 // Test when the record is not a duplicate
@@ -19,7 +20,7 @@ fn test_process_record_no_duplicate() {
         StringRecord::from(vec!["test"]),
     );
     let _ = processor.process_record_for_dupes(&record);
-    assert_eq!(processor.duplicate_checker_set.len(), 1);
+    assert_eq!(processor.duplicate_tracker.len(), 1);
     assert_eq!(processor.num_dupes, 0);
 }
 
@@ -52,7 +53,7 @@ fn test_process_record_with_one_duplicate() {
     let _ = processor.process_record_for_dupes(&record2);
     let _ = processor.process_record_for_dupes(&record3);
 
-    assert_eq!(processor.duplicate_checker_set.len(), 2);
+    assert_eq!(processor.duplicate_tracker.len(), 2);
     assert_eq!(processor.num_dupes, 1);
 }
 
@@ -85,7 +86,7 @@ fn test_process_record_with_two_duplicate() {
     let _ = processor.process_record_for_dupes(&record2);
     let _ = processor.process_record_for_dupes(&record3);
 
-    assert_eq!(processor.duplicate_checker_set.len(), 1);
+    assert_eq!(processor.duplicate_tracker.len(), 1);
     assert_eq!(processor.num_dupes, 2);
 }
 
@@ -118,7 +119,7 @@ fn test_process_record_with_no_dupe_multiple_values() {
     let _ = processor.process_record_for_dupes(&record2);
     let _ = processor.process_record_for_dupes(&record3);
 
-    assert_eq!(processor.duplicate_checker_set.len(), 3);
+    assert_eq!(processor.duplicate_tracker.len(), 3);
     assert_eq!(processor.num_dupes, 0);
 }
 
@@ -151,6 +152,84 @@ fn test_process_record_with_dupe_multiple_values() {
     let _ = processor.process_record_for_dupes(&record2);
     let _ = processor.process_record_for_dupes(&record3);
 
-    assert_eq!(processor.duplicate_checker_set.len(), 2);
+    assert_eq!(processor.duplicate_tracker.len(), 2);
     assert_eq!(processor.num_dupes, 1);
 }
+
+fn process_records_for_dupes(values: Vec<&str>) -> LogRecordProcessor {
+    let settings = ExecutionSettings::default();
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &settings,
+        "Test".to_string(),
+        None,
+    );
+    for (index, value) in values.into_iter().enumerate() {
+        let timestamp = format!("2024-05-01 14:00:{:02}", index);
+        let record = make_fake_record(index, Some(&timestamp), StringRecord::from(vec![value]));
+        let _ = processor.process_record_for_dupes(&record);
+    }
+    processor
+}
+
+#[test]
+fn test_duplicate_tracker_links_repeats_to_first_occurrence() {
+    let processor = process_records_for_dupes(vec!["a", "b", "a", "c", "b", "a"]);
+    let duplicated_records = processor.get_duplicated_records();
+
+    assert_eq!(processor.num_dupes, 3);
+    assert_eq!(duplicated_records.len(), 2);
+    let (hash_of_record, first_duplicate) = duplicated_records[0];
+    assert_eq!(first_duplicate.first_index, 0);
+    assert_eq!(first_duplicate.num_occurrences, 3);
+    assert_eq!(first_duplicate.repeat_indexes, vec![2, 5]);
+    assert!(!first_duplicate.repeats_are_contiguous());
+    let output_row = first_duplicate.get_summary_output_row(hash_of_record);
+    assert_eq!(output_row[3], "2;5");
+    assert_eq!(output_row[6], "5.000");
+    assert_eq!(output_row[7], "No");
+    assert_eq!(duplicated_records[1].1.first_index, 1);
+}
+
+#[test]
+fn test_duplicate_tracker_finds_contiguous_runs() {
+    let processor = process_records_for_dupes(vec!["a", "a", "a", "b"]);
+    let duplicated_records = processor.get_duplicated_records();
+
+    assert_eq!(duplicated_records.len(), 1);
+    assert_eq!(duplicated_records[0].1.num_contiguous_repeats, 2);
+    assert!(duplicated_records[0].1.repeats_are_contiguous());
+}
+
+#[test]
+fn test_duplicate_tracker_finds_repeated_blocks() {
+    let processor = process_records_for_dupes(vec!["x", "a", "b", "c", "a", "b", "c", "y"]);
+    let duplicated_records = processor.get_duplicated_records();
+
+    assert_eq!(duplicated_records.len(), 3);
+    assert!(
+        duplicated_records
+            .iter()
+            .all(|(_, occurrences)| occurrences.repeats_are_contiguous())
+    );
+}
+
+#[test]
+fn test_duplicate_tracker_only_builds_occurrences_for_repeats() {
+    let processor = process_records_for_dupes(vec!["a", "b", "a"]);
+
+    let hash_of_b = hash_csv_record(&StringRecord::from(vec!["b"]));
+    assert_eq!(
+        processor.duplicate_tracker[&hash_of_b],
+        TrackedRecord::SeenOnce {
+            index: 1,
+            timestamp: Some(dt("2024-05-01 14:00:01")),
+        }
+    );
+    let hash_of_a = hash_csv_record(&StringRecord::from(vec!["a"]));
+    assert!(matches!(
+        processor.duplicate_tracker[&hash_of_a],
+        TrackedRecord::Repeated(_)
+    ));
+    assert!(size_of::<TrackedRecord>() < size_of::<DuplicateOccurrences>());
+}
@@ -0,0 +1,65 @@
+use super::super::*;
+use crate::basic_objects::ExecutionSettings;
+use crate::test_helpers::*;
+use csv::StringRecord;
+
+fn process_evtx_records(records: Vec<(u64, u64)>) -> LogRecordProcessor {
+    let settings = ExecutionSettings::default();
+    let mut processor = LogRecordProcessor::new(&None, &settings, "Test".to_string(), None);
+    for (index, (event_record_id, event_id)) in records.into_iter().enumerate() {
+        let record = make_fake_record(
+            index,
+            Some("2024-05-01 14:00:00"),
+            StringRecord::from(vec![format!("record {}", event_record_id)]),
+        );
+        let _ = processor.process_evtx_record(&record, event_record_id, Some(event_id));
+    }
+    processor.finish_processing_evtx_record_ids();
+    processor
+}
+
+#[test]
+fn test_sequential_record_ids_have_no_gaps() {
+    let processor = process_evtx_records(vec![(100, 4624), (101, 4624), (102, 4634)]);
+    assert_eq!(processor.num_evtx_record_id_gaps, 0);
+    assert_eq!(processor.num_evtx_log_cleared_events, 0);
+}
+
+#[test]
+fn test_record_id_gaps_are_counted() {
+    let processor = process_evtx_records(vec![(100, 4624), (105, 4624), (106, 4624), (90, 4624)]);
+    assert_eq!(processor.num_evtx_record_id_gaps, 2);
+}
+
+#[test]
+fn test_log_cleared_event_is_counted() {
+    let processor = process_evtx_records(vec![(1, 1102), (2, 4624)]);
+    assert_eq!(processor.num_evtx_log_cleared_events, 1);
+    assert_eq!(processor.num_evtx_record_id_gaps, 0);
+}
+
+#[test]
+fn test_wrapped_circular_log_has_no_gaps() {
+    // The newest chunk overwrote the oldest one at the start of the file
+    let processor = process_evtx_records(vec![
+        (1000, 4624),
+        (1001, 4624),
+        (200, 4624),
+        (201, 4624),
+        (999, 4624),
+    ]);
+    assert_eq!(processor.num_evtx_record_id_gaps, 1); // 201 to 999 is still a gap
+
+    let processor = process_evtx_records(vec![
+        (1000, 4624),
+        (1001, 4624),
+        (200, 4624),
+        (201, 4624),
+        (202, 4624),
+    ]);
+    assert_eq!(processor.num_evtx_record_id_gaps, 1); // Doesn't join back up to 1000, so the jump back is a gap
+
+    let processor =
+        process_evtx_records(vec![(1000, 4624), (1001, 4624), (998, 4624), (999, 4624)]);
+    assert_eq!(processor.num_evtx_record_id_gaps, 0);
+}
@@ -0,0 +1,140 @@
+use super::super::*;
+use crate::basic_objects::{ExecutionSettings, OutOfOrderTolerance, TimeDirection, TimeGap};
+use crate::test_helpers::*;
+use chrono::TimeDelta;
+use csv::StringRecord;
+
+fn tolerant_settings(jitter_seconds: i64, max_out_of_order_records: usize) -> ExecutionSettings {
+    ExecutionSettings {
+        out_of_order_tolerance: Some(OutOfOrderTolerance {
+            jitter_window: TimeDelta::seconds(jitter_seconds),
+            max_out_of_order_records,
+        }),
+        ..Default::default()
+    }
+}
+
+fn process_times(processor: &mut LogRecordProcessor, times: &[&str]) {
+    for (index, time) in times.iter().enumerate() {
+        processor
+            .process_timestamp(&make_fake_record(
+                index,
+                Some(time),
+                StringRecord::from(vec!["test"]),
+            ))
+            .unwrap();
+    }
+    processor.finish_processing_timestamps();
+}
+
+#[test]
+fn tolerates_jitter_and_measures_gaps_in_time_order() {
+    let settings = tolerant_settings(30, 10);
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &settings,
+        "Test".to_string(),
+        None,
+    );
+    process_times(
+        &mut processor,
+        &[
+            "2024-05-01 12:00:00",
+            "2024-05-01 12:00:10",
+            "2024-05-01 12:00:04",
+            "2024-05-01 12:00:20",
+        ],
+    );
+
+    assert!(processor.errors.is_empty());
+    assert_eq!(processor.timestamp_num_records, 4);
+    assert_eq!(processor.num_out_of_order_records, 1);
+    assert_eq!(
+        processor.largest_out_of_order_displacement,
+        Some(TimeDelta::seconds(6))
+    );
+    assert_eq!(processor.min_timestamp, Some(dt("2024-05-01 12:00:00")));
+    assert_eq!(processor.max_timestamp, Some(dt("2024-05-01 12:00:20")));
+    assert_eq!(
+        processor.largest_time_gap.unwrap(),
+        TimeGap::new(dt("2024-05-01 12:00:10"), dt("2024-05-01 12:00:20"))
+    );
+    assert_eq!(processor.welford_calculator.mean(), Some(20000 / 3));
+}
+
+#[test]
+fn tolerates_jitter_in_descending_file() {
+    let settings = tolerant_settings(30, 10);
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Descending)),
+        &settings,
+        "Test".to_string(),
+        None,
+    );
+    process_times(
+        &mut processor,
+        &[
+            "2024-05-01 12:00:20",
+            "2024-05-01 12:00:04",
+            "2024-05-01 12:00:10",
+            "2024-05-01 12:00:00",
+        ],
+    );
+
+    assert!(processor.errors.is_empty());
+    assert_eq!(processor.num_out_of_order_records, 1);
+    assert_eq!(processor.min_timestamp, Some(dt("2024-05-01 12:00:00")));
+    assert_eq!(processor.max_timestamp, Some(dt("2024-05-01 12:00:20")));
+    assert_eq!(
+        processor.largest_time_gap.unwrap(),
+        TimeGap::new(dt("2024-05-01 12:00:10"), dt("2024-05-01 12:00:20"))
+    );
+}
+
+#[test]
+fn record_outside_of_jitter_window_stops_time_processing() {
+    let settings = tolerant_settings(30, 10);
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &settings,
+        "Test".to_string(),
+        None,
+    );
+    process_times(
+        &mut processor,
+        &[
+            "2024-05-01 12:00:00",
+            "2024-05-01 12:05:00",
+            "2024-05-01 12:01:00",
+        ],
+    );
+
+    assert_eq!(processor.errors.len(), 1);
+    assert_eq!(processor.timestamp_num_records, 0);
+    assert_eq!(processor.min_timestamp, None);
+    assert_eq!(processor.largest_time_gap, None);
+}
+
+#[test]
+fn too_many_out_of_order_records_stops_time_processing() {
+    let settings = tolerant_settings(30, 1);
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &settings,
+        "Test".to_string(),
+        None,
+    );
+    process_times(
+        &mut processor,
+        &[
+            "2024-05-01 12:00:10",
+            "2024-05-01 12:00:05",
+            "2024-05-01 12:00:20",
+            "2024-05-01 12:00:15",
+        ],
+    );
+
+    assert_eq!(processor.num_out_of_order_records, 1);
+    assert_eq!(processor.errors.len(), 1);
+    assert_eq!(processor.min_timestamp, None);
+}
@@ -0,0 +1,250 @@
+use super::super::*;
+use crate::basic_objects::{
+    ExecutionSettings, IndexedTimeGap, OutOfOrderTolerance, TimeDirection, TimeGap,
+    TimeGapAlertMethod,
+};
+use crate::test_helpers::*;
+use chrono::TimeDelta;
+use csv::StringRecord;
+
+fn top_gaps_settings(num_top_time_gaps: usize) -> ExecutionSettings {
+    ExecutionSettings {
+        num_top_time_gaps,
+        ..Default::default()
+    }
+}
+
+fn process_times(processor: &mut LogRecordProcessor, times: &[NaiveDateTime]) {
+    for (index, time) in times.iter().enumerate() {
+        processor
+            .process_timestamp(&LogFileRecord::new(
+                index,
+                Some(*time),
+                StringRecord::from(vec!["test"]),
+            ))
+            .unwrap();
+    }
+    processor.finish_processing_timestamps();
+}
+
+#[test]
+fn keeps_the_largest_gaps_in_order() {
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &top_gaps_settings(2),
+        "Test".to_string(),
+        None,
+    );
+    process_times(
+        &mut processor,
+        &[
+            dt("2024-05-01 00:00:00"),
+            dt("2024-05-01 06:00:00"), // 6 hour gap
+            dt("2024-05-01 06:00:10"),
+            dt("2024-05-01 08:00:10"), // 2 hour gap
+            dt("2024-05-01 08:00:20"),
+            dt("2024-05-01 15:00:20"), // 7 hour gap
+        ],
+    );
+
+    assert_eq!(
+        processor.get_largest_time_gaps(),
+        vec![
+            IndexedTimeGap {
+                time_gap: TimeGap::new(dt("2024-05-01 08:00:20"), dt("2024-05-01 15:00:20")),
+                beginning_index: 4,
+                end_index: 5,
+            },
+            IndexedTimeGap {
+                time_gap: TimeGap::new(dt("2024-05-01 00:00:00"), dt("2024-05-01 06:00:00")),
+                beginning_index: 0,
+                end_index: 1,
+            },
+        ]
+    );
+}
+
+#[test]
+fn descending_gap_indexes_follow_the_timestamps() {
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Descending)),
+        &top_gaps_settings(1),
+        "Test".to_string(),
+        None,
+    );
+    process_times(
+        &mut processor,
+        &[
+            dt("2024-05-01 12:00:00"),
+            dt("2024-05-01 11:59:00"),
+            dt("2024-05-01 06:00:00"),
+        ],
+    );
+
+    let largest_time_gaps = processor.get_largest_time_gaps();
+    assert_eq!(largest_time_gaps.len(), 1);
+    assert_eq!(
+        largest_time_gaps[0].time_gap.beginning_time,
+        dt("2024-05-01 06:00:00")
+    );
+    assert_eq!(largest_time_gaps[0].beginning_index, 2);
+    assert_eq!(largest_time_gaps[0].end_index, 1);
+}
+
+#[test]
+fn tolerant_mode_gap_indexes_follow_time_order() {
+    let settings = ExecutionSettings {
+        num_top_time_gaps: 1,
+        out_of_order_tolerance: Some(OutOfOrderTolerance {
+            jitter_window: TimeDelta::seconds(30),
+            max_out_of_order_records: 10,
+        }),
+        ..Default::default()
+    };
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &settings,
+        "Test".to_string(),
+        None,
+    );
+    process_times(
+        &mut processor,
+        &[
+            dt("2024-05-01 12:00:00"),
+            dt("2024-05-01 12:00:20"),
+            dt("2024-05-01 12:00:05"),
+            dt("2024-05-01 12:00:10"),
+        ],
+    );
+
+    // In time order the records are 0, 2, 3, 1 so the largest gap is between index 3 and index 1
+    assert_eq!(
+        processor.get_largest_time_gaps(),
+        vec![IndexedTimeGap {
+            time_gap: TimeGap::new(dt("2024-05-01 12:00:10"), dt("2024-05-01 12:00:20")),
+            beginning_index: 3,
+            end_index: 1,
+        }]
+    );
+}
+
+#[test]
+fn counts_gaps_above_the_alert_threshold() {
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &top_gaps_settings(5),
+        "Test".to_string(),
+        None,
+    );
+    let start = dt("2024-05-01 00:00:00");
+    let mut times: Vec<NaiveDateTime> = (0..300).map(|i| start + TimeDelta::seconds(i)).collect();
+    let last = *times.last().unwrap();
+    times.push(last + TimeDelta::hours(6));
+
+    process_times(&mut processor, &times);
+
+    assert_eq!(processor.get_largest_time_gaps().len(), 5);
+    assert_eq!(processor.approx_num_time_gaps_above_threshold, 1);
+}
+
+fn one_second_apart_with_longer_gaps_at_the_end(longer_gaps: &[TimeDelta]) -> Vec<NaiveDateTime> {
+    let start = dt("2024-05-01 00:00:00");
+    let mut times: Vec<NaiveDateTime> = (0..1000).map(|i| start + TimeDelta::seconds(i)).collect();
+    for longer_gap in longer_gaps {
+        let last = *times.last().unwrap();
+        times.push(last + *longer_gap);
+    }
+    times
+}
+
+#[test]
+fn counts_gaps_above_the_alert_threshold_that_were_not_kept() {
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &top_gaps_settings(1),
+        "Test".to_string(),
+        None,
+    );
+    let times = one_second_apart_with_longer_gaps_at_the_end(&[
+        TimeDelta::hours(6),
+        TimeDelta::hours(5),
+        TimeDelta::hours(4),
+    ]);
+
+    process_times(&mut processor, &times);
+
+    assert_eq!(processor.get_largest_time_gaps().len(), 1);
+    assert_eq!(processor.approx_num_time_gaps_above_threshold, 3);
+}
+
+#[test]
+fn counts_gaps_above_the_percentile_threshold_with_the_percentile_method() {
+    let longer_gaps = [
+        TimeDelta::seconds(20),
+        TimeDelta::seconds(20),
+        TimeDelta::minutes(5),
+    ];
+    let mut std_processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &top_gaps_settings(5),
+        "Test".to_string(),
+        None,
+    );
+    let mut percentile_processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &ExecutionSettings {
+            time_gap_alert_method: TimeGapAlertMethod::Percentile,
+            ..top_gaps_settings(5)
+        },
+        "Test".to_string(),
+        None,
+    );
+    let times = one_second_apart_with_longer_gaps_at_the_end(&longer_gaps);
+
+    process_times(&mut std_processor, &times);
+    process_times(&mut percentile_processor, &times);
+
+    // The 20 second gaps are more than 10 times the 99th percentile, but not 10 standard deviations out
+    assert_eq!(std_processor.approx_num_time_gaps_above_threshold, 1);
+    assert_eq!(percentile_processor.approx_num_time_gaps_above_threshold, 3);
+}
+
+#[test]
+fn no_gaps_are_kept_when_top_gaps_is_zero() {
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &top_gaps_settings(0),
+        "Test".to_string(),
+        None,
+    );
+    process_times(
+        &mut processor,
+        &[dt("2024-05-01 00:00:00"), dt("2024-05-01 06:00:00")],
+    );
+
+    assert!(processor.get_largest_time_gaps().is_empty());
+    assert!(processor.largest_time_gap.is_some());
+}
+
+#[test]
+fn time_gap_percentiles_are_not_pulled_up_by_one_large_gap() {
+    let mut processor = LogRecordProcessor::new(
+        &build_fake_timestamp_hit_from_direction(Some(TimeDirection::Ascending)),
+        &top_gaps_settings(1),
+        "Test".to_string(),
+        None,
+    );
+    let start = dt("2024-05-01 00:00:00");
+    let mut times: Vec<NaiveDateTime> = (0..1000).map(|i| start + TimeDelta::seconds(i)).collect();
+    let last = *times.last().unwrap();
+    times.push(last + TimeDelta::hours(6));
+
+    process_times(&mut processor, &times);
+
+    let (mean, _) = processor.get_mean_and_variance();
+    let median = processor.time_gap_sketch.get_percentile(50.0).unwrap();
+    assert!(mean > 20_000.0); // Over 20 seconds because of the one gap
+    assert!((median - 1000.0).abs() < 10.0);
+    assert!((processor.time_gap_sketch.get_percentile(99.0).unwrap() - 1000.0).abs() < 10.0);
+    assert_eq!(processor.time_gap_sketch.count(), 1000);
+}
@@ -39,8 +39,10 @@ pub fn build_fake_timestamp_hit_from_direction(
     Some(IdentifiedTimeInformation {
         column_name: None,
         column_index: None,
+        composite_column_indices: Vec::new(),
         regex_info: fake_regex_info, // Assumes DateRegex implements Default
         direction: direction,
+        assumed_timezone: None,
     })
 }
 pub fn dt(s: &str) -> NaiveDateTime {
@@ -0,0 +1,236 @@
+use crate::basic_objects::VolumeWindow;
+use crate::errors::*;
+use crate::helpers::write_rows_to_file;
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+static ROLLING_BASELINE_WINDOWS: usize = 24; // How many windows before the current one the baseline is taken from
+static MIN_BASELINE_WINDOWS: usize = 6; // Windows needed before the baseline is trusted
+static VOLUME_ANOMALY_THRESHOLD: f64 = 5.0; // Robust z-score a window has to be beyond to be flagged
+static MAD_TO_STD: f64 = 1.4826; // Scales the median absolute deviation to match a standard deviation for normal data
+static MAX_VOLUME_WINDOWS: i64 = 1_000_000; // Keeps a file spanning decades in minute windows from filling memory with empty windows
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VolumeAnomaly {
+    Drop,
+    Spike,
+}
+
+/// One window of the series, with the baseline it was compared to
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeWindowCount {
+    pub window_start: NaiveDateTime,
+    pub count: usize,
+    pub baseline_median: Option<f64>,
+    pub robust_z_score: Option<f64>,
+    pub anomaly: Option<VolumeAnomaly>,
+}
+
+/// Number of records in each window, kept by window start so descending and slightly out of order files work the same
+#[derive(Debug, Default)]
+pub struct VolumeSeries {
+    window: VolumeWindow,
+    counts_by_window_start: BTreeMap<NaiveDateTime, usize>,
+}
+
+impl VolumeSeries {
+    pub fn new(window: VolumeWindow) -> Self {
+        Self {
+            window,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_timestamp(&mut self, timestamp: NaiveDateTime) {
+        *self
+            .counts_by_window_start
+            .entry(self.window.get_window_start(timestamp))
+            .or_insert(0) += 1;
+    }
+
+    /// Every window from the first to the last, including the empty ones. Each window is compared to the median of
+    /// the windows before it, scaled by their median absolute deviation so a few outliers don't move the baseline.
+    /// The first and last windows are only partly covered by the log so they are never flagged
+    pub fn get_window_counts(&self) -> Vec<VolumeWindowCount> {
+        let (Some(first_window_start), Some(last_window_start)) = (
+            self.counts_by_window_start.keys().next(),
+            self.counts_by_window_start.keys().next_back(),
+        ) else {
+            return Vec::new();
+        };
+        if (*last_window_start - *first_window_start).num_seconds()
+            / self.window.get_duration().num_seconds()
+            > MAX_VOLUME_WINDOWS
+        {
+            return Vec::new();
+        }
+        let mut window_counts: Vec<VolumeWindowCount> = Vec::new();
+        let mut window_start = *first_window_start;
+        while window_start <= *last_window_start {
+            let count = self
+                .counts_by_window_start
+                .get(&window_start)
+                .copied()
+                .unwrap_or(0);
+            let baseline_counts: Vec<f64> = window_counts
+                .iter()
+                .rev()
+                .take(ROLLING_BASELINE_WINDOWS)
+                .filter(|window_count| window_count.window_start != *first_window_start)
+                .map(|window_count| window_count.count as f64)
+                .collect();
+            let mut window_count = VolumeWindowCount {
+                window_start,
+                count,
+                baseline_median: None,
+                robust_z_score: None,
+                anomaly: None,
+            };
+            if baseline_counts.len() >= MIN_BASELINE_WINDOWS {
+                let baseline_median = get_median(&baseline_counts);
+                let absolute_deviations: Vec<f64> = baseline_counts
+                    .iter()
+                    .map(|baseline_count| (baseline_count - baseline_median).abs())
+                    .collect();
+                // A perfectly steady baseline has no deviation, so allow at least one record of it
+                let scale = (get_median(&absolute_deviations) * MAD_TO_STD).max(1.0);
+                let robust_z_score = (count as f64 - baseline_median) / scale;
+                window_count.baseline_median = Some(baseline_median);
+                window_count.robust_z_score = Some(robust_z_score);
+                if window_start != *last_window_start {
+                    if robust_z_score < -VOLUME_ANOMALY_THRESHOLD {
+                        window_count.anomaly = Some(VolumeAnomaly::Drop);
+                    } else if robust_z_score > VOLUME_ANOMALY_THRESHOLD {
+                        window_count.anomaly = Some(VolumeAnomaly::Spike);
+                    }
+                }
+            }
+            window_counts.push(window_count);
+            window_start += self.window.get_duration();
+        }
+        window_counts
+    }
+
+    pub fn write_to_file(
+        &self,
+        output_dir: &Path,
+        file_name: &str,
+        window_counts: &[VolumeWindowCount],
+    ) -> Result<()> {
+        let output_file = output_dir.join(format!("Volume/{}_VOLUME.csv", file_name));
+        write_rows_to_file(
+            &output_file,
+            &[
+                "Window Start",
+                "Record Count",
+                "Baseline Median",
+                "Robust Z-Score",
+                "Anomaly",
+            ],
+            window_counts.iter().map(|window_count| {
+                [
+                    window_count
+                        .window_start
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string(),
+                    window_count.count.to_string(),
+                    window_count
+                        .baseline_median
+                        .map(|v| v.to_string())
+                        .unwrap_or_default(),
+                    window_count
+                        .robust_z_score
+                        .map(|v| format!("{:.2}", v))
+                        .unwrap_or_default(),
+                    match window_count.anomaly {
+                        Some(VolumeAnomaly::Drop) => "Drop".to_string(),
+                        Some(VolumeAnomaly::Spike) => "Spike".to_string(),
+                        None => String::new(),
+                    },
+                ]
+            }),
+        )
+    }
+}
+
+fn get_median(values: &[f64]) -> f64 {
+    let mut sorted_values = values.to_vec();
+    sorted_values.sort_by(|a, b| a.total_cmp(b));
+    let middle = sorted_values.len() / 2;
+    match sorted_values.len() % 2 {
+        0 => (sorted_values[middle - 1] + sorted_values[middle]) / 2.0,
+        _ => sorted_values[middle],
+    }
+}
+
+#[cfg(test)]
+mod volume_series_tests {
+    use super::*;
+    use crate::test_helpers::dt;
+    use chrono::TimeDelta;
+
+    /// Adds the given number of records spread across each hour, starting at midnight
+    fn build_hourly_series(counts_per_hour: &[usize]) -> VolumeSeries {
+        let mut volume_series = VolumeSeries::new(VolumeWindow::Hour);
+        let start = dt("2024-05-01 00:00:00");
+        for (hour, count) in counts_per_hour.iter().enumerate() {
+            for i in 0..*count {
+                volume_series.add_timestamp(
+                    start + TimeDelta::hours(hour as i64) + TimeDelta::seconds(i as i64),
+                );
+            }
+        }
+        volume_series
+    }
+
+    fn get_anomalies(volume_series: &VolumeSeries) -> Vec<(usize, VolumeAnomaly)> {
+        volume_series
+            .get_window_counts()
+            .iter()
+            .enumerate()
+            .filter_map(|(hour, window_count)| window_count.anomaly.map(|anomaly| (hour, anomaly)))
+            .collect()
+    }
+
+    #[test]
+    fn flags_drop_to_a_fraction_of_the_normal_rate() {
+        let mut counts = vec![100, 98, 103, 101, 99, 97, 102, 100, 104, 99];
+        counts.extend([1, 100, 101]);
+        assert_eq!(
+            get_anomalies(&build_hourly_series(&counts)),
+            vec![(10, VolumeAnomaly::Drop)]
+        );
+    }
+
+    #[test]
+    fn flags_spike() {
+        let counts = vec![50, 51, 49, 50, 52, 48, 50, 51, 500, 50, 49];
+        assert_eq!(
+            get_anomalies(&build_hourly_series(&counts)),
+            vec![(8, VolumeAnomaly::Spike)]
+        );
+    }
+
+    #[test]
+    fn empty_windows_are_in_the_series() {
+        let counts = vec![10, 10, 10, 10, 10, 10, 10, 10, 0, 10, 10];
+        let volume_series = build_hourly_series(&counts);
+        let window_counts = volume_series.get_window_counts();
+        assert_eq!(window_counts.len(), counts.len());
+        assert_eq!(window_counts[8].count, 0);
+        assert_eq!(window_counts[8].anomaly, Some(VolumeAnomaly::Drop));
+    }
+
+    #[test]
+    fn steady_noisy_log_is_not_flagged() {
+        let counts = vec![20, 25, 18, 22, 30, 19, 24, 21, 27, 17, 23, 26, 20];
+        assert!(get_anomalies(&build_hourly_series(&counts)).is_empty());
+    }
+
+    #[test]
+    fn last_partial_window_is_not_flagged() {
+        let counts = vec![100, 100, 100, 100, 100, 100, 100, 100, 3];
+        assert!(get_anomalies(&build_hourly_series(&counts)).is_empty());
+    }
+}
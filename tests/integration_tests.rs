@@ -1,11 +1,18 @@
-use chrono::NaiveDateTime;
+use chrono::{FixedOffset, NaiveDateTime, TimeDelta};
 use lava::{
-    alerts::Alert,
-    basic_objects::{ExecutionSettings, LogFile, LogType},
-    helpers::print_pretty_alerts_and_write_to_alerts_output_file,
+    alerts::{Alert, AlertKind, AlertLevel, AlertType},
+    basic_objects::{
+        ArchiveMember, ArchiveType, AssumedTimezone, DupeDetectionMode, ExecutionSettings,
+        HashAlgorithm, LogFile, LogType, NearDupeSettings, OutOfOrderTolerance,
+    },
+    helpers::{
+        convert_vector_of_processed_log_files_into_one_for_multipart,
+        print_pretty_alerts_and_write_to_alerts_output_file,
+    },
     process_file,
 };
 use std::fs;
+use std::io::Write;
 use tempfile::NamedTempFile;
 struct TempInputFile {
     log_file_object: LogFile,
@@ -14,6 +21,10 @@ struct TempInputFile {
 
 impl TempInputFile {
     pub fn new(file_type: LogType, content: &str) -> Self {
+        Self::new_from_bytes(file_type, content.as_bytes())
+    }
+
+    pub fn new_from_bytes(file_type: LogType, content: &[u8]) -> Self {
         let temp_file = NamedTempFile::new().expect("failed to create temp file");
         let file_path = temp_file.path();
         fs::write(file_path, content).expect("Failed to write content to temp file.");
@@ -21,6 +32,8 @@ impl TempInputFile {
             log_file_object: LogFile {
                 log_type: file_type,
                 file_path: file_path.to_path_buf(),
+                type_detection: None,
+                archive_member: None,
             },
             temp_file: temp_file,
         }
@@ -708,3 +721,511 @@ fn integration_test_unstructured_junk_at_top() {
     );
     temp_log_file.delete_temp_file();
 }
+
+#[test]
+fn integration_test_gzipped_csv() {
+    let data = "\
+    id,name,date\n\
+    1,John,2025-05-09 10:00:00\n\
+    2,Jane,2025-05-10 11:00:00\n\
+    2,Jane,2025-05-10 11:00:00\n\
+    4,James,2025-06-01 13:00:00\n";
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data.as_bytes()).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let temp_log_file = TempInputFile::new_from_bytes(LogType::Csv, &compressed);
+    let log_file = temp_log_file.get_log_file_object();
+    let settings = ExecutionSettings::create_integration_test_object(None, false);
+
+    let output = process_file(log_file, &settings);
+    let processed = output.expect("Failed to get Proceesed Log File");
+    assert_eq!(0, processed.errors.len());
+    assert_eq!(Some("gzip".to_string()), processed.compression);
+    assert_eq!(4, processed.total_num_records);
+    assert_eq!(1, processed.num_dupes.unwrap());
+    assert_ne!(processed.sha256hash, processed.decompressed_sha256hash);
+    assert!(processed.decompressed_sha256hash.is_some());
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2025-06-01 13:00:00"),
+        processed.max_timestamp.unwrap()
+    );
+    temp_log_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_hashes_computed_while_streaming() {
+    use sha2::Digest;
+    let data = "\
+    id,name,date\n\
+    1,John,2025-05-09 10:00:00\n\
+    2,Jane,2025-05-10 11:00:00\n";
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data.as_bytes()).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let temp_log_file = TempInputFile::new_from_bytes(LogType::Csv, &compressed);
+    let log_file = temp_log_file.get_log_file_object();
+    let mut settings = ExecutionSettings::create_integration_test_object(None, false);
+    settings.hash_algorithms = vec![
+        HashAlgorithm::Md5,
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Sha256,
+    ];
+
+    let output = process_file(log_file, &settings);
+    let processed = output.expect("Failed to get Proceesed Log File");
+    assert_eq!(0, processed.errors.len());
+    assert_eq!(
+        Some(format!("{:x}", md5::Md5::digest(&compressed))),
+        processed.md5hash
+    );
+    assert_eq!(
+        Some(format!("{:x}", sha1::Sha1::digest(&compressed))),
+        processed.sha1hash
+    );
+    assert_eq!(
+        Some(format!("{:x}", sha2::Sha256::digest(&compressed))),
+        processed.sha256hash
+    );
+    assert_eq!(
+        Some(format!("{:x}", sha2::Sha256::digest(data.as_bytes()))),
+        processed.decompressed_sha256hash
+    );
+
+    settings.hash_algorithms = vec![HashAlgorithm::Md5];
+    let processed = process_file(log_file, &settings).expect("Failed to get Proceesed Log File");
+    assert!(processed.md5hash.is_some());
+    assert_eq!(None, processed.sha256hash);
+    assert_eq!(None, processed.decompressed_sha256hash);
+    temp_log_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_near_dupes_ignore_sequence_ids() {
+    let mut data = String::from("seq,user,action,date\n");
+    for i in 0..12 {
+        // Every record is sent twice with a new sequence ID
+        data.push_str(&format!(
+            "{},user{},login,2025-05-09 10:{:02}:00\n",
+            1000 + i,
+            i / 2,
+            i / 2
+        ));
+    }
+    let temp_log_file = TempInputFile::new_from_bytes(LogType::Csv, data.as_bytes());
+    let log_file = temp_log_file.get_log_file_object();
+    let mut settings = ExecutionSettings::create_integration_test_object(None, false);
+    settings.near_dupe_settings = Some(NearDupeSettings::default());
+
+    let processed = process_file(log_file, &settings).expect("Failed to get Proceesed Log File");
+    assert_eq!(0, processed.errors.len());
+    assert_eq!(0, processed.num_dupes.unwrap());
+    assert_eq!(Some(6), processed.num_near_dupe_clusters);
+    assert_eq!(vec!["seq".to_string()], processed.near_dupe_dropped_fields);
+
+    settings.near_dupe_settings = Some(NearDupeSettings {
+        ignored_fields: vec!["user".to_string()],
+    });
+    let processed = process_file(log_file, &settings).expect("Failed to get Proceesed Log File");
+    assert_eq!(Some(6), processed.num_near_dupe_clusters);
+    assert_eq!(
+        vec!["user".to_string(), "seq".to_string()],
+        processed.near_dupe_dropped_fields
+    );
+
+    settings.near_dupe_settings = None;
+    let processed = process_file(log_file, &settings).expect("Failed to get Proceesed Log File");
+    assert_eq!(None, processed.num_near_dupe_clusters);
+    temp_log_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_spilled_and_approximate_dupe_counts() {
+    let mut data = String::from("id,name,date\n");
+    for i in 0..50 {
+        // Every tenth record repeats one from far earlier in the file
+        let id = if i % 10 == 9 { i / 10 } else { i };
+        data.push_str(&format!("{},name{},2025-05-09 10:00:00\n", id, id));
+    }
+    let temp_log_file = TempInputFile::new_from_bytes(LogType::Csv, data.as_bytes());
+    let log_file = temp_log_file.get_log_file_object();
+    let mut settings = ExecutionSettings::create_integration_test_object(None, false);
+
+    let in_memory = process_file(log_file, &settings).expect("Failed to get Proceesed Log File");
+    assert_eq!(5, in_memory.num_dupes.unwrap());
+    assert_eq!(Some("Exact".to_string()), in_memory.dupe_detection_mode);
+
+    // A budget this small spills the tracker after every record
+    settings.dupe_detection_mode = DupeDetectionMode::Spill {
+        memory_budget_bytes: 1,
+    };
+    let spilled = process_file(log_file, &settings).expect("Failed to get Proceesed Log File");
+    assert_eq!(0, spilled.errors.len());
+    assert_eq!(5, spilled.num_dupes.unwrap());
+    assert_eq!(
+        Some("Exact, merged from 50 sorted runs spilled to disk".to_string()),
+        spilled.dupe_detection_mode
+    );
+
+    // Every repeat is only found in the merge, so they all go to the spilled repeats file with the hash on every row
+    let output_dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(output_dir.path().join("Duplicates")).unwrap();
+    settings.output_dir = output_dir.path().to_path_buf();
+    settings.actually_write_to_files = true;
+    process_file(log_file, &settings).expect("Failed to get Proceesed Log File");
+    let spilled_repeats_path = fs::read_dir(output_dir.path().join("Duplicates"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.to_string_lossy().ends_with("_SPILLED_REPEATS.csv"))
+        .expect("No spilled repeats file was written");
+    let spilled_repeats = fs::read_to_string(spilled_repeats_path).unwrap();
+    let mut lines = spilled_repeats.lines();
+    assert_eq!(
+        Some("Index of Repeat,Hash of Record,Index of First Occurrence"),
+        lines.next()
+    );
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    assert_eq!(5, rows.len());
+    assert_eq!(
+        vec!["9", "19", "29", "39", "49"],
+        rows.iter().map(|row| row[0]).collect::<Vec<&str>>()
+    );
+    assert_eq!(
+        vec!["0", "1", "2", "3", "4"],
+        rows.iter().map(|row| row[2]).collect::<Vec<&str>>()
+    );
+    assert!(rows.iter().all(|row| !row[1].is_empty()));
+    settings.actually_write_to_files = false;
+
+    settings.dupe_detection_mode = DupeDetectionMode::Approximate {
+        false_positive_rate: 0.001,
+    };
+    let approximate = process_file(log_file, &settings).expect("Failed to get Proceesed Log File");
+    assert_eq!(5, approximate.num_dupes.unwrap());
+    assert_eq!(
+        Some("Approximate, 0.1% false positive rate".to_string()),
+        approximate.dupe_detection_mode
+    );
+    temp_log_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_csv_inside_zip() {
+    let data = "\
+    id,name,date\n\
+    1,John,2025-05-09 10:00:00\n\
+    2,Jane,2025-05-10 11:00:00\n\
+    4,James,2025-06-01 13:00:00\n";
+    let temp_file = NamedTempFile::new().expect("failed to create temp file");
+    let mut writer = zip::ZipWriter::new(fs::File::create(temp_file.path()).unwrap());
+    writer
+        .start_file(
+            "exports/users.csv",
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+    writer.write_all(data.as_bytes()).unwrap();
+    writer.finish().unwrap();
+
+    let log_file = LogFile {
+        log_type: LogType::Csv,
+        file_path: temp_file.path().to_path_buf(),
+        type_detection: None,
+        archive_member: Some(ArchiveMember {
+            archive_type: ArchiveType::Zip,
+            inner_path: "exports/users.csv".to_string(),
+            size: data.len() as u64,
+            modified_time: None,
+        }),
+    };
+    let settings = ExecutionSettings::create_integration_test_object(None, false);
+
+    let output = process_file(&log_file, &settings);
+    let processed = output.expect("Failed to get Proceesed Log File");
+    assert_eq!(0, processed.errors.len());
+    assert_eq!(Some("users.csv".to_string()), processed.filename);
+    assert_eq!(
+        Some(format!(
+            "{}!/exports/users.csv",
+            temp_file.path().to_string_lossy()
+        )),
+        processed.file_path
+    );
+    assert_eq!(3, processed.total_num_records);
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2025-06-01 13:00:00"),
+        processed.max_timestamp.unwrap()
+    );
+}
+
+#[test]
+fn integration_test_syslog_year_rollover() {
+    let data = "\
+    Dec 31 23:58:00 web01 sshd[812]: Accepted publickey for REDACTED\n\
+    Dec 31 23:59:00 web01 CRON[900]: (root) CMD (run-parts /etc/cron.hourly)\n\
+    Jan  1 00:01:00 web01 systemd[1]: Started Daily apt download activities.\n";
+    let temp_log_file = TempInputFile::new(LogType::Syslog, data);
+    let log_file = temp_log_file.get_log_file_object();
+    let mut settings = ExecutionSettings::create_integration_test_object(None, false);
+    settings.syslog_year = Some(2024);
+
+    let output = process_file(log_file, &settings);
+    let processed = output.expect("Failed to get Proceesed Log File");
+    assert_eq!(0, processed.errors.len());
+    assert_eq!(3, processed.timestamp_num_records);
+    assert_eq!(1, processed.num_redactions.unwrap());
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2024-12-31 23:58:00"),
+        processed.min_timestamp.unwrap()
+    );
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2025-01-01 00:01:00"),
+        processed.max_timestamp.unwrap()
+    );
+    temp_log_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_w3c_with_server_restart() {
+    let data = "\
+    #Software: Microsoft Internet Information Services 10.0\n\
+    #Version: 1.0\n\
+    #Date: 2024-01-01 00:00:00\n\
+    #Fields: date time s-ip cs-method cs-uri-stem\n\
+    2024-01-01 00:00:01 10.0.0.1 GET /index.html\n\
+    2024-01-01 00:00:05 10.0.0.1 GET /about.html\n\
+    #Software: Microsoft Internet Information Services 10.0\n\
+    #Version: 1.0\n\
+    #Date: 2024-01-01 06:00:00\n\
+    #Fields: date time s-ip cs-method cs-uri-stem sc-status\n\
+    2024-01-01 06:00:01 10.0.0.1 GET /index.html 200\n\
+    2024-01-01 06:00:01 10.0.0.1 GET /index.html 200\n";
+    let temp_log_file = TempInputFile::new(LogType::W3c, data);
+    let log_file = temp_log_file.get_log_file_object();
+    let settings = ExecutionSettings::create_integration_test_object(None, false);
+
+    let output = process_file(log_file, &settings);
+    let processed = output.expect("Failed to get Proceesed Log File");
+    assert_eq!(0, processed.errors.len());
+    assert_eq!(Some("date+time".to_string()), processed.time_header);
+    assert_eq!(4, processed.total_num_records);
+    assert_eq!(1, processed.num_dupes.unwrap());
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2024-01-01 00:00:01"),
+        processed.min_timestamp.unwrap()
+    );
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2024-01-01 06:00:01"),
+        processed.max_timestamp.unwrap()
+    );
+    temp_log_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_csv_with_split_date_and_time_columns() {
+    let data = "\
+    id,Date,Time,message\n\
+    1,2025-05-09,10:00:00,logon\n\
+    2,2025-05-10,11:00:00,logoff\n\
+    3,2025-06-01,13:00:00,logon\n";
+    let temp_log_file = TempInputFile::new(LogType::Csv, data);
+    let log_file = temp_log_file.get_log_file_object();
+    let settings = ExecutionSettings::create_integration_test_object(None, false);
+
+    let output = process_file(log_file, &settings);
+    let processed = output.expect("Failed to get Proceesed Log File");
+    assert_eq!(0, processed.errors.len());
+    assert_eq!(Some("Date+Time".to_string()), processed.time_header);
+    assert_eq!(3, processed.timestamp_num_records);
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2025-06-01 13:00:00"),
+        processed.max_timestamp.unwrap()
+    );
+    temp_log_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_multipart_across_timezones_does_not_overlap() {
+    // Both files are the same hour of the day in local time, but the second one picks up where the first left off in UTC
+    let new_york_data = "\
+    id,date\n\
+    1,2025-05-09T08:00:00-04:00\n\
+    2,2025-05-09T09:00:00-04:00\n";
+    let berlin_data = "\
+    id,date\n\
+    1,2025-05-09T16:00:00+02:00\n\
+    2,2025-05-09T17:00:00+02:00\n";
+    let new_york_file = TempInputFile::new(LogType::Csv, new_york_data);
+    let berlin_file = TempInputFile::new(LogType::Csv, berlin_data);
+    let mut settings = ExecutionSettings::create_integration_test_object(None, false);
+    settings.multipart_mode = true;
+
+    let results = vec![
+        process_file(new_york_file.get_log_file_object(), &settings).unwrap(),
+        process_file(berlin_file.get_log_file_object(), &settings).unwrap(),
+    ];
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2025-05-09 12:00:00"),
+        results[0].min_timestamp.unwrap()
+    );
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2025-05-09 14:00:00"),
+        results[1].min_timestamp.unwrap()
+    );
+
+    let combined =
+        convert_vector_of_processed_log_files_into_one_for_multipart(&results, &settings);
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2025-05-09 15:00:00"),
+        combined.max_timestamp.unwrap()
+    );
+    assert!(
+        !combined
+            .alerts
+            .iter()
+            .any(|alert| alert.alert_type.kind() == AlertKind::MultipartOverlap)
+    );
+    new_york_file.delete_temp_file();
+    berlin_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_assumed_timezone_for_naive_timestamps() {
+    let data = "\
+    id,date\n\
+    1,2025-05-09 22:00:00\n\
+    2,2025-05-09 23:30:00\n";
+    let temp_log_file = TempInputFile::new(LogType::Csv, data);
+    let mut settings = ExecutionSettings::create_integration_test_object(None, false);
+    settings.assumed_timezone = Some(AssumedTimezone::Offset(
+        FixedOffset::west_opt(5 * 3600).unwrap(),
+    ));
+
+    let processed = process_file(temp_log_file.get_log_file_object(), &settings).unwrap();
+    assert_eq!(0, processed.errors.len());
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2025-05-10 03:00:00"),
+        processed.min_timestamp.unwrap()
+    );
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2025-05-10 04:30:00"),
+        processed.max_timestamp.unwrap()
+    );
+    temp_log_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_named_timezone_fall_back_is_annotated() {
+    let data = "\
+    id,date\n\
+    1,2024-11-03 00:45:00\n\
+    2,2024-11-03 01:30:00\n\
+    3,2024-11-03 01:50:00\n\
+    4,2024-11-03 01:05:00\n\
+    5,2024-11-03 01:35:00\n\
+    6,2024-11-03 02:10:00\n";
+    let temp_log_file = TempInputFile::new(LogType::Csv, data);
+    let mut settings = ExecutionSettings::create_integration_test_object(None, false);
+    settings.assumed_timezone = AssumedTimezone::parse("America/New_York");
+
+    let processed = process_file(temp_log_file.get_log_file_object(), &settings).unwrap();
+    assert_eq!(0, processed.errors.len());
+    assert_eq!(6, processed.timestamp_num_records);
+    assert_eq!(
+        vec!["Clocks fell back from EDT to EST at index 3".to_string()],
+        processed.dst_transitions
+    );
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2024-11-03 07:10:00"),
+        processed.max_timestamp.unwrap()
+    );
+    temp_log_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_tolerant_mode_keeps_stats_for_jittered_file() {
+    let data = "\
+    id,date\n\
+    1,2025-05-09 10:00:00\n\
+    2,2025-05-09 10:00:30\n\
+    3,2025-05-09 10:00:29\n\
+    4,2025-05-09 10:01:00\n";
+    let temp_log_file = TempInputFile::new(LogType::Csv, data);
+    let mut settings = ExecutionSettings::create_integration_test_object(None, false);
+    settings.out_of_order_tolerance = Some(OutOfOrderTolerance {
+        jitter_window: TimeDelta::seconds(5),
+        max_out_of_order_records: 10,
+    });
+
+    let processed = process_file(temp_log_file.get_log_file_object(), &settings).unwrap();
+    assert_eq!(0, processed.errors.len());
+    assert_eq!(4, processed.timestamp_num_records);
+    assert_eq!(Some(1), processed.num_out_of_order_records);
+    assert_eq!(
+        get_time_from_hardcoded_time_format("2025-05-09 10:01:00"),
+        processed.max_timestamp.unwrap()
+    );
+    assert!(processed.alerts.contains(&Alert::new(
+        AlertLevel::Low,
+        AlertType::OutOfOrderRecords(1, TimeDelta::seconds(1))
+    )));
+    temp_log_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_top_time_gaps_count_is_reported() {
+    let data = "\
+    id,date\n\
+    1,2025-05-09 10:00:00\n\
+    2,2025-05-09 10:00:01\n\
+    3,2025-05-09 16:00:01\n\
+    4,2025-05-09 16:00:02\n";
+    let temp_log_file = TempInputFile::new(LogType::Csv, data);
+    let mut settings = ExecutionSettings::create_integration_test_object(None, false);
+    settings.num_top_time_gaps = 3;
+
+    let processed = process_file(temp_log_file.get_log_file_object(), &settings).unwrap();
+    assert_eq!(0, processed.errors.len());
+    // Three gaps aren't enough for any one of them to be 10 standard deviations out
+    assert_eq!(Some(0), processed.approx_num_time_gaps_above_threshold);
+    temp_log_file.delete_temp_file();
+}
+
+#[test]
+fn integration_test_alert_config_scoped_to_log_type() {
+    let data = "\
+    id,name,date\n\
+    1,John,2025-05-09 10:00:00\n\
+    2,Jane,2025-05-10 11:00:00\n\
+    2,Jane,2025-05-10 11:00:00\n\
+    4,J*********s,2025-06-01 13:00:00\n";
+    let temp_log_file = TempInputFile::new(LogType::Csv, data);
+    let mut settings = ExecutionSettings::create_integration_test_object(None, false);
+    settings.alert_config = serde_yaml::from_str(
+        "
+overrides:
+  - log_type: csv
+    disabled: [RedactionEvents]
+  - file_glob: '*.evtx'
+    thresholds:
+      DupeEvents: {low: 5}
+",
+    )
+    .unwrap();
+
+    let processed = process_file(temp_log_file.get_log_file_object(), &settings).unwrap();
+    assert_eq!(Some(1), processed.num_redactions);
+    assert!(
+        processed
+            .alerts
+            .contains(&Alert::new(AlertLevel::Low, AlertType::DupeEvents))
+    );
+    assert!(
+        !processed
+            .alerts
+            .iter()
+            .any(|alert| alert.alert_type.kind() == AlertKind::RedactionEvents)
+    );
+    temp_log_file.delete_temp_file();
+}